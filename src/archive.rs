@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ArchiveFormat {
+    #[value(name = "tar.gz")]
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+}
+
+// Files under `dir` with their archive names, relative to `dir` and '/'-separated, in a stable order
+fn archive_entries(dir: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let mut entries = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir)?;
+        let name = relative.components().map(|part| part.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        entries.push((entry.into_path(), name));
+    }
+    Ok(entries)
+}
+
+// Pack the converted dataset into <output_dir>.tar.gz or .zip next to it, streaming each file
+// from disk. Entries sit under a top-level directory named like the output directory.
+pub fn write(output_dir: &Path, format: ArchiveFormat) -> Result<PathBuf> {
+    let dir_name = output_dir.file_name().and_then(|s| s.to_str()).context("Output directory has no name")?;
+    let archive_path = output_dir.with_file_name(format!("{}.{}", dir_name, format.extension()));
+    let file = File::create(&archive_path)
+        .with_context(|| format!("Failed to create archive: {}", archive_path.display()))?;
+
+    let entries = archive_entries(output_dir)?;
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            for (path, name) in &entries {
+                builder.append_path_with_name(path, format!("{}/{}", dir_name, name))
+                    .with_context(|| format!("Failed to archive {}", path.display()))?;
+            }
+            builder.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut writer = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+            for (path, name) in &entries {
+                writer.start_file(format!("{}/{}", dir_name, name), options)?;
+                let mut source = File::open(path).with_context(|| format!("Failed to archive {}", path.display()))?;
+                io::copy(&mut source, &mut writer)?;
+            }
+            writer.finish()?;
+        }
+    }
+
+    println!("Generated archive: {} ({} files)", archive_path.display(), entries.len());
+    Ok(archive_path)
+}
+
+pub fn remove_output(output_dir: &Path) -> Result<()> {
+    fs::remove_dir_all(output_dir)
+        .with_context(|| format!("Failed to remove output directory: {}", output_dir.display()))
+}
@@ -0,0 +1,170 @@
+use anyhow::{Context, Result};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// A single image to fetch; `index` identifies the image in the caller's list
+pub struct DownloadJob {
+    pub index: usize,
+    pub url: String,
+    pub dest: PathBuf,
+}
+
+pub fn is_url(value: &str) -> bool {
+    value.starts_with("http://") || value.starts_with("https://")
+}
+
+// Last path segment of a URL without query string or fragment, used as the local filename
+pub fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn download_one(client: &reqwest::blocking::Client, job: &DownloadJob, retries: u32) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        let result = client
+            .get(&job.url)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.bytes());
+
+        match result {
+            Ok(bytes) => {
+                // Write to a temporary file first so an interrupted run never leaves a truncated image behind
+                let tmp_path = job.dest.with_extension("part");
+                fs::write(&tmp_path, &bytes)
+                    .with_context(|| format!("Failed to write downloaded image: {}", tmp_path.display()))?;
+                fs::rename(&tmp_path, &job.dest)
+                    .with_context(|| format!("Failed to move downloaded image into place: {}", job.dest.display()))?;
+                return Ok(());
+            }
+            Err(_) if attempt < retries => {
+                attempt += 1;
+                thread::sleep(Duration::from_millis(500 * attempt as u64));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+// Download all jobs with at most `concurrency` requests in flight, retrying each up to `retries` times.
+// Failures are reported and skipped; the indices of successfully downloaded images are returned.
+pub fn download_images(jobs: &[DownloadJob], concurrency: usize, retries: u32) -> Result<HashSet<usize>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let pb = ProgressBar::new(jobs.len() as u64);
+    pb.set_style(
+        ProgressStyle::with_template(
+            "Downloading    [{elapsed_precise}] [{bar:40.yellow/blue}] {pos:>7}/{len:7} {msg}"
+        )?
+        .progress_chars("#>-")
+    );
+
+    let next_job = AtomicUsize::new(0);
+    let downloaded = Mutex::new(HashSet::new());
+    let failures = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let job_idx = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some(job) = jobs.get(job_idx) else {
+                        break;
+                    };
+
+                    pb.set_message(url_file_name(&job.url).to_string());
+                    match download_one(&client, job, retries) {
+                        Ok(()) => {
+                            downloaded.lock().unwrap().insert(job.index);
+                        }
+                        Err(err) => {
+                            failures.lock().unwrap().push(format!("{}: {}", job.url, err));
+                        }
+                    }
+                    pb.inc(1);
+                }
+            });
+        }
+    });
+
+    pb.finish_with_message("Downloads complete");
+
+    let failures = failures.into_inner().unwrap();
+    if !failures.is_empty() {
+        println!("Warning: {} image downloads failed", failures.len());
+        for failure in &failures {
+            println!("  {}", failure);
+        }
+    }
+
+    Ok(downloaded.into_inner().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // Serve `/<name>` for the given files and 404 for anything else; returns the base URL
+    fn serve(files: &'static [(&'static str, &'static [u8])]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let target = request_line.split_whitespace().nth(1).unwrap_or("");
+                let path = target.split('?').next().unwrap_or(target);
+                let (status, body): (&str, &[u8]) = match files.iter().find(|(name, _)| path == format!("/{}", name)) {
+                    Some((_, body)) => ("200 OK", body),
+                    None => ("404 Not Found", b""),
+                };
+                let head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+                let response = [head.as_bytes(), body].concat();
+                stream.write_all(&response).unwrap();
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn downloads_images_and_skips_failures() {
+        let base_url = serve(&[("a.jpg", b"first image"), ("b.jpg", b"second image")]);
+        let dir = tempfile::tempdir().unwrap();
+        let jobs: Vec<DownloadJob> = ["a.jpg", "missing.jpg", "b.jpg"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| DownloadJob { index, url: format!("{}/{}?size=full", base_url, name), dest: dir.path().join(name) })
+            .collect();
+
+        let downloaded = download_images(&jobs, 2, 0).unwrap();
+        assert_eq!(downloaded, HashSet::from([0, 2]));
+        assert_eq!(fs::read(dir.path().join("a.jpg")).unwrap(), b"first image");
+        assert_eq!(fs::read(dir.path().join("b.jpg")).unwrap(), b"second image");
+        assert!(!dir.path().join("missing.jpg").exists());
+        assert!(!dir.path().join("missing.part").exists());
+    }
+
+    #[test]
+    fn url_file_names_drop_query_and_fragment() {
+        assert!(is_url("https://example.com/a.jpg") && !is_url("images/a.jpg"));
+        assert_eq!(url_file_name("https://example.com/images/a.jpg?token=1#top"), "a.jpg");
+    }
+}
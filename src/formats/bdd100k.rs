@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+// Every BDD100K frame is 1280x720; the label files don't record it
+pub const BDD100K_IMAGE_SIZE: (u32, u32) = (1280, 720);
+
+#[derive(Debug, Deserialize)]
+struct Bdd100kBox {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+// Lanes and drivable areas carry poly2d instead of box2d and are skipped
+#[derive(Debug, Deserialize)]
+struct Bdd100kLabel {
+    category: String,
+    #[serde(default)]
+    box2d: Option<Bdd100kBox>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Bdd100kFrame {
+    name: String,
+    #[serde(default)]
+    labels: Option<Vec<Bdd100kLabel>>,
+}
+
+// Parse a BDD100K detection label file (a list of frames). Category strings are indexed into
+// classes. Only the fields needed for boxes are deserialized, which keeps 100k-frame files fast.
+pub fn parse(
+    content: &str,
+    labels: &mut LabelRegistry,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let frames: Vec<Bdd100kFrame> = serde_json::from_str(content)?;
+    let mut unified_images = Vec::with_capacity(frames.len());
+
+    for frame in frames {
+        let unified_annotations = frame.labels
+            .iter()
+            .flatten()
+            .filter_map(|label| {
+                let bbox = label.box2d.as_ref()?;
+                Some(UnifiedAnnotation {
+                    bbox: vec![bbox.x1, bbox.y1, bbox.x2, bbox.y2],
+                    category_id: labels.id_for(&label.category),
+                    category_name: Some(labels.name(&label.category).to_string()),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let (width, height) = image_size(&frame.name)
+            .with_context(|| format!("Failed to determine the size of {}", frame.name))?;
+        unified_images.push(UnifiedImage {
+            file_name: frame.name,
+            source_url: None,
+            height,
+            width,
+            annotations: unified_annotations,
+            ..Default::default()
+        });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct Bdd100kParser;
+
+impl FormatParser for Bdd100kParser {
+    fn name(&self) -> &'static str {
+        "bdd100k"
+    }
+
+    fn description(&self) -> &'static str {
+        "BDD100K labels"
+    }
+
+    // Reading 100k image headers is slow and BDD100K frames share one size, so only do it on request
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        let image_size = |file_name: &str| {
+            if ctx.read_image_size {
+                ctx.image_size(file_name)
+            } else {
+                Ok(BDD100K_IMAGE_SIZE)
+            }
+        };
+        parse(content, labels, &image_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputSource;
+    use std::path::Path;
+
+    #[test]
+    fn boxes_are_kept_and_frames_default_to_the_bdd100k_size() {
+        let frames = serde_json::json!([
+            {"name": "a.jpg", "labels": [
+                {"category": "car", "box2d": {"x1": 4, "y1": 4, "x2": 24, "y2": 14}},
+                {"category": "lane", "poly2d": [{"vertices": [[0, 0], [10, 10]], "types": "LL", "closed": false}]},
+                {"category": "person", "box2d": {"x1": 2, "y1": 3, "x2": 12, "y2": 9}},
+            ]},
+            {"name": "b.jpg"},
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let input = InputSource::open(dir.path()).unwrap();
+        let ctx = ParseContext { path: Path::new("labels.json"), file_name: "labels.json", input: &input, read_image_size: false };
+        let images = Bdd100kParser.parse(&frames.to_string(), &ctx, &mut LabelRegistry::default()).unwrap();
+
+        assert_eq!(images.len(), 2);
+        assert!(images.iter().all(|image| (image.width, image.height) == BDD100K_IMAGE_SIZE));
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [(0, vec![4.0, 4.0, 24.0, 14.0]), (1, vec![2.0, 3.0, 12.0, 9.0])]);
+        assert!(images[1].annotations.is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{CocoId, UnifiedAnnotation, UnifiedImage, split_image_url};
+
+#[derive(Debug, Deserialize)]
+struct PanopticCategory {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    isthing: u8,
+    #[serde(default)]
+    supercategory: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PanopticImage {
+    id: CocoId,
+    file_name: String,
+    height: u32,
+    width: u32,
+    #[serde(default)]
+    coco_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PanopticSegment {
+    id: u32,
+    category_id: u32,
+    #[serde(default)]
+    bbox: Option<Vec<f64>>, // [x, y, width, height]
+}
+
+#[derive(Debug, Deserialize)]
+struct PanopticAnnotation {
+    image_id: CocoId,
+    file_name: String, // Segment map PNG
+    segments_info: Vec<PanopticSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PanopticDataset {
+    images: Vec<PanopticImage>,
+    annotations: Vec<PanopticAnnotation>,
+    categories: Vec<PanopticCategory>,
+}
+
+// Pixel extents [x1, y1, x2, y2] of every segment id in a panoptic PNG, where id = R + 256 G + 256² B
+fn segment_extents(png_bytes: Vec<u8>) -> Result<HashMap<u32, [f64; 4]>> {
+    let mut decoder = png::Decoder::new(Cursor::new(png_bytes));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size().context("PNG is too large")?];
+    let info = reader.next_frame(&mut pixels)?;
+    let channels = info.color_type.samples();
+    if channels < 3 {
+        anyhow::bail!("Expected an RGB segment map, got {:?}", info.color_type);
+    }
+
+    let mut extents: HashMap<u32, [f64; 4]> = HashMap::new();
+    for (y, row) in pixels.chunks_exact(info.line_size).take(info.height as usize).enumerate() {
+        for (x, pixel) in row.chunks_exact(channels).take(info.width as usize).enumerate() {
+            let id = pixel[0] as u32 + 256 * pixel[1] as u32 + 256 * 256 * pixel[2] as u32;
+            if id == 0 {
+                continue;
+            }
+            let (x, y) = (x as f64, y as f64);
+            let extent = extents.entry(id).or_insert([x, y, x + 1.0, y + 1.0]);
+            extent[0] = extent[0].min(x);
+            extent[1] = extent[1].min(y);
+            extent[2] = extent[2].max(x + 1.0);
+            extent[3] = extent[3].max(y + 1.0);
+        }
+    }
+    Ok(extents)
+}
+
+// Segment maps sit in a directory named like the JSON file (panoptic_val2017.json → panoptic_val2017/);
+// otherwise they are searched for by file name
+fn find_segment_map(ctx: &ParseContext, file_name: &str) -> Option<PathBuf> {
+    let stem = Path::new(ctx.file_name).file_stem()?;
+    let beside = ctx.path.with_file_name(stem).join(file_name);
+    if ctx.input.contains(&beside) {
+        return Some(beside);
+    }
+    ctx.input.find_image(file_name)
+}
+
+// Parse a COCO panoptic JSON. Only `isthing` categories become classes; stuff segments are skipped.
+// Boxes come from each segment's bbox, or from the segment map PNG when a segment has none.
+pub fn parse(content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let dataset: PanopticDataset = serde_json::from_str(content)?;
+    let things: HashMap<u32, &PanopticCategory> = dataset.categories
+        .iter()
+        .filter(|category| category.isthing == 1)
+        .map(|category| (category.id, category))
+        .collect();
+    let annotations: HashMap<&CocoId, &PanopticAnnotation> =
+        dataset.annotations.iter().map(|annotation| (&annotation.image_id, annotation)).collect();
+
+    let mut unified_images = Vec::new();
+    for image in &dataset.images {
+        let mut unified_annotations = Vec::new();
+        if let Some(annotation) = annotations.get(&image.id) {
+            let segments: Vec<&PanopticSegment> = annotation.segments_info
+                .iter()
+                .filter(|segment| things.contains_key(&segment.category_id))
+                .collect();
+
+            let mut extents = HashMap::new();
+            if segments.iter().any(|segment| segment.bbox.is_none()) {
+                let png_path = find_segment_map(ctx, &annotation.file_name)
+                    .with_context(|| format!("Segment map not found: {}", annotation.file_name))?;
+                extents = segment_extents(ctx.input.read_bytes(&png_path)?)
+                    .with_context(|| format!("Failed to decode segment map: {}", png_path.display()))?;
+            }
+
+            for segment in segments {
+                let bbox = match &segment.bbox {
+                    Some(bbox) if bbox.len() == 4 => [bbox[0], bbox[1], bbox[0] + bbox[2], bbox[1] + bbox[3]],
+                    _ => match extents.get(&segment.id) {
+                        Some(extent) => *extent,
+                        None => continue,
+                    },
+                };
+                unified_annotations.push(UnifiedAnnotation {
+                    bbox: bbox.to_vec(),
+                    category_id: segment.category_id,
+                    category_name: things.get(&segment.category_id).map(|category| labels.name(&category.name).to_string()),
+                    supercategory: things.get(&segment.category_id).and_then(|category| category.supercategory.clone()),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let (file_name, source_url) = split_image_url(image.file_name.clone(), image.coco_url.clone());
+        unified_images.push(UnifiedImage {
+            file_name,
+            source_url,
+            image_id: Some(image.id.to_string()),
+            height: image.height,
+            width: image.width,
+            annotations: unified_annotations,
+            ..Default::default()
+        });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct CocoPanopticParser;
+
+impl FormatParser for CocoPanopticParser {
+    fn name(&self) -> &'static str {
+        "coco-panoptic"
+    }
+
+    fn description(&self) -> &'static str {
+        "COCO panoptic"
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, ctx, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputSource;
+    use std::fs;
+
+    // 8x6 RGB segment map with segment 2 covering x 2..5, y 1..4 and stuff segment 3 elsewhere
+    fn segment_map() -> Vec<u8> {
+        let mut pixels = Vec::new();
+        for y in 0..6 {
+            for x in 0..8 {
+                let id = if (2..5).contains(&x) && (1..4).contains(&y) { 2 } else { 3 };
+                pixels.extend([id, 0, 0]);
+            }
+        }
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 8, 6);
+        encoder.set_color(png::ColorType::Rgb);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&pixels).unwrap();
+        writer.finish().unwrap();
+        png
+    }
+
+    #[test]
+    fn thing_segments_become_boxes_from_their_bbox_or_the_segment_map() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("panoptic_val")).unwrap();
+        fs::write(dir.path().join("panoptic_val/a.png"), segment_map()).unwrap();
+        let dataset = serde_json::json!({
+            "images": [{"id": 1, "file_name": "a.jpg", "width": 8, "height": 6}],
+            "annotations": [{"image_id": 1, "file_name": "a.png", "segments_info": [
+                {"id": 1, "category_id": 5, "bbox": [0, 0, 2, 2]},
+                {"id": 2, "category_id": 5},
+                {"id": 3, "category_id": 200, "bbox": [0, 0, 8, 6]},
+            ]}],
+            "categories": [
+                {"id": 5, "name": "car", "isthing": 1, "supercategory": "vehicle"},
+                {"id": 200, "name": "road", "isthing": 0},
+            ],
+        });
+
+        let input = InputSource::open(dir.path()).unwrap();
+        let path = dir.path().join("panoptic_val.json");
+        let ctx = ParseContext { path: &path, file_name: "panoptic_val.json", input: &input, read_image_size: false };
+        let images = parse(&dataset.to_string(), &ctx, &mut LabelRegistry::default()).unwrap();
+
+        assert_eq!(images[0].image_id.as_deref(), Some("1"));
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.supercategory.as_deref(), annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [
+            (5, Some("vehicle"), vec![0.0, 0.0, 2.0, 2.0]),
+            (5, Some("vehicle"), vec![2.0, 1.0, 5.0, 4.0]),
+        ]);
+    }
+}
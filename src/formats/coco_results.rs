@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{CocoCategory, CocoId, CocoImageInfo, UnifiedAnnotation, UnifiedImage, split_image_url};
+
+// One detection from a COCO results file
+#[derive(Debug, Deserialize)]
+struct CocoDetection {
+    image_id: CocoId,
+    category_id: u32,
+    bbox: Vec<f64>, // [x, y, width, height]
+    score: f64,
+}
+
+// Image and category information from the original COCO file; results files only carry ids.
+// Annotations are not needed, so image-info files such as test-dev work as well.
+#[derive(Debug, Deserialize)]
+pub struct CocoImagesIndex {
+    images: Vec<CocoImageInfo>,
+    #[serde(default)]
+    categories: Vec<CocoCategory>,
+}
+
+pub fn load_index(path: &Path) -> Result<CocoImagesIndex> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read images index: {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse images index as COCO: {}", path.display()))
+}
+
+// Parse a COCO results file into pseudo-labels. Detections below `score_threshold` are dropped;
+// images that only had low-scoring detections are kept with no labels (negatives).
+pub fn parse(content: &str, index: &CocoImagesIndex, score_threshold: f64, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let detections: Vec<CocoDetection> = serde_json::from_str(content)?;
+
+    let images: HashMap<&CocoId, &CocoImageInfo> = index.images.iter().map(|image| (&image.id, image)).collect();
+    let category_names: HashMap<u32, &str> = index.categories
+        .iter()
+        .map(|category| (category.id, labels.name(&category.name)))
+        .collect();
+
+    // Keyed by file name so output order is stable
+    let mut by_image: BTreeMap<&str, (&CocoImageInfo, Vec<UnifiedAnnotation>)> = BTreeMap::new();
+    for detection in &detections {
+        let Some(image) = images.get(&detection.image_id) else {
+            anyhow::bail!("Detection references image {:?} which is not in the images index", detection.image_id);
+        };
+        let entry = by_image.entry(image.file_name.as_str()).or_insert_with(|| (image, Vec::new()));
+        if detection.score < score_threshold {
+            continue;
+        }
+
+        let [x, y, w, h] = detection.bbox[..] else {
+            anyhow::bail!("Detection bbox must have 4 values");
+        };
+        entry.1.push(UnifiedAnnotation {
+            bbox: vec![x, y, x + w, y + h],
+            category_id: detection.category_id,
+            category_name: category_names.get(&detection.category_id).map(|name| name.to_string()),
+            ..Default::default()
+        });
+    }
+
+    Ok(by_image
+        .into_values()
+        .map(|(image, annotations)| {
+            let (file_name, source_url) = split_image_url(image.file_name.clone(), image.coco_url.clone());
+            UnifiedImage { file_name, source_url, height: image.height, width: image.width, annotations, ..Default::default() }
+        })
+        .collect())
+}
+
+pub struct CocoResultsParser {
+    pub index: Option<CocoImagesIndex>,
+    pub score_threshold: f64,
+}
+
+impl FormatParser for CocoResultsParser {
+    fn name(&self) -> &'static str {
+        "coco-results"
+    }
+
+    fn description(&self) -> &'static str {
+        "COCO results"
+    }
+
+    fn parse(&self, content: &str, _ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        let index = self.index.as_ref().context("--format coco-results requires --images-index")?;
+        parse(content, index, self.score_threshold, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_scoring_detections_leave_negative_images() {
+        let index: CocoImagesIndex = serde_json::from_value(serde_json::json!({
+            "images": [
+                {"id": 1, "file_name": "a.jpg", "width": 64, "height": 48},
+                {"id": "b", "file_name": "b.jpg", "width": 32, "height": 24},
+                {"id": 3, "file_name": "c.jpg", "width": 32, "height": 24},
+            ],
+            "categories": [{"id": 18, "name": "dog"}],
+        })).unwrap();
+        let results = r#"[
+            {"image_id": 1, "category_id": 18, "bbox": [4, 4, 20, 10], "score": 0.9},
+            {"image_id": 1, "category_id": 5, "bbox": [2, 3, 10, 6], "score": 0.5},
+            {"image_id": "b", "category_id": 18, "bbox": [0, 0, 8, 8], "score": 0.1}
+        ]"#;
+        let images = parse(results, &index, 0.5, &mut LabelRegistry::default()).unwrap();
+
+        let names: Vec<_> = images.iter().map(|image| image.file_name.as_str()).collect();
+        assert_eq!(names, ["a.jpg", "b.jpg"]);
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.category_name.as_deref(), annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [(18, Some("dog"), vec![4.0, 4.0, 24.0, 14.0]), (5, None, vec![2.0, 3.0, 12.0, 9.0])]);
+        assert!(images[1].annotations.is_empty());
+
+        let error = parse(r#"[{"image_id": 9, "category_id": 18, "bbox": [0, 0, 1, 1], "score": 1}]"#, &index, 0.5, &mut LabelRegistry::default());
+        assert!(error.is_err());
+    }
+}
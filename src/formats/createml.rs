@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{UnifiedAnnotation, UnifiedImage, split_image_url};
+
+// Box center and size in absolute pixels
+#[derive(Debug, Deserialize)]
+struct CreateMlCoordinates {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateMlAnnotation {
+    label: String,
+    coordinates: CreateMlCoordinates,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateMlImage {
+    image: String,
+    annotations: Vec<CreateMlAnnotation>,
+}
+
+// Parse a CreateML / Roboflow JSON array. String labels are indexed into classes and center-based
+// boxes converted to corners; `image_size` supplies dimensions since the format doesn't store them.
+pub fn parse(
+    content: &str,
+    labels: &mut LabelRegistry,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let images: Vec<CreateMlImage> = serde_json::from_str(content)?;
+    let mut unified_images = Vec::new();
+
+    for image in images {
+        let unified_annotations = image.annotations
+            .iter()
+            .map(|annotation| {
+                let c = &annotation.coordinates;
+                UnifiedAnnotation {
+                    bbox: vec![c.x - c.width / 2.0, c.y - c.height / 2.0, c.x + c.width / 2.0, c.y + c.height / 2.0],
+                    category_id: labels.id_for(&annotation.label),
+                    category_name: Some(labels.name(&annotation.label).to_string()),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        let (width, height) = image_size(&image.image)
+            .with_context(|| format!("Failed to determine the size of {}", image.image))?;
+        let (file_name, source_url) = split_image_url(image.image, None);
+        unified_images.push(UnifiedImage { file_name, source_url, height, width, annotations: unified_annotations, ..Default::default() });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct CreateMlParser;
+
+impl FormatParser for CreateMlParser {
+    fn name(&self) -> &'static str {
+        "createml"
+    }
+
+    fn description(&self) -> &'static str {
+        "CreateML JSON"
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels, &|file_name| ctx.image_size(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn center_boxes_become_corners() {
+        let export = r#"[
+            {"image": "a.jpg", "annotations": [
+                {"label": "car", "coordinates": {"x": 14, "y": 9, "width": 20, "height": 10}},
+                {"label": "dog", "coordinates": {"x": 7, "y": 6, "width": 10, "height": 6}}
+            ]},
+            {"image": "b.jpg", "annotations": [{"label": "dog", "coordinates": {"x": 5, "y": 5, "width": 2, "height": 2}}]}
+        ]"#;
+        let images = parse(export, &mut LabelRegistry::default(), &|_| Ok((64, 48))).unwrap();
+
+        assert_eq!((images[0].file_name.as_str(), images[0].width, images[0].height), ("a.jpg", 64, 48));
+        let annotations: Vec<_> = images.iter()
+            .flat_map(|image| &image.annotations)
+            .map(|annotation| (annotation.category_id, annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [
+            (0, vec![4.0, 4.0, 24.0, 14.0]),
+            (1, vec![2.0, 3.0, 12.0, 9.0]),
+            (1, vec![4.0, 4.0, 6.0, 6.0]),
+        ]);
+    }
+}
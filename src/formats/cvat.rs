@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, normalize_path, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+fn attribute<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Result<&'a str> {
+    node.attribute(name)
+        .with_context(|| format!("<{}> element is missing the '{}' attribute", node.tag_name().name(), name))
+}
+
+fn number_attribute(node: &roxmltree::Node, name: &str) -> Result<f64> {
+    attribute(node, name)?
+        .parse()
+        .with_context(|| format!("Invalid '{}' attribute on <{}>", name, node.tag_name().name()))
+}
+
+// CVAT polygon points are "x1,y1;x2,y2;..."
+fn parse_points(points: &str) -> Result<Vec<Vec<f64>>> {
+    points
+        .split(';')
+        .filter(|point| !point.trim().is_empty())
+        .map(|point| {
+            point
+                .split(',')
+                .map(|value| value.trim().parse::<f64>().with_context(|| format!("Invalid polygon point '{}'", point)))
+                .collect()
+        })
+        .collect()
+}
+
+// Parse a "CVAT for images 1.1" XML dump. Labels declared in <meta> are registered first so
+// class ids follow the project's label order; <box> and the extent of <polygon> become boxes.
+pub fn parse(content: &str, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let document = roxmltree::Document::parse(content)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "annotations" {
+        anyhow::bail!("Expected <annotations> root element, found <{}>", root.tag_name().name());
+    }
+
+    for label in root.descendants().filter(|node| node.has_tag_name("label")) {
+        if let Some(name) = label.children().find(|node| node.has_tag_name("name")).and_then(|node| node.text()) {
+            labels.id_for(name);
+        }
+    }
+
+    let mut unified_images = Vec::new();
+    for image in root.children().filter(|node| node.has_tag_name("image")) {
+        let mut unified_annotations = Vec::new();
+
+        for shape in image.children().filter(|node| node.is_element()) {
+            let bbox = match shape.tag_name().name() {
+                "box" => vec![
+                    number_attribute(&shape, "xtl")?,
+                    number_attribute(&shape, "ytl")?,
+                    number_attribute(&shape, "xbr")?,
+                    number_attribute(&shape, "ybr")?,
+                ],
+                "polygon" => match points_extent(&parse_points(attribute(&shape, "points")?)?) {
+                    Some(extent) => extent.to_vec(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let label = attribute(&shape, "label")?;
+            unified_annotations.push(UnifiedAnnotation {
+                bbox,
+                category_id: labels.id_for(label),
+                category_name: Some(labels.name(label).to_string()),
+                ..Default::default()
+            });
+        }
+
+        unified_images.push(UnifiedImage {
+            file_name: normalize_path(attribute(&image, "name")?),
+            source_url: None,
+            height: number_attribute(&image, "height")? as u32,
+            width: number_attribute(&image, "width")? as u32,
+            annotations: unified_annotations,
+            ..Default::default()
+        });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct CvatParser;
+
+impl FormatParser for CvatParser {
+    fn name(&self) -> &'static str {
+        "cvat"
+    }
+
+    fn description(&self) -> &'static str {
+        "CVAT XML"
+    }
+
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".xml")
+    }
+
+    fn parse(&self, content: &str, _ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_labels_fix_class_order_and_shapes_become_boxes() {
+        let dump = r#"<?xml version="1.0" encoding="utf-8"?>
+<annotations>
+  <version>1.1</version>
+  <meta><task><labels>
+    <label><name>person</name></label>
+    <label><name>car</name></label>
+  </labels></task></meta>
+  <image id="0" name="frames\a.jpg" width="64" height="48">
+    <box label="car" xtl="4" ytl="4" xbr="24" ybr="14" occluded="0"/>
+    <polygon label="person" points="2,3;12,3;7,9"/>
+    <points label="person" points="5,5"/>
+  </image>
+</annotations>"#;
+        let images = parse(dump, &mut LabelRegistry::default()).unwrap();
+
+        assert_eq!((images[0].file_name.as_str(), images[0].width, images[0].height), ("frames/a.jpg", 64, 48));
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [(1, vec![4.0, 4.0, 24.0, 14.0]), (0, vec![2.0, 3.0, 12.0, 9.0])]);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, is_ndjson, largest_polygon, parse_ndjson_lines};
+use crate::{UnifiedAnnotation, UnifiedImage, split_image_url};
+
+// DAMM format annotation (custom format)
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DammAnnotation {
+    bbox: Vec<Vec<f64>>, // [[x1, y1], [x2, y2]] format
+    category_id: u32,
+    #[serde(default)]
+    bbox_mode: Option<DammBoxMode>, // BoxMode.XYXY_ABS when absent
+    #[serde(default)]
+    segmentation: Option<Vec<Vec<f64>>>,
+}
+
+// Detectron2 BoxMode, written either by name ("BoxMode.XYWH_ABS") or as its integer value
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum DammBoxMode {
+    Name(String),
+    Index(u8),
+}
+
+// DAMM format image structure
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DammImage {
+    file_name: String,
+    height: u32,
+    width: u32,
+    image_id: u32,
+    annotations: Vec<DammAnnotation>,
+}
+
+// DAMM format dataset
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DammDataset {
+    annotations: Vec<DammImage>,
+}
+
+pub fn parse(content: &str) -> Result<Vec<UnifiedImage>> {
+    convert_damm_dataset(serde_json::from_str(content)?)
+}
+
+// DAMM NDJSON: one DAMM image record per line
+pub fn parse_ndjson(content: &str) -> Result<Vec<UnifiedImage>> {
+    convert_damm_dataset(DammDataset { annotations: parse_ndjson_lines(content)? })
+}
+
+// Convert a DAMM [[a, b], [c, d]] box to unified [x1, y1, x2, y2] according to its bbox_mode.
+// XYWH modes store the size in the second pair; REL modes are fractions of the image size.
+fn damm_bbox_to_xyxy(annotation: &DammAnnotation, width: u32, height: u32) -> Result<[f64; 4]> {
+    let [first, second] = annotation.bbox.as_slice() else {
+        anyhow::bail!("Expected [[x, y], [x, y]], got {} points", annotation.bbox.len());
+    };
+    let ([a, b], [c, d]) = (first.as_slice(), second.as_slice()) else {
+        anyhow::bail!("Expected two coordinates per point");
+    };
+
+    let mode = match &annotation.bbox_mode {
+        None => "XYXY_ABS",
+        Some(DammBoxMode::Name(name)) => name.strip_prefix("BoxMode.").unwrap_or(name),
+        Some(DammBoxMode::Index(0)) => "XYXY_ABS",
+        Some(DammBoxMode::Index(1)) => "XYWH_ABS",
+        Some(DammBoxMode::Index(2)) => "XYXY_REL",
+        Some(DammBoxMode::Index(3)) => "XYWH_REL",
+        Some(DammBoxMode::Index(index)) => anyhow::bail!("Unsupported bbox_mode {}", index),
+    };
+    let (x1, y1, x2, y2) = match mode {
+        "XYXY_ABS" | "XYXY_REL" => (*a, *b, *c, *d),
+        "XYWH_ABS" | "XYWH_REL" => (*a, *b, a + c, b + d),
+        _ => anyhow::bail!("Unsupported bbox_mode {:?}", mode),
+    };
+
+    if mode.ends_with("_REL") {
+        let (width, height) = (width as f64, height as f64);
+        Ok([x1 * width, y1 * height, x2 * width, y2 * height])
+    } else {
+        Ok([x1, y1, x2, y2])
+    }
+}
+
+pub struct DammParser;
+
+impl FormatParser for DammParser {
+    fn name(&self) -> &'static str {
+        "damm"
+    }
+
+    fn description(&self) -> &'static str {
+        "DAMM format"
+    }
+
+    // DAMM records may also come as NDJSON, one record per line
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        [".json", ".jsonl", ".ndjson"].iter().any(|ext| file_name.ends_with(ext))
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, _labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        if is_ndjson(ctx.path, content) {
+            parse_ndjson(content)
+        } else {
+            parse(content)
+        }
+    }
+}
+
+fn convert_damm_dataset(dataset: DammDataset) -> Result<Vec<UnifiedImage>> {
+    let mut unified_images = Vec::new();
+    
+    for damm_image in dataset.annotations {
+        let mut unified_annotations = Vec::new();
+        
+        for damm_ann in damm_image.annotations {
+            let bbox = damm_bbox_to_xyxy(&damm_ann, damm_image.width, damm_image.height)
+                .with_context(|| format!("Invalid bbox in {}", damm_image.file_name))?;
+            let unified_ann = UnifiedAnnotation {
+                bbox: bbox.to_vec(),
+                category_id: damm_ann.category_id,
+                category_name: None,
+                segmentation: damm_ann.segmentation.as_deref().and_then(largest_polygon),
+                ..Default::default()
+            };
+            unified_annotations.push(unified_ann);
+        }
+        
+        let (file_name, source_url) = split_image_url(damm_image.file_name, None);
+        let unified_image = UnifiedImage {
+            file_name,
+            source_url,
+            image_id: Some(damm_image.image_id.to_string()),
+            height: damm_image.height,
+            width: damm_image.width,
+            annotations: unified_annotations,
+            ..Default::default()
+        };
+        unified_images.push(unified_image);
+    }
+    
+    Ok(unified_images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damm_boxes_follow_their_bbox_mode() {
+        let damm_box = |bbox_mode: serde_json::Value| -> Result<[f64; 4]> {
+            let annotation: DammAnnotation = serde_json::from_value(serde_json::json!(
+                {"bbox": [[0.25, 0.5], [0.5, 0.25]], "category_id": 1, "bbox_mode": bbox_mode}
+            )).unwrap();
+            damm_bbox_to_xyxy(&annotation, 64, 48)
+        };
+        assert_eq!(damm_box(serde_json::Value::Null).unwrap(), [0.25, 0.5, 0.5, 0.25]);
+        assert_eq!(damm_box(serde_json::json!("BoxMode.XYWH_ABS")).unwrap(), [0.25, 0.5, 0.75, 0.75]);
+        assert_eq!(damm_box(serde_json::json!(2)).unwrap(), [16.0, 24.0, 32.0, 12.0]);
+        assert_eq!(damm_box(serde_json::json!("XYWH_REL")).unwrap(), [16.0, 24.0, 48.0, 36.0]);
+        assert!(damm_box(serde_json::json!(4)).is_err());
+        assert!(damm_box(serde_json::json!("XYXY_PIXELS")).is_err());
+    }
+
+    #[test]
+    fn ndjson_records_parse_like_a_dataset() {
+        let record = serde_json::json!({"file_name": "a.jpg", "height": 48, "width": 64, "image_id": 7, "annotations": [
+            {"bbox": [[4, 4], [24, 14]], "category_id": 2, "segmentation": [[4, 4, 24, 4, 24, 14]]},
+        ]});
+        let dataset = serde_json::json!({"annotations": [record]});
+        let images = parse(&dataset.to_string()).unwrap();
+        let ndjson_images = parse_ndjson(&format!("{}\n\n{}\n", record, record)).unwrap();
+
+        assert_eq!(ndjson_images.len(), 2);
+        for image in [&images[0], &ndjson_images[1]] {
+            assert_eq!((image.image_id.as_deref(), image.width, image.height), (Some("7"), 64, 48));
+            let annotation = &image.annotations[0];
+            assert_eq!((annotation.category_id, annotation.category_name.as_deref()), (2, None));
+            assert_eq!(annotation.bbox, [4.0, 4.0, 24.0, 14.0]);
+            assert_eq!(annotation.segmentation.as_deref(), Some(&[4.0, 4.0, 24.0, 4.0, 24.0, 14.0][..]));
+        }
+    }
+}
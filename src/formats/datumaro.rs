@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, normalize_path, parent_dir_name, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+#[derive(Debug, Deserialize)]
+struct DatumaroLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatumaroLabelCategories {
+    labels: Vec<DatumaroLabel>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DatumaroCategories {
+    #[serde(default)]
+    label: Option<DatumaroLabelCategories>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatumaroAnnotation {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    label_id: Option<usize>,
+    #[serde(default)]
+    bbox: Option<Vec<f64>>, // [x, y, width, height]
+    #[serde(default)]
+    points: Option<Vec<f64>>, // Flat [x1, y1, x2, y2, ...]
+}
+
+// Image reference; older exports put it under "image", newer ones split path ("media") from size
+#[derive(Debug, Default, Deserialize)]
+struct DatumaroImage {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    size: Option<[u32; 2]>, // [height, width]
+}
+
+#[derive(Debug, Deserialize)]
+struct DatumaroItem {
+    id: String,
+    #[serde(default)]
+    annotations: Vec<DatumaroAnnotation>,
+    #[serde(default)]
+    image: Option<DatumaroImage>,
+    #[serde(default)]
+    media: Option<DatumaroImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatumaroDataset {
+    #[serde(default)]
+    categories: DatumaroCategories,
+    items: Vec<DatumaroItem>,
+}
+
+// Parse a Datumaro `annotations/<subset>.json`. The label categories are registered first so
+// class ids follow their order; bbox and polygon annotations are kept, other types (points,
+// masks, captions, image labels) are skipped. Items without a stored size use `image_size`.
+pub fn parse(
+    content: &str,
+    labels: &mut LabelRegistry,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let dataset: DatumaroDataset = serde_json::from_str(content)?;
+    let label_names: Vec<String> = dataset.categories.label
+        .map(|categories| categories.labels.into_iter().map(|label| label.name).collect())
+        .unwrap_or_default();
+    for name in &label_names {
+        labels.id_for(name);
+    }
+
+    let mut unified_images = Vec::new();
+    for item in dataset.items {
+        let mut unified_annotations = Vec::new();
+        for annotation in &item.annotations {
+            let bbox = match (annotation.kind.as_str(), &annotation.bbox, &annotation.points) {
+                ("bbox", Some(bbox), _) if bbox.len() == 4 => [bbox[0], bbox[1], bbox[0] + bbox[2], bbox[1] + bbox[3]],
+                ("polygon", _, Some(points)) => {
+                    let pairs: Vec<Vec<f64>> = points.chunks_exact(2).map(|pair| pair.to_vec()).collect();
+                    match points_extent(&pairs) {
+                        Some(extent) => extent,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            let Some(name) = annotation.label_id.and_then(|label_id| label_names.get(label_id)) else {
+                anyhow::bail!("Item {} has a {} annotation without a known label", item.id, annotation.kind);
+            };
+            unified_annotations.push(UnifiedAnnotation {
+                bbox: bbox.to_vec(),
+                category_id: labels.id_for(name),
+                category_name: Some(labels.name(name).to_string()),
+                ..Default::default()
+            });
+        }
+
+        let image = item.image.unwrap_or_default();
+        let media = item.media.unwrap_or_default();
+        let file_name = image.path
+            .or(media.path)
+            .map(|path| normalize_path(&path))
+            .unwrap_or_else(|| format!("{}.jpg", item.id));
+        let (width, height) = match image.size.or(media.size) {
+            Some([height, width]) => (width, height),
+            None => image_size(&file_name)
+                .with_context(|| format!("Failed to determine the size of {}", file_name))?,
+        };
+
+        unified_images.push(UnifiedImage { file_name, height, width, annotations: unified_annotations, ..Default::default() });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct DatumaroParser;
+
+impl FormatParser for DatumaroParser {
+    fn name(&self) -> &'static str {
+        "datumaro"
+    }
+
+    fn description(&self) -> &'static str {
+        "Datumaro dataset"
+    }
+
+    // Datumaro keeps one <subset>.json per subset under annotations/
+    fn is_annotation_file(&self, path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".json") && parent_dir_name(path) == Some("annotations")
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels, &|file_name| ctx.image_size(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_and_polygons_follow_the_label_categories() {
+        let dataset = serde_json::json!({
+            "categories": {"label": {"labels": [{"name": "person"}, {"name": "car"}]}},
+            "items": [
+                {"id": "a", "image": {"path": "a.png", "size": [48, 64]}, "annotations": [
+                    {"type": "bbox", "label_id": 1, "bbox": [4, 4, 20, 10]},
+                    {"type": "polygon", "label_id": 0, "points": [2, 3, 12, 3, 7, 9]},
+                    {"type": "points", "label_id": 0, "points": [5, 5]},
+                ]},
+                {"id": "b", "media": {"path": "frames/b.jpg"}},
+                {"id": "c"},
+            ],
+        });
+        let images = parse(&dataset.to_string(), &mut LabelRegistry::default(), &|_| Ok((32, 24))).unwrap();
+
+        let sizes: Vec<_> = images.iter().map(|image| (image.file_name.as_str(), image.width, image.height)).collect();
+        assert_eq!(sizes, [("a.png", 64, 48), ("frames/b.jpg", 32, 24), ("c.jpg", 32, 24)]);
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [(1, vec![4.0, 4.0, 24.0, 14.0]), (0, vec![2.0, 3.0, 12.0, 9.0])]);
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, parent_dir_name, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+// Parse one DOTA label file: optional `imagesource:` / `gsd:` header lines followed by
+// `x1 y1 x2 y2 x3 y3 x4 y4 category difficult` per object. The four corners are kept as an
+// oriented box and their extent becomes the axis-aligned box. Images are `<stem>.png`.
+pub fn parse(
+    content: &str,
+    file_stem: &str,
+    labels: &mut LabelRegistry,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let mut unified_annotations = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("imagesource:") || line.starts_with("gsd:") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 9 {
+            anyhow::bail!("Line {} has {} fields, expected at least 9", line_number + 1, fields.len());
+        }
+
+        let mut corners = [0.0; 8];
+        for (corner, value) in corners.iter_mut().zip(&fields[..8]) {
+            *corner = value
+                .parse()
+                .with_context(|| format!("Invalid corner coordinate on line {}", line_number + 1))?;
+        }
+        let points: Vec<Vec<f64>> = corners.chunks(2).map(|point| point.to_vec()).collect();
+        let Some(extent) = points_extent(&points) else {
+            continue;
+        };
+
+        let category = fields[8];
+        unified_annotations.push(UnifiedAnnotation {
+            bbox: extent.to_vec(),
+            category_id: labels.id_for(category),
+            category_name: Some(category.to_string()),
+            obb: Some(corners),
+            ..Default::default()
+        });
+    }
+
+    let file_name = format!("{}.png", file_stem);
+    let (width, height) = image_size(&file_name)
+        .with_context(|| format!("Failed to determine the size of {}", file_name))?;
+
+    Ok(vec![UnifiedImage { file_name, source_url: None, height, width, annotations: unified_annotations, ..Default::default() }])
+}
+
+pub struct DotaParser;
+
+impl FormatParser for DotaParser {
+    fn name(&self) -> &'static str {
+        "dota"
+    }
+
+    fn description(&self) -> &'static str {
+        "DOTA labels"
+    }
+
+    fn is_annotation_file(&self, path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".txt") && parent_dir_name(path).is_some_and(|dir| dir.starts_with("labelTxt"))
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, ctx.file_stem()?, labels, &|file_name| ctx.image_size(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_are_kept_as_oriented_boxes() {
+        let labels = "imagesource:GoogleEarth\ngsd:0.146\n\
+                      10 0 20 10 10 20 0 10 plane 0\n\
+                      4 4 24 4 24 14 4 14 small-vehicle 1\n";
+        let images = parse(labels, "P0001", &mut LabelRegistry::default(), &|_| Ok((64, 48))).unwrap();
+
+        assert_eq!((images[0].file_name.as_str(), images[0].width, images[0].height), ("P0001.png", 64, 48));
+        let plane = &images[0].annotations[0];
+        assert_eq!((plane.category_id, plane.category_name.as_deref()), (0, Some("plane")));
+        assert_eq!(plane.bbox, [0.0, 0.0, 20.0, 20.0]);
+        assert_eq!(plane.obb, Some([10.0, 0.0, 20.0, 10.0, 10.0, 20.0, 0.0, 10.0]));
+        assert_eq!(images[0].annotations[1].category_id, 1);
+        assert_eq!(images[0].annotations[1].bbox, [4.0, 4.0, 24.0, 14.0]);
+
+        assert!(parse("1 2 3 4 plane\n", "P0002", &mut LabelRegistry::default(), &|_| Ok((64, 48))).is_err());
+    }
+}
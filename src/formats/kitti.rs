@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, parent_dir_name};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+// KITTI type → class index mapping. Types sharing an index are one class, named after the first
+// of them in the file.
+pub struct ClassMap {
+    indices: HashMap<String, u32>,
+    names: HashMap<u32, String>,
+}
+
+// Load a KITTI type mapping file: one "<type> <class index>" pair per line, '#' starts a comment
+pub fn load_class_map(path: &Path) -> Result<ClassMap> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read KITTI class map: {}", path.display()))?;
+
+    let mut class_map = ClassMap { indices: HashMap::new(), names: HashMap::new() };
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let (Some(kitti_type), Some(index), None) = (fields.next(), fields.next(), fields.next()) else {
+            anyhow::bail!("Expected '<type> <class index>' on line {} of {}", line_number + 1, path.display());
+        };
+        let index = index
+            .parse()
+            .with_context(|| format!("Invalid class index on line {} of {}", line_number + 1, path.display()))?;
+        class_map.names.entry(index).or_insert_with(|| kitti_type.to_string());
+        class_map.indices.insert(kitti_type.to_string(), index);
+    }
+
+    Ok(class_map)
+}
+
+// Parse one KITTI label file (`type truncated occluded alpha x1 y1 x2 y2 ...` per object).
+// `DontCare` regions are skipped. With a class map, types are mapped to its indices and unmapped
+// types are dropped and merged types take the name of their class; without one, types are indexed
+// into classes as they are seen.
+pub fn parse(
+    content: &str,
+    file_stem: &str,
+    labels: &mut LabelRegistry,
+    class_map: Option<&ClassMap>,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let mut unified_annotations = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 8 {
+            anyhow::bail!("Line {} has {} fields, expected at least 8", line_number + 1, fields.len());
+        }
+
+        let kitti_type = fields[0];
+        if kitti_type == "DontCare" {
+            continue;
+        }
+        let (category_id, category_name) = match class_map {
+            Some(class_map) => match class_map.indices.get(kitti_type) {
+                Some(&index) => (index, class_map.names[&index].clone()),
+                None => continue,
+            },
+            None => (labels.id_for(kitti_type), kitti_type.to_string()),
+        };
+
+        let bbox = fields[4..8]
+            .iter()
+            .map(|value| value.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid bbox on line {}", line_number + 1))?;
+        unified_annotations.push(UnifiedAnnotation {
+            bbox,
+            category_id,
+            category_name: Some(category_name),
+            ..Default::default()
+        });
+    }
+
+    // KITTI images are PNGs named like their label file; other extensions are found by stem
+    let file_name = format!("{}.png", file_stem);
+    let (width, height) = image_size(&file_name)
+        .with_context(|| format!("Failed to determine the size of {}", file_name))?;
+
+    Ok(vec![UnifiedImage { file_name, source_url: None, height, width, annotations: unified_annotations, ..Default::default() }])
+}
+
+pub struct KittiParser {
+    pub class_map: Option<ClassMap>,
+}
+
+impl FormatParser for KittiParser {
+    fn name(&self) -> &'static str {
+        "kitti"
+    }
+
+    fn description(&self) -> &'static str {
+        "KITTI labels"
+    }
+
+    // KITTI trees also contain calib/*.txt and split lists; labels live in label_2/ (or similar)
+    fn is_annotation_file(&self, path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".txt") && parent_dir_name(path).is_some_and(|dir| dir.starts_with("label"))
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, ctx.file_stem()?, labels, self.class_map.as_ref(), &|file_name| ctx.image_size(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABELS: &str = "Car 0.00 0 -1.58 4.00 4.00 24.00 14.00 1.6 1.7 3.9 -0.6 1.9 47.7 -1.6\n\
+                          Van 0.00 0 -1.58 30.00 4.00 40.00 14.00 1.6 1.7 3.9 -0.6 1.9 47.7 -1.6\n\
+                          Pedestrian 0.00 0 0.2 2.00 3.00 12.00 9.00 1.8 0.6 0.8 1.0 1.7 8.4 0.3\n\
+                          DontCare -1 -1 -10 0.00 0.00 5.00 5.00 -1 -1 -1 -1000 -1000 -1000 -10\n";
+
+    fn classes(images: &[UnifiedImage]) -> Vec<(u32, &str)> {
+        images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.category_name.as_deref().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn types_are_indexed_as_seen_without_a_class_map() {
+        let images = parse(LABELS, "000001", &mut LabelRegistry::default(), None, &|_| Ok((64, 48))).unwrap();
+        assert_eq!(images[0].file_name, "000001.png");
+        assert_eq!(classes(&images), [(0, "Car"), (1, "Van"), (2, "Pedestrian")]);
+        assert_eq!(images[0].annotations[0].bbox, [4.0, 4.0, 24.0, 14.0]);
+    }
+
+    #[test]
+    fn class_maps_merge_and_drop_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("classes.txt");
+        fs::write(&path, "# vehicles\nCar 1\nVan 1\n\nPedestrian 0 # people\n").unwrap();
+        let class_map = load_class_map(&path).unwrap();
+
+        let labels = LABELS.replace("Van", "Truck");
+        let images = parse(&labels, "000001", &mut LabelRegistry::default(), Some(&class_map), &|_| Ok((64, 48))).unwrap();
+        assert_eq!(classes(&images), [(1, "Car"), (0, "Pedestrian")]);
+        let images = parse(LABELS, "000001", &mut LabelRegistry::default(), Some(&class_map), &|_| Ok((64, 48))).unwrap();
+        assert_eq!(classes(&images), [(1, "Car"), (1, "Car"), (0, "Pedestrian")]);
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::{FormatParser, LabelRegistry, ParseContext, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage, split_image_url};
+
+// Region value; all coordinates are percentages of the original image size
+#[derive(Debug, Deserialize)]
+struct LabelStudioValue {
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    #[serde(default)]
+    rotation: f64,
+    #[serde(default)]
+    points: Vec<Vec<f64>>,
+    #[serde(default)]
+    rectanglelabels: Vec<String>,
+    #[serde(default)]
+    polygonlabels: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelStudioResult {
+    #[serde(rename = "type")]
+    result_type: String,
+    #[serde(default)]
+    original_width: u32,
+    #[serde(default)]
+    original_height: u32,
+    value: LabelStudioValue,
+}
+
+#[derive(Debug, Deserialize)]
+struct LabelStudioAnnotation {
+    #[serde(default)]
+    was_cancelled: bool,
+    result: Vec<LabelStudioResult>,
+}
+
+// One exported task: the image reference plus its annotations
+#[derive(Debug, Deserialize)]
+struct LabelStudioTask {
+    data: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    annotations: Vec<LabelStudioAnnotation>,
+}
+
+// Absolute [x1, y1, x2, y2] extent of a rectangle given in percentages, rotated clockwise by
+// `rotation` degrees around its top-left corner as Label Studio does.
+fn rectangle_extent(value: &LabelStudioValue, img_width: f64, img_height: f64) -> Option<[f64; 4]> {
+    let x = value.x * img_width / 100.0;
+    let y = value.y * img_height / 100.0;
+    let w = value.width * img_width / 100.0;
+    let h = value.height * img_height / 100.0;
+    if value.rotation == 0.0 {
+        return Some([x, y, x + w, y + h]);
+    }
+
+    let (sin, cos) = value.rotation.to_radians().sin_cos();
+    let corners: Vec<Vec<f64>> = [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)]
+        .iter()
+        .map(|(dx, dy)| vec![x + dx * cos - dy * sin, y + dx * sin + dy * cos])
+        .collect();
+    points_extent(&corners)
+}
+
+// Parse a Label Studio JSON export (list of tasks). The first non-cancelled annotation of each
+// task is used; rectangle and polygon regions are converted from percentages to pixels.
+pub fn parse(content: &str, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let tasks: Vec<LabelStudioTask> = serde_json::from_str(content)?;
+    let mut unified_images = Vec::new();
+
+    for task in tasks {
+        // The image is normally under "image", but the key follows the labeling config
+        let image_ref = task.data.get("image")
+            .and_then(|value| value.as_str())
+            .or_else(|| task.data.values().find_map(|value| value.as_str()));
+        let Some(image_ref) = image_ref else {
+            anyhow::bail!("Task has no image reference in its data");
+        };
+
+        let mut width = 0;
+        let mut height = 0;
+        let mut unified_annotations = Vec::new();
+        let Some(annotation) = task.annotations.iter().find(|annotation| !annotation.was_cancelled) else {
+            let (file_name, source_url) = split_image_url(image_ref.to_string(), None);
+            unified_images.push(UnifiedImage { file_name, source_url, height, width, annotations: unified_annotations, ..Default::default() });
+            continue;
+        };
+
+        for result in &annotation.result {
+            width = result.original_width;
+            height = result.original_height;
+            let (img_width, img_height) = (width as f64, height as f64);
+
+            let (extent, names) = match result.result_type.as_str() {
+                "rectanglelabels" => (rectangle_extent(&result.value, img_width, img_height), &result.value.rectanglelabels),
+                "polygonlabels" => {
+                    let points: Vec<Vec<f64>> = result.value.points
+                        .iter()
+                        .filter(|point| point.len() >= 2)
+                        .map(|point| vec![point[0] * img_width / 100.0, point[1] * img_height / 100.0])
+                        .collect();
+                    (points_extent(&points), &result.value.polygonlabels)
+                }
+                _ => continue,
+            };
+
+            let (Some(extent), Some(name)) = (extent, names.first()) else {
+                continue;
+            };
+            unified_annotations.push(UnifiedAnnotation {
+                bbox: extent.to_vec(),
+                category_id: labels.id_for(name),
+                category_name: Some(labels.name(name).to_string()),
+                ..Default::default()
+            });
+        }
+
+        let (file_name, source_url) = split_image_url(image_ref.to_string(), None);
+        unified_images.push(UnifiedImage { file_name, source_url, height, width, annotations: unified_annotations, ..Default::default() });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct LabelStudioParser;
+
+impl FormatParser for LabelStudioParser {
+    fn name(&self) -> &'static str {
+        "label-studio"
+    }
+
+    fn description(&self) -> &'static str {
+        "Label Studio export"
+    }
+
+    fn parse(&self, content: &str, _ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentages_become_pixels_from_the_first_kept_annotation() {
+        let export = serde_json::json!([{
+            "data": {"image": "https://example.com/data/a.jpg"},
+            "annotations": [
+                {"was_cancelled": true, "result": [
+                    {"type": "rectanglelabels", "original_width": 200, "original_height": 100,
+                     "value": {"x": 0, "y": 0, "width": 50, "height": 50, "rectanglelabels": ["ignored"]}},
+                ]},
+                {"result": [
+                    {"type": "rectanglelabels", "original_width": 200, "original_height": 100,
+                     "value": {"x": 10, "y": 20, "width": 25, "height": 30, "rectanglelabels": ["car"]}},
+                    {"type": "polygonlabels", "original_width": 200, "original_height": 100,
+                     "value": {"points": [[10, 10], [50, 10], [30, 60]], "polygonlabels": ["dog"]}},
+                ]},
+            ],
+        }]);
+        let images = parse(&export.to_string(), &mut LabelRegistry::default()).unwrap();
+
+        assert_eq!(images[0].file_name, "a.jpg");
+        assert_eq!(images[0].source_url.as_deref(), Some("https://example.com/data/a.jpg"));
+        assert_eq!((images[0].width, images[0].height), (200, 100));
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_name.as_deref().unwrap(), annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [("car", vec![20.0, 20.0, 70.0, 50.0]), ("dog", vec![20.0, 10.0, 100.0, 60.0])]);
+    }
+}
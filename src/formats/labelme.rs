@@ -0,0 +1,103 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{FormatParser, LabelRegistry, ParseContext, normalize_path, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+// LabelMe shape: a labeled rectangle, polygon, point, line, ...
+#[derive(Debug, Deserialize)]
+struct LabelMeShape {
+    label: String,
+    points: Vec<Vec<f64>>,
+    #[serde(default = "default_shape_type")]
+    shape_type: String,
+}
+
+fn default_shape_type() -> String {
+    "polygon".to_string()
+}
+
+// LabelMe per-image annotation file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LabelMeFile {
+    image_path: String,
+    image_height: u32,
+    image_width: u32,
+    shapes: Vec<LabelMeShape>,
+}
+
+// Parse one LabelMe JSON file. Rectangles use their two corner points and polygons their
+// bounding extent; other shape types (points, lines, circles) carry no box and are skipped.
+pub fn parse(content: &str, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let file: LabelMeFile = serde_json::from_str(content)?;
+    let mut unified_annotations = Vec::new();
+
+    for shape in &file.shapes {
+        if shape.shape_type != "rectangle" && shape.shape_type != "polygon" {
+            continue;
+        }
+
+        let Some(extent) = points_extent(&shape.points) else {
+            continue;
+        };
+        unified_annotations.push(UnifiedAnnotation {
+            bbox: extent.to_vec(),
+            category_id: labels.id_for(&shape.label),
+            category_name: Some(labels.name(&shape.label).to_string()),
+            ..Default::default()
+        });
+    }
+
+    Ok(vec![UnifiedImage {
+        file_name: normalize_path(&file.image_path),
+        source_url: None,
+        height: file.image_height,
+        width: file.image_width,
+        annotations: unified_annotations,
+        ..Default::default()
+    }])
+}
+
+pub struct LabelMeParser;
+
+impl FormatParser for LabelMeParser {
+    fn name(&self) -> &'static str {
+        "labelme"
+    }
+
+    fn description(&self) -> &'static str {
+        "LabelMe format"
+    }
+
+    fn parse(&self, content: &str, _ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangles_and_polygons_become_boxes() {
+        let file = serde_json::json!({
+            "imagePath": "..\\images\\a.jpg",
+            "imageHeight": 48,
+            "imageWidth": 64,
+            "shapes": [
+                {"label": "car", "points": [[24, 14], [4, 4]], "shape_type": "rectangle"},
+                {"label": "dog ", "points": [[2, 3], [12, 3], [7, 9]]},
+                {"label": "car", "points": [[5, 5]], "shape_type": "point"},
+            ],
+        });
+        let images = parse(&file.to_string(), &mut LabelRegistry::default()).unwrap();
+
+        assert_eq!((images[0].file_name.as_str(), images[0].width, images[0].height), ("../images/a.jpg", 64, 48));
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.category_name.as_deref().unwrap(), annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [(0, "car", vec![4.0, 4.0, 24.0, 14.0]), (1, "dog", vec![2.0, 3.0, 12.0, 9.0])]);
+    }
+}
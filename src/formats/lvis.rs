@@ -0,0 +1,152 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{UnifiedAnnotation, UnifiedImage, download};
+
+// What to do with images whose labels are incomplete for some categories
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NotExhaustivePolicy {
+    /// Convert the image with the annotations it has
+    Keep,
+    /// Skip images listing any not_exhaustive_category_ids, since YOLO treats unlabeled objects as background
+    SkipImage,
+}
+
+#[derive(Debug, Deserialize)]
+struct LvisImage {
+    id: u64,
+    height: u32,
+    width: u32,
+    coco_url: String,
+    #[serde(default)]
+    not_exhaustive_category_ids: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LvisAnnotation {
+    image_id: u64,
+    category_id: u32,
+    bbox: Vec<f64>, // [x, y, width, height]
+}
+
+#[derive(Debug, Deserialize)]
+struct LvisCategory {
+    id: u32,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LvisDataset {
+    images: Vec<LvisImage>,
+    annotations: Vec<LvisAnnotation>,
+    categories: Vec<LvisCategory>,
+}
+
+// Parse an LVIS annotation file. Annotations keep their LVIS category id; the sparse ids are
+// numbered contiguously like any other source's. Images have no file_name; it is taken from coco_url.
+pub fn parse(content: &str, not_exhaustive: NotExhaustivePolicy, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let dataset: LvisDataset = serde_json::from_str(content)?;
+
+    let category_names: HashMap<u32, &str> = dataset.categories
+        .iter()
+        .map(|category| (category.id, labels.name(&category.name)))
+        .collect();
+
+    let mut annotations_by_image: HashMap<u64, Vec<&LvisAnnotation>> = HashMap::new();
+    for annotation in &dataset.annotations {
+        annotations_by_image.entry(annotation.image_id).or_default().push(annotation);
+    }
+
+    let mut unified_images = Vec::new();
+    let mut not_exhaustive_images = 0;
+    for image in &dataset.images {
+        if !image.not_exhaustive_category_ids.is_empty() {
+            not_exhaustive_images += 1;
+            if not_exhaustive == NotExhaustivePolicy::SkipImage {
+                continue;
+            }
+        }
+
+        let mut unified_annotations = Vec::new();
+        for annotation in annotations_by_image.get(&image.id).into_iter().flatten() {
+            let Some(&name) = category_names.get(&annotation.category_id) else {
+                anyhow::bail!("Annotation references unknown category {}", annotation.category_id);
+            };
+            let [x, y, w, h] = annotation.bbox[..] else {
+                anyhow::bail!("Annotation bbox must have 4 values");
+            };
+            unified_annotations.push(UnifiedAnnotation {
+                bbox: vec![x, y, x + w, y + h],
+                category_id: annotation.category_id,
+                category_name: Some(name.to_string()),
+                ..Default::default()
+            });
+        }
+
+        unified_images.push(UnifiedImage {
+            file_name: download::url_file_name(&image.coco_url).to_string(),
+            source_url: Some(image.coco_url.clone()),
+            image_id: Some(image.id.to_string()),
+            height: image.height,
+            width: image.width,
+            annotations: unified_annotations,
+            ..Default::default()
+        });
+    }
+
+    if not_exhaustive_images > 0 {
+        let action = match not_exhaustive {
+            NotExhaustivePolicy::Keep => "kept",
+            NotExhaustivePolicy::SkipImage => "skipped",
+        };
+        println!("LVIS: {} {} images with non-exhaustively annotated categories", action, not_exhaustive_images);
+    }
+
+    Ok(unified_images)
+}
+
+pub struct LvisParser {
+    pub not_exhaustive: NotExhaustivePolicy,
+}
+
+impl FormatParser for LvisParser {
+    fn name(&self) -> &'static str {
+        "lvis"
+    }
+
+    fn description(&self) -> &'static str {
+        "LVIS"
+    }
+
+    fn parse(&self, content: &str, _ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, self.not_exhaustive, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotations_keep_their_category_id() {
+        let dataset = serde_json::json!({
+            "images": [{"id": 1, "width": 64, "height": 48, "coco_url": "http://images.cocodataset.org/val2017/a.jpg"}],
+            "annotations": [
+                {"id": 1, "image_id": 1, "category_id": 7, "bbox": [4, 4, 20, 10]},
+                {"id": 2, "image_id": 1, "category_id": 3, "bbox": [30, 4, 10, 10]},
+            ],
+            "categories": [{"id": 7, "name": "dog"}, {"id": 3, "name": "cat"}],
+        });
+        let images = parse(&dataset.to_string(), NotExhaustivePolicy::Keep, &mut LabelRegistry::default()).unwrap();
+        assert_eq!(images[0].file_name, "a.jpg");
+        let categories: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.category_name.as_deref().unwrap()))
+            .collect();
+        assert_eq!(categories, [(7, "dog"), (3, "cat")]);
+        assert_eq!(images[0].annotations[0].bbox, [4.0, 4.0, 24.0, 14.0]);
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::UnifiedImage;
+use crate::input::InputSource;
+
+pub mod bdd100k;
+pub mod coco_panoptic;
+pub mod coco_results;
+pub mod createml;
+pub mod cvat;
+pub mod damm;
+pub mod datumaro;
+pub mod dota;
+pub mod kitti;
+pub mod label_studio;
+pub mod labelme;
+pub mod lvis;
+pub mod mot;
+pub mod open_images;
+pub mod sagemaker;
+pub mod standard;
+pub mod supervisely;
+pub mod via;
+pub mod yolo;
+
+// Assigns class ids to formats that label objects by name only.
+// Ids are handed out in order of first appearance and shared across all parsed files.
+#[derive(Debug, Default)]
+pub struct LabelRegistry {
+    ids: HashMap<String, u32>,
+    trimmed: HashSet<String>,
+}
+
+impl LabelRegistry {
+    // Class name with surrounding whitespace trimmed, so sloppy exports with "person " and "person"
+    // don't end up as two classes. Warns once per name that had whitespace.
+    pub fn name<'a>(&mut self, name: &'a str) -> &'a str {
+        let trimmed = name.trim();
+        if trimmed != name && self.trimmed.insert(name.to_string()) {
+            println!("Warning: trimmed whitespace from class name {:?}", name);
+        }
+        trimmed
+    }
+
+    pub fn id_for(&mut self, name: &str) -> u32 {
+        let name = self.name(name);
+        let next_id = self.ids.len() as u32;
+        *self.ids.entry(name.to_string()).or_insert(next_id)
+    }
+}
+
+// What a parser knows about the annotation file besides its content
+pub struct ParseContext<'a> {
+    pub path: &'a Path, // Entry name for .zip inputs
+    pub file_name: &'a str, // Without any .gz/.zst suffix
+    pub input: &'a InputSource,
+    pub read_image_size: bool,
+}
+
+impl ParseContext<'_> {
+    // Size of an image found by file name under --input, for formats that don't store sizes
+    pub fn image_size(&self, file_name: &str) -> Result<(u32, u32)> {
+        if !self.read_image_size {
+            anyhow::bail!("This format doesn't store image sizes; pass --read-image-size");
+        }
+        crate::read_image_size(self.input, file_name)
+    }
+
+    // Like image_size, for an image whose path is already known
+    pub fn image_size_at(&self, path: &Path) -> Result<(u32, u32)> {
+        if !self.read_image_size {
+            anyhow::bail!("This format doesn't store image sizes; pass --read-image-size");
+        }
+        self.input.image_size(path)
+    }
+
+    // Stem of the annotation file, for formats that name their images after it
+    pub fn file_stem(&self) -> Result<&str> {
+        Path::new(self.file_name).file_stem().and_then(|s| s.to_str()).context("Non-UTF8 label filename")
+    }
+}
+
+// One input annotation format. New formats implement this and are added to the registry built in
+// main, without changes to the conversion loop.
+pub trait FormatParser {
+    // Value of --format that selects this parser
+    fn name(&self) -> &'static str;
+
+    // Used in "Failed to parse as ..." errors
+    fn description(&self) -> &'static str;
+
+    // Whether a file found under --input holds annotations for this format; `file_name` has
+    // any compression suffix removed
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".json")
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>>;
+}
+
+// Parsers selectable with --format, in registration order
+#[derive(Default)]
+pub struct FormatRegistry {
+    parsers: Vec<Box<dyn FormatParser>>,
+}
+
+impl FormatRegistry {
+    pub fn register(&mut self, parser: impl FormatParser + 'static) {
+        self.parsers.push(Box::new(parser));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn FormatParser> {
+        self.parsers.iter().find(|parser| parser.name() == name).map(|parser| parser.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.parsers.iter().map(|parser| parser.name()).collect()
+    }
+}
+
+// Name of the directory directly containing `path`
+pub fn parent_dir_name(path: &Path) -> Option<&str> {
+    path.parent()?.file_name()?.to_str()
+}
+
+// Pick a JSON parser for --format auto from the file's structure. NDJSON files are judged by their
+// first record. Returns None when no supported layout matches, e.g. for an empty list.
+pub fn detect_json_format(content: &str, ndjson: bool) -> Option<&'static str> {
+    use serde_json::Value;
+
+    if ndjson {
+        let first_line = content.lines().find(|line| !line.trim().is_empty())?;
+        let record: Value = serde_json::from_str(first_line).ok()?;
+        return if record.get("image_id").is_some() && record.get("annotations").is_some() {
+            Some("damm")
+        } else if record.get("file_name").is_some() || record.get("categories").is_some() {
+            Some("standard")
+        } else {
+            None
+        };
+    }
+
+    let value: Value = serde_json::from_str(content).ok()?;
+    let has = |value: &Value, key: &str| value.get(key).is_some();
+    match &value {
+        Value::Object(_) if has(&value, "images") && has(&value, "annotations") => {
+            let is_panoptic = value["annotations"]
+                .as_array()
+                .and_then(|annotations| annotations.first())
+                .is_some_and(|annotation| has(annotation, "segments_info"));
+            if is_panoptic {
+                return Some("coco-panoptic");
+            }
+            // LVIS marks each category with its frequency bucket
+            let is_lvis = value["categories"]
+                .as_array()
+                .and_then(|categories| categories.first())
+                .is_some_and(|category| has(category, "frequency"));
+            Some(if is_lvis { "lvis" } else { "standard" })
+        }
+        Value::Object(_) if has(&value, "shapes") && has(&value, "imagePath") => Some("labelme"),
+        Value::Object(_) if has(&value, "items") && has(&value, "categories") => Some("datumaro"),
+        Value::Object(_) => {
+            // DAMM nests per-image annotation lists inside the top-level annotations array
+            let first_image = value["annotations"].as_array()?.first()?;
+            has(first_image, "annotations").then_some("damm")
+        }
+        Value::Array(records) => {
+            let first = records.first()?;
+            if has(first, "image") && has(first, "annotations") {
+                Some("createml")
+            } else if has(first, "data") && (has(first, "annotations") || has(first, "predictions")) {
+                Some("label-studio")
+            } else if has(first, "name") && has(first, "labels") {
+                Some("bdd100k")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+// Compressed annotation files are matched by the name they have once inflated
+pub const COMPRESSED_EXTENSIONS: [&str; 2] = [".gz", ".zst"];
+
+pub fn decompressed_name(file_name: &str) -> &str {
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .unwrap_or(file_name)
+}
+
+// Whether an annotation file holds one JSON record per line rather than a single document.
+// Besides the .jsonl/.ndjson extensions, a .json file counts when its first line is a complete object
+// and more lines follow; pretty-printed and single-line documents don't match.
+pub fn is_ndjson(path: &Path, content: &str) -> bool {
+    let file_name = decompressed_name(path.file_name().and_then(|s| s.to_str()).unwrap_or(""));
+    if file_name.ends_with(".jsonl") || file_name.ends_with(".ndjson") {
+        return true;
+    }
+    let mut lines = content.lines().map(str::trim).filter(|line| !line.is_empty());
+    let first_is_object = lines
+        .next()
+        .is_some_and(|line| matches!(serde_json::from_str(line), Ok(serde_json::Value::Object(_))));
+    first_is_object && lines.next().is_some()
+}
+
+// Deserialize each non-empty line of an NDJSON file, reporting the line number on failure
+pub fn parse_ndjson_lines<T: serde::de::DeserializeOwned>(content: &str) -> Result<Vec<T>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            serde_json::from_str(line).with_context(|| format!("Invalid record on line {}", line_number + 1))
+        })
+        .collect()
+}
+
+// YOLO-seg holds a single polygon per object; objects split into several parts keep the one with
+// the largest area. Polygons need at least three points.
+pub fn largest_polygon(polygons: &[Vec<f64>]) -> Option<Vec<f64>> {
+    let area = |polygon: &Vec<f64>| {
+        let points: Vec<&[f64]> = polygon.chunks_exact(2).collect();
+        let twice_area: f64 = points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+            .sum();
+        twice_area.abs()
+    };
+    polygons
+        .iter()
+        .filter(|polygon| polygon.len() >= 6)
+        .max_by(|a, b| area(a).total_cmp(&area(b)))
+        .cloned()
+}
+
+// Annotation tools written on Windows store paths with backslashes; normalize them so
+// the filename can be extracted on any platform.
+pub fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+// Axis-aligned [x1, y1, x2, y2] extent of a list of points, or None for an empty list
+pub fn points_extent(points: &[Vec<f64>]) -> Option<[f64; 4]> {
+    let mut points = points.iter().filter(|point| point.len() >= 2);
+    let first = points.next()?;
+    let mut extent = [first[0], first[1], first[0], first[1]];
+    for point in points {
+        extent[0] = extent[0].min(point[0]);
+        extent[1] = extent[1].min(point[1]);
+        extent[2] = extent[2].max(point[0]);
+        extent[3] = extent[3].max(point[1]);
+    }
+    Some(extent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_names_share_a_class() {
+        let mut labels = LabelRegistry::default();
+        let person = labels.id_for("person ");
+        assert_eq!(labels.id_for("person"), person);
+        assert_eq!(labels.id_for("\tperson"), person);
+        assert_ne!(labels.id_for("car"), person);
+        assert_eq!(labels.name(" person "), "person");
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, parent_dir_name};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+// MOT17/MOT20 ground-truth class numbers
+const MOT_CLASSES: [&str; 12] = [
+    "pedestrian",
+    "person_on_vehicle",
+    "car",
+    "bicycle",
+    "motorbike",
+    "non_motorized_vehicle",
+    "static_person",
+    "distractor",
+    "occluder",
+    "occluder_on_ground",
+    "occluder_full",
+    "reflection",
+];
+
+// Sequence settings from seqinfo.ini; sequences without one fall back to img1/*.jpg
+struct SeqInfo {
+    im_dir: String,
+    im_ext: String,
+    seq_length: Option<u32>,
+    size: Option<(u32, u32)>,
+}
+
+fn parse_seqinfo(content: Option<&str>) -> Result<SeqInfo> {
+    let mut info = SeqInfo { im_dir: "img1".to_string(), im_ext: ".jpg".to_string(), seq_length: None, size: None };
+    let Some(content) = content else {
+        return Ok(info);
+    };
+
+    let mut values = HashMap::new();
+    for line in content.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    let number = |key: &str| -> Result<Option<u32>> {
+        values.get(key)
+            .map(|value| value.parse().with_context(|| format!("Invalid {} in seqinfo.ini", key)))
+            .transpose()
+    };
+    if let Some(im_dir) = values.get("imDir") {
+        info.im_dir = im_dir.clone();
+    }
+    if let Some(im_ext) = values.get("imExt") {
+        info.im_ext = im_ext.clone();
+    }
+    info.seq_length = number("seqLength")?;
+    if let (Some(width), Some(height)) = (number("imWidth")?, number("imHeight")?) {
+        info.size = Some((width, height));
+    }
+    Ok(info)
+}
+
+// Sequence directory of a `<sequence>/gt/gt.txt` file, where seqinfo.ini and the frame directory live
+pub fn sequence_dir(gt_path: &Path) -> Result<&Path> {
+    gt_path.parent().and_then(Path::parent).context("gt.txt is not inside <sequence>/gt/")
+}
+
+// Parse one MOTChallenge `<sequence>/gt/gt.txt` (`frame, id, bb_left, bb_top, w, h, conf, class, visibility`).
+// Rows with conf 0 are ignore regions and are skipped; rows without a class column count as pedestrians.
+// Every frame of the sequence becomes an image named `<sequence>_<frame>` so sequences don't collide,
+// and each annotation keeps its track id. `seqinfo` is the sequence's seqinfo.ini, if it has one.
+pub fn parse(
+    content: &str,
+    gt_path: &Path,
+    seqinfo: Option<&str>,
+    image_size: &dyn Fn(&Path) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let seq_dir = sequence_dir(gt_path)?;
+    let seq_name = seq_dir.file_name().and_then(|s| s.to_str()).context("Non-UTF8 sequence directory")?;
+    let info = parse_seqinfo(seqinfo)?;
+
+    let mut frames: BTreeMap<u32, Vec<UnifiedAnnotation>> = BTreeMap::new();
+    if let Some(seq_length) = info.seq_length {
+        frames.extend((1..=seq_length).map(|frame| (frame, Vec::new())));
+    }
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 6 {
+            anyhow::bail!("Line {} has {} fields, expected at least 6", line_number + 1, fields.len());
+        }
+        let values = fields
+            .iter()
+            .map(|value| value.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid number on line {}", line_number + 1))?;
+
+        if values.get(6).is_some_and(|&conf| conf == 0.0) {
+            continue;
+        }
+        let class = values.get(7).map_or(1, |&class| if class >= 1.0 { class as u32 } else { 1 });
+
+        let (frame, track_id) = (values[0] as u32, values[1] as i64);
+        let (left, top, width, height) = (values[2], values[3], values[4], values[5]);
+        frames.entry(frame).or_default().push(UnifiedAnnotation {
+            bbox: vec![left, top, left + width, top + height],
+            category_id: class,
+            category_name: MOT_CLASSES.get(class as usize - 1).map(|name| name.to_string()),
+            track_id: Some(track_id),
+            ..Default::default()
+        });
+    }
+
+    let mut unified_images = Vec::new();
+    for (frame, annotations) in frames {
+        let frame_name = format!("{:06}{}", frame, info.im_ext);
+        let source_path = seq_dir.join(&info.im_dir).join(&frame_name);
+        let (width, height) = match info.size {
+            Some(size) => size,
+            None => image_size(&source_path)
+                .with_context(|| format!("Failed to determine the size of {}", source_path.display()))?,
+        };
+
+        unified_images.push(UnifiedImage {
+            file_name: format!("{}_{}", seq_name, frame_name),
+            height,
+            width,
+            annotations,
+            source_path: Some(source_path),
+            video_id: Some(seq_name.to_string()),
+            ..Default::default()
+        });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct MotParser;
+
+impl FormatParser for MotParser {
+    fn name(&self) -> &'static str {
+        "mot"
+    }
+
+    fn description(&self) -> &'static str {
+        "MOT ground truth"
+    }
+
+    // MOTChallenge sequences keep ground truth in <sequence>/gt/gt.txt, next to det/ and img1/
+    fn is_annotation_file(&self, path: &Path, file_name: &str) -> bool {
+        file_name == "gt.txt" && parent_dir_name(path) == Some("gt")
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, _labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        let seqinfo_path = sequence_dir(ctx.path)?.join("seqinfo.ini");
+        let seqinfo = if ctx.input.contains(&seqinfo_path) {
+            Some(ctx.input.read_annotation(&seqinfo_path)?)
+        } else {
+            None
+        };
+        parse(content, ctx.path, seqinfo.as_deref(), &|path| ctx.image_size_at(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_frame_becomes_an_image_with_tracked_boxes() {
+        let seqinfo = "[Sequence]\nname=MOT17-02\nimDir=img1\nseqLength=3\nimWidth=1920\nimHeight=1080\nimExt=.jpg\n";
+        let gt = "1,1,4,4,20,10,1,1,0.8\n\
+                  1,2,0,0,5,5,0,1,1.0\n\
+                  3,1,6,4,20,10,1,3,0.5\n";
+        let images = parse(gt, Path::new("train/MOT17-02/gt/gt.txt"), Some(seqinfo), &|_| unreachable!()).unwrap();
+
+        let names: Vec<_> = images.iter().map(|image| image.file_name.as_str()).collect();
+        assert_eq!(names, ["MOT17-02_000001.jpg", "MOT17-02_000002.jpg", "MOT17-02_000003.jpg"]);
+        assert_eq!((images[0].width, images[0].height), (1920, 1080));
+        assert_eq!(images[0].source_path.as_deref(), Some(Path::new("train/MOT17-02/img1/000001.jpg")));
+        assert_eq!(images[0].video_id.as_deref(), Some("MOT17-02"));
+
+        // The conf 0 ignore region is dropped
+        let first = &images[0].annotations;
+        assert_eq!(first.len(), 1);
+        assert_eq!((first[0].category_name.as_deref(), first[0].track_id), (Some("pedestrian"), Some(1)));
+        assert_eq!(first[0].bbox, [4.0, 4.0, 24.0, 14.0]);
+        assert!(images[1].annotations.is_empty());
+        assert_eq!(images[2].annotations[0].category_name.as_deref(), Some("car"));
+    }
+
+    #[test]
+    fn sequences_without_seqinfo_read_frame_sizes() {
+        let images = parse("2,7,4,4,20,10\n", Path::new("seq/gt/gt.txt"), None, &|_| Ok((64, 48))).unwrap();
+
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].file_name, "seq_000002.jpg");
+        assert_eq!((images[0].width, images[0].height), (64, 48));
+        assert_eq!((images[0].annotations[0].category_id, images[0].annotations[0].track_id), (1, Some(7)));
+    }
+}
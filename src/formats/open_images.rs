@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+// One row of a *-annotations-bbox.csv file; coordinates are normalized to [0, 1]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct OpenImagesBox {
+    #[serde(rename = "ImageID")]
+    image_id: String,
+    label_name: String,
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+}
+
+// Load the class description CSV (LabelName,DisplayName), with or without a header row
+pub fn load_class_descriptions(content: &str, path: &Path) -> Result<HashMap<String, String>> {
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).from_reader(content.as_bytes());
+
+    let mut descriptions = HashMap::new();
+    for record in reader.records() {
+        let record = record.with_context(|| format!("Invalid class descriptions CSV: {}", path.display()))?;
+        if let (Some(label_name), Some(display_name)) = (record.get(0), record.get(1)) {
+            if label_name == "LabelName" {
+                continue;
+            }
+            descriptions.insert(label_name.to_string(), display_name.to_string());
+        }
+    }
+
+    Ok(descriptions)
+}
+
+// Parse an Open Images bbox annotation CSV. LabelName MIDs are translated to display names via
+// `descriptions` (falling back to the MID) and indexed into classes. Images are `<ImageID>.jpg`;
+// `image_size` supplies their dimensions since the CSV only stores normalized coordinates.
+pub fn parse(
+    content: &str,
+    labels: &mut LabelRegistry,
+    descriptions: &HashMap<String, String>,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+
+    // Group boxes by image, keeping a stable order
+    let mut boxes_by_image: BTreeMap<String, Vec<OpenImagesBox>> = BTreeMap::new();
+    for row in reader.deserialize() {
+        let row: OpenImagesBox = row?;
+        boxes_by_image.entry(row.image_id.clone()).or_default().push(row);
+    }
+
+    let mut unified_images = Vec::new();
+    for (image_id, boxes) in boxes_by_image {
+        let file_name = format!("{}.jpg", image_id);
+        let (width, height) = image_size(&file_name)
+            .with_context(|| format!("Failed to determine the size of {}", file_name))?;
+        let (img_width, img_height) = (width as f64, height as f64);
+
+        let unified_annotations = boxes
+            .iter()
+            .map(|row| {
+                let name = descriptions.get(&row.label_name).unwrap_or(&row.label_name);
+                UnifiedAnnotation {
+                    bbox: vec![row.x_min * img_width, row.y_min * img_height, row.x_max * img_width, row.y_max * img_height],
+                    category_id: labels.id_for(name),
+                    category_name: Some(labels.name(name).to_string()),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        unified_images.push(UnifiedImage { file_name, source_url: None, height, width, annotations: unified_annotations, ..Default::default() });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct OpenImagesParser {
+    pub descriptions: HashMap<String, String>,
+}
+
+impl FormatParser for OpenImagesParser {
+    fn name(&self) -> &'static str {
+        "open-images"
+    }
+
+    fn description(&self) -> &'static str {
+        "Open Images CSV"
+    }
+
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        file_name.ends_with("-annotations-bbox.csv")
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels, &self.descriptions, &|file_name| ctx.image_size(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mids_are_translated_and_coordinates_scaled() {
+        let descriptions = load_class_descriptions("LabelName,DisplayName\n/m/01g317,Person\n", Path::new("classes.csv")).unwrap();
+        assert_eq!(descriptions.len(), 1);
+
+        let csv = "ImageID,Source,LabelName,Confidence,XMin,XMax,YMin,YMax\n\
+                   b,xclick,/m/0k4j,1,0.5,1,0.5,1\n\
+                   a,xclick,/m/01g317,1,0.25,0.5,0,0.5\n";
+        let images = parse(csv, &mut LabelRegistry::default(), &descriptions, &|_| Ok((200, 100))).unwrap();
+
+        let names: Vec<_> = images.iter().map(|image| image.file_name.as_str()).collect();
+        assert_eq!(names, ["a.jpg", "b.jpg"]);
+        assert_eq!(images[0].annotations[0].category_name.as_deref(), Some("Person"));
+        assert_eq!(images[0].annotations[0].bbox, [50.0, 0.0, 100.0, 50.0]);
+        // MIDs without a description are used as the class name
+        assert_eq!(images[1].annotations[0].category_name.as_deref(), Some("/m/0k4j"));
+        assert_eq!(images[1].annotations[0].bbox, [100.0, 50.0, 200.0, 100.0]);
+    }
+}
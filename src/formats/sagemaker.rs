@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext};
+use crate::{UnifiedAnnotation, UnifiedImage, split_image_url};
+
+#[derive(Debug, Deserialize)]
+struct GroundTruthBox {
+    class_id: u32,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroundTruthImageSize {
+    width: u32,
+    height: u32,
+}
+
+// The labeling job's output attribute: boxes plus the image size
+#[derive(Debug, Deserialize)]
+struct GroundTruthLabel {
+    annotations: Vec<GroundTruthBox>,
+    image_size: Vec<GroundTruthImageSize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GroundTruthMetadata {
+    #[serde(rename = "class-map", default)]
+    class_map: HashMap<String, String>,
+}
+
+// Parse an augmented manifest (one JSON record per line). The label attribute is named after the
+// labeling job, so it is detected as the first object holding `annotations` and `image_size`;
+// class names come from the matching `<attribute>-metadata` class-map.
+pub fn parse(content: &str, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let mut unified_images = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: serde_json::Map<String, serde_json::Value> = serde_json::from_str(line)
+            .with_context(|| format!("Invalid JSON on line {}", line_number + 1))?;
+
+        let source_ref = record.get("source-ref")
+            .and_then(|value| value.as_str())
+            .with_context(|| format!("Record on line {} has no source-ref", line_number + 1))?;
+
+        let Some((attribute, label)) = record.iter().find_map(|(key, value)| {
+            let label = value.as_object()
+                .filter(|object| object.contains_key("annotations") && object.contains_key("image_size"))?;
+            Some((key, label))
+        }) else {
+            anyhow::bail!("Record on line {} has no object-detection label attribute", line_number + 1);
+        };
+
+        let label: GroundTruthLabel = serde_json::from_value(serde_json::Value::Object(label.clone()))
+            .with_context(|| format!("Invalid '{}' label on line {}", attribute, line_number + 1))?;
+        let metadata: GroundTruthMetadata = match record.get(&format!("{}-metadata", attribute)) {
+            Some(value) => serde_json::from_value(value.clone())
+                .with_context(|| format!("Invalid '{}-metadata' on line {}", attribute, line_number + 1))?,
+            None => GroundTruthMetadata::default(),
+        };
+        let image_size = label.image_size
+            .first()
+            .with_context(|| format!("Empty image_size on line {}", line_number + 1))?;
+
+        let unified_annotations = label.annotations
+            .iter()
+            .map(|annotation| UnifiedAnnotation {
+                bbox: vec![
+                    annotation.left,
+                    annotation.top,
+                    annotation.left + annotation.width,
+                    annotation.top + annotation.height,
+                ],
+                category_id: annotation.class_id,
+                category_name: metadata.class_map.get(&annotation.class_id.to_string()).map(|name| labels.name(name).to_string()),
+                ..Default::default()
+            })
+            .collect();
+
+        // source-ref is usually an s3:// URI; keep just the object's filename
+        let (file_name, source_url) = if source_ref.starts_with("s3://") {
+            (source_ref.rsplit('/').next().unwrap_or(source_ref).to_string(), None)
+        } else {
+            split_image_url(source_ref.to_string(), None)
+        };
+        unified_images.push(UnifiedImage {
+            file_name,
+            source_url,
+            height: image_size.height,
+            width: image_size.width,
+            annotations: unified_annotations,
+            ..Default::default()
+        });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct SageMakerParser;
+
+impl FormatParser for SageMakerParser {
+    fn name(&self) -> &'static str {
+        "sagemaker"
+    }
+
+    fn description(&self) -> &'static str {
+        "SageMaker Ground Truth manifest"
+    }
+
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".manifest") || file_name.ends_with(".jsonl")
+    }
+
+    fn parse(&self, content: &str, _ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_attribute_is_found_whatever_the_job_is_named() {
+        let record = serde_json::json!({
+            "source-ref": "s3://bucket/frames/a.jpg",
+            "my-job": {
+                "annotations": [{"class_id": 3, "left": 4, "top": 4, "width": 20, "height": 10}],
+                "image_size": [{"width": 64, "height": 48, "depth": 3}],
+            },
+            "my-job-metadata": {"class-map": {"3": "car "}, "type": "groundtruth/object-detection"},
+        });
+        let manifest = format!("{}\n\n{}\n", record, record.to_string().replace("a.jpg", "b.jpg"));
+        let images = parse(&manifest, &mut LabelRegistry::default()).unwrap();
+
+        let names: Vec<_> = images.iter().map(|image| image.file_name.as_str()).collect();
+        assert_eq!(names, ["a.jpg", "b.jpg"]);
+        assert_eq!(images[0].source_url, None);
+        assert_eq!((images[0].width, images[0].height), (64, 48));
+        let annotation = &images[0].annotations[0];
+        assert_eq!((annotation.category_id, annotation.category_name.as_deref()), (3, Some("car")));
+        assert_eq!(annotation.bbox, [4.0, 4.0, 24.0, 14.0]);
+    }
+}
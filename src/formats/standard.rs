@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, is_ndjson, largest_polygon, parse_ndjson_lines};
+use crate::{CocoAnnotation, CocoAnnotationFields, CocoCategory, CocoDataset, CocoId, CocoImageInfo, UnifiedAnnotation, UnifiedImage, mask, split_image_url};
+
+// One line of a standard NDJSON export: either a categories record or an image with its annotations nested
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CocoNdjsonRecord {
+    Categories { categories: Vec<CocoCategory> },
+    Image(CocoNdjsonImage),
+}
+
+#[derive(Debug, Deserialize)]
+struct CocoNdjsonImage {
+    #[serde(default)]
+    id: Option<CocoId>,
+    file_name: String,
+    height: u32,
+    width: u32,
+    #[serde(default)]
+    coco_url: Option<String>,
+    #[serde(default)]
+    video_id: Option<CocoId>,
+    // Nested annotations don't need their own ids; they belong to the enclosing image
+    #[serde(default)]
+    annotations: Vec<CocoAnnotationFields>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+pub fn parse(content: &str, mask_tolerance: Option<f64>, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    convert_standard_dataset(serde_json::from_str(content)?, mask_tolerance, labels)
+}
+
+// Standard NDJSON: image records with nested annotations, plus optional categories records.
+// Images keep their own ids when they have one and are numbered by record order otherwise; nested
+// annotations don't carry ids, so they are always numbered.
+pub fn parse_ndjson(content: &str, mask_tolerance: Option<f64>, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let mut dataset = CocoDataset { images: Vec::new(), annotations: Vec::new(), categories: None };
+    for record in parse_ndjson_lines(content)? {
+        match record {
+            CocoNdjsonRecord::Categories { categories } => {
+                dataset.categories.get_or_insert_with(Vec::new).extend(categories);
+            }
+            CocoNdjsonRecord::Image(image) => {
+                let image_id = image.id.unwrap_or(CocoId::Int(dataset.images.len() as u64));
+                for fields in image.annotations {
+                    let id = CocoId::Int(dataset.annotations.len() as u64);
+                    dataset.annotations.push(CocoAnnotation { id, image_id: image_id.clone(), fields });
+                }
+                dataset.images.push(CocoImageInfo {
+                    id: image_id,
+                    file_name: image.file_name,
+                    height: image.height,
+                    width: image.width,
+                    coco_url: image.coco_url,
+                    video_id: image.video_id,
+                    extra: image.extra,
+                });
+            }
+        }
+    }
+    convert_standard_dataset(dataset, mask_tolerance, labels)
+}
+
+// `mask_tolerance` enables tracing RLE masks into polygons, simplified to that many pixels
+pub struct StandardParser {
+    pub mask_tolerance: Option<f64>,
+}
+
+impl FormatParser for StandardParser {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+
+    fn description(&self) -> &'static str {
+        "standard COCO format"
+    }
+
+    // Standard records may also come as NDJSON, one record per line
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        [".json", ".jsonl", ".ndjson"].iter().any(|ext| file_name.ends_with(ext))
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        if is_ndjson(ctx.path, content) {
+            parse_ndjson(content, self.mask_tolerance, labels)
+        } else {
+            parse(content, self.mask_tolerance, labels)
+        }
+    }
+}
+
+fn convert_standard_dataset(dataset: CocoDataset, mask_tolerance: Option<f64>, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let mut unified_images = Vec::new();
+    
+    // Create a map of category_id to category name
+    let mut category_names: HashMap<u32, &str> = HashMap::new();
+    let mut supercategories: HashMap<u32, &str> = HashMap::new();
+    for category in dataset.categories.iter().flatten() {
+        if let Some(supercategory) = &category.supercategory {
+            supercategories.insert(category.id, labels.name(supercategory));
+        }
+        category_names.insert(category.id, labels.name(&category.name));
+    }
+    
+    // Create a map of image_id to image info
+    let mut image_map: HashMap<&CocoId, &CocoImageInfo> = HashMap::new();
+    for image in &dataset.images {
+        image_map.insert(&image.id, image);
+    }
+    
+    // Group annotations by image_id
+    let mut annotations_by_image: HashMap<&CocoId, Vec<&CocoAnnotation>> = HashMap::new();
+    for annotation in &dataset.annotations {
+        annotations_by_image.entry(&annotation.image_id)
+            .or_default()
+            .push(annotation);
+    }
+    
+    // Convert to unified format
+    for (image_id, image_info) in image_map {
+        let mut unified_annotations = Vec::new();
+        
+        if let Some(annotations) = annotations_by_image.get(&image_id) {
+            for coco_ann in annotations {
+                // Convert COCO [x, y, width, height] to unified [x1, y1, x2, y2]
+                let x1 = coco_ann.fields.bbox[0];
+                let y1 = coco_ann.fields.bbox[1];
+                let x2 = x1 + coco_ann.fields.bbox[2];
+                let y2 = y1 + coco_ann.fields.bbox[3];
+                
+                // Polygon segmentations are a list of flat point lists; RLE masks (usually iscrowd) are objects
+                let mut segmentation = None;
+                if let Some(value) = &coco_ann.fields.segmentation {
+                    if let Ok(polygons) = serde_json::from_value::<Vec<Vec<f64>>>(value.clone()) {
+                        segmentation = largest_polygon(&polygons);
+                    } else if let Some(tolerance) = mask_tolerance {
+                        let rle: mask::Rle = serde_json::from_value(value.clone())
+                            .with_context(|| format!("Invalid segmentation in annotation {:?}", coco_ann.id))?;
+                        let decoded = mask::decode_rle(&rle)
+                            .with_context(|| format!("Invalid RLE mask in annotation {:?}", coco_ann.id))?;
+                        segmentation = mask::mask_to_polygon(&decoded, tolerance);
+                    }
+                }
+                let unified_ann = UnifiedAnnotation {
+                    bbox: vec![x1, y1, x2, y2],
+                    category_id: coco_ann.fields.category_id,
+                    category_name: category_names.get(&coco_ann.fields.category_id).map(|name| name.to_string()),
+                    supercategory: supercategories.get(&coco_ann.fields.category_id).map(|name| name.to_string()),
+                    iscrowd: coco_ann.fields.iscrowd != 0,
+                    segmentation,
+                    keypoints: coco_ann.fields.keypoints.clone(),
+                    metadata: coco_ann.fields.extra.clone(),
+                    ..Default::default()
+                };
+                unified_annotations.push(unified_ann);
+            }
+        }
+        
+        let (file_name, source_url) = split_image_url(image_info.file_name.clone(), image_info.coco_url.clone());
+        let unified_image = UnifiedImage {
+            file_name,
+            source_url,
+            height: image_info.height,
+            width: image_info.width,
+            annotations: unified_annotations,
+            image_id: Some(image_info.id.to_string()),
+            video_id: image_info.video_id.as_ref().map(CocoId::to_string),
+            metadata: image_info.extra.clone(),
+            ..Default::default()
+        };
+        unified_images.push(unified_image);
+    }
+    
+    Ok(unified_images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padded_coco_category_names_are_trimmed() {
+        let dataset = serde_json::json!({
+            "images": [{"id": 1, "file_name": "a.jpg", "width": 64, "height": 48}],
+            "annotations": [{"id": 1, "image_id": 1, "category_id": 1, "bbox": [4, 4, 20, 10], "area": 200, "iscrowd": 0}],
+            "categories": [{"id": 1, "name": " person "}],
+        });
+        let images = parse(&dataset.to_string(), None, &mut LabelRegistry::default()).unwrap();
+        assert_eq!(images[0].annotations[0].category_name.as_deref(), Some("person"));
+    }
+
+    #[test]
+    fn ndjson_annotations_keep_crowd_flags_and_segmentations() {
+        let lines = [
+            serde_json::json!({"categories": [{"id": 1, "name": "person"}]}),
+            serde_json::json!({"file_name": "a.jpg", "height": 48, "width": 64, "annotations": [
+                {"category_id": 1, "bbox": [4, 4, 20, 10], "iscrowd": 1, "segmentation": [[4, 4, 24, 4, 24, 14]], "score": 0.5},
+            ]}),
+        ];
+        let content: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        let images = parse_ndjson(&content, None, &mut LabelRegistry::default()).unwrap();
+
+        let annotation = &images[0].annotations[0];
+        assert!(annotation.iscrowd);
+        assert_eq!(annotation.category_name.as_deref(), Some("person"));
+        assert_eq!(annotation.bbox, [4.0, 4.0, 24.0, 14.0]);
+        assert!(annotation.segmentation.is_some());
+        assert_eq!(annotation.metadata.get("score"), Some(&serde_json::json!(0.5)));
+    }
+
+    #[test]
+    fn ndjson_images_keep_their_ids_and_fields() {
+        let lines = [
+            serde_json::json!({"id": "frame-7", "file_name": "a.jpg", "height": 48, "width": 64, "video_id": 3,
+                               "date_captured": "2024-05-01 12:00:00", "annotations": [{"category_id": 1, "bbox": [4, 4, 20, 10]}]}),
+            serde_json::json!({"file_name": "b.jpg", "height": 48, "width": 64}),
+        ];
+        let content: String = lines.iter().map(|line| format!("{}\n", line)).collect();
+        let mut images = parse_ndjson(&content, None, &mut LabelRegistry::default()).unwrap();
+        images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+        assert_eq!((images[0].image_id.as_deref(), images[0].video_id.as_deref()), (Some("frame-7"), Some("3")));
+        assert_eq!(images[0].annotations.len(), 1);
+        assert_eq!(images[0].metadata.get("date_captured"), Some(&serde_json::json!("2024-05-01 12:00:00")));
+        assert_eq!(images[1].image_id.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn large_and_string_ids_match_annotations_to_images() {
+        let dataset = serde_json::json!({
+            "images": [
+                {"id": 5_000_000_000u64, "file_name": "a.jpg", "width": 64, "height": 48},
+                {"id": "img-b", "file_name": "b.jpg", "width": 64, "height": 48},
+            ],
+            "annotations": [
+                {"id": 7_000_000_000u64, "image_id": 5_000_000_000u64, "category_id": 1, "bbox": [4, 4, 20, 10], "area": 200},
+                {"id": "ann-b", "image_id": "img-b", "category_id": 1, "bbox": [8, 8, 10, 10], "area": 100},
+            ],
+            "categories": [{"id": 1, "name": "person"}],
+        });
+        let mut images = parse(&dataset.to_string(), None, &mut LabelRegistry::default()).unwrap();
+        images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+        let ids: Vec<_> = images.iter().map(|image| (image.image_id.as_deref().unwrap(), image.annotations.len())).collect();
+        assert_eq!(ids, [("5000000000", 1), ("img-b", 1)]);
+        assert_eq!(images[1].annotations[0].bbox, [8.0, 8.0, 18.0, 18.0]);
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{FormatParser, LabelRegistry, ParseContext, parent_dir_name, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage};
+
+#[derive(Debug, Deserialize)]
+struct SuperviselyClass {
+    title: String,
+}
+
+// Project-level meta.json listing the classes in project order
+#[derive(Debug, Deserialize)]
+struct SuperviselyMeta {
+    classes: Vec<SuperviselyClass>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SuperviselySize {
+    height: u32,
+    width: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SuperviselyPoints {
+    exterior: Vec<Vec<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SuperviselyObject {
+    class_title: String,
+    geometry_type: String,
+    #[serde(default)]
+    points: Option<SuperviselyPoints>,
+}
+
+// Per-image annotation file, stored as <dataset>/ann/<image name>.json
+#[derive(Debug, Deserialize)]
+struct SuperviselyAnnotation {
+    size: SuperviselySize,
+    objects: Vec<SuperviselyObject>,
+}
+
+// Register the classes from meta.json so class ids follow the project's class order
+pub fn load_meta(content: &str, path: &Path, labels: &mut LabelRegistry) -> Result<usize> {
+    let meta: SuperviselyMeta = serde_json::from_str(content)
+        .with_context(|| format!("Failed to parse Supervisely meta: {}", path.display()))?;
+
+    for class in &meta.classes {
+        labels.id_for(&class.title);
+    }
+    Ok(meta.classes.len())
+}
+
+// Parse one Supervisely annotation file. Rectangles and the exterior of polygons become boxes;
+// bitmaps and other geometries are skipped. The image name is the annotation filename minus ".json".
+pub fn parse(content: &str, ann_file_name: &str, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+    let annotation: SuperviselyAnnotation = serde_json::from_str(content)?;
+    let mut unified_annotations = Vec::new();
+
+    for object in &annotation.objects {
+        if object.geometry_type != "rectangle" && object.geometry_type != "polygon" {
+            continue;
+        }
+        let Some(extent) = object.points.as_ref().and_then(|points| points_extent(&points.exterior)) else {
+            continue;
+        };
+
+        unified_annotations.push(UnifiedAnnotation {
+            bbox: extent.to_vec(),
+            category_id: labels.id_for(&object.class_title),
+            category_name: Some(labels.name(&object.class_title).to_string()),
+            ..Default::default()
+        });
+    }
+
+    let file_name = ann_file_name.strip_suffix(".json").unwrap_or(ann_file_name).to_string();
+    Ok(vec![UnifiedImage {
+        file_name,
+        source_url: None,
+        height: annotation.size.height,
+        width: annotation.size.width,
+        annotations: unified_annotations,
+        ..Default::default()
+    }])
+}
+
+pub struct SuperviselyParser;
+
+impl FormatParser for SuperviselyParser {
+    fn name(&self) -> &'static str {
+        "supervisely"
+    }
+
+    fn description(&self) -> &'static str {
+        "Supervisely annotation"
+    }
+
+    // Supervisely keeps one <image>.json per image under <dataset>/ann/, next to meta.json
+    fn is_annotation_file(&self, path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".json") && parent_dir_name(path) == Some("ann")
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, ctx.file_name, labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_classes_come_first_and_shapes_become_boxes() {
+        let mut labels = LabelRegistry::default();
+        let meta = r#"{"classes": [{"title": "person", "shape": "polygon"}, {"title": "car", "shape": "rectangle"}]}"#;
+        assert_eq!(load_meta(meta, Path::new("meta.json"), &mut labels).unwrap(), 2);
+
+        let annotation = serde_json::json!({
+            "size": {"height": 48, "width": 64},
+            "objects": [
+                {"classTitle": "car", "geometryType": "rectangle", "points": {"exterior": [[4, 4], [24, 14]], "interior": []}},
+                {"classTitle": "person", "geometryType": "polygon", "points": {"exterior": [[2, 3], [12, 3], [7, 9]], "interior": []}},
+                {"classTitle": "person", "geometryType": "bitmap", "bitmap": {"data": "", "origin": [0, 0]}},
+            ],
+        });
+        let images = parse(&annotation.to_string(), "a.jpg.json", &mut labels).unwrap();
+
+        assert_eq!((images[0].file_name.as_str(), images[0].width, images[0].height), ("a.jpg", 64, 48));
+        let annotations: Vec<_> = images[0].annotations
+            .iter()
+            .map(|annotation| (annotation.category_id, annotation.bbox.clone()))
+            .collect();
+        assert_eq!(annotations, [(1, vec![4.0, 4.0, 24.0, 14.0]), (0, vec![2.0, 3.0, 12.0, 9.0])]);
+    }
+}
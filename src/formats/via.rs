@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::{FormatParser, LabelRegistry, ParseContext, points_extent};
+use crate::{UnifiedAnnotation, UnifiedImage, split_image_url};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+enum ViaShape {
+    Rect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    Polygon {
+        all_points_x: Vec<f64>,
+        all_points_y: Vec<f64>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ViaRegion {
+    shape_attributes: ViaShape,
+    #[serde(default)]
+    region_attributes: BTreeMap<String, serde_json::Value>,
+}
+
+// VIA 2.x stores regions as a list, VIA 1.x as an object keyed by index
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ViaRegions {
+    List(Vec<ViaRegion>),
+    Map(BTreeMap<String, ViaRegion>),
+}
+
+#[derive(Debug, Deserialize)]
+struct ViaImage {
+    filename: String,
+    regions: ViaRegions,
+    #[serde(default)]
+    file_attributes: BTreeMap<String, serde_json::Value>,
+}
+
+// Either a bare via_region_data.json or a full project file with the same data under _via_img_metadata
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ViaFile {
+    Project {
+        #[serde(rename = "_via_img_metadata")]
+        img_metadata: BTreeMap<String, ViaImage>,
+    },
+    RegionData(BTreeMap<String, ViaImage>),
+}
+
+// Class name of a region: the configured attribute, or the only attribute if there is just one.
+// Dropdown/radio attributes are strings; checkbox attributes are objects of {option: true}.
+fn region_label(region: &ViaRegion, label_attribute: &str) -> Option<String> {
+    let value = region.region_attributes.get(label_attribute).or_else(|| {
+        (region.region_attributes.len() == 1).then(|| region.region_attributes.values().next()).flatten()
+    })?;
+
+    match value {
+        serde_json::Value::String(label) if !label.trim().is_empty() => Some(label.trim().to_string()),
+        serde_json::Value::Object(options) => options
+            .iter()
+            .find(|(_, checked)| checked.as_bool() == Some(true))
+            .map(|(label, _)| label.clone()),
+        _ => None,
+    }
+}
+
+// Image size stored as width/height file attributes, as some exporters add; VIA itself only
+// records the file size in bytes. Attribute values may be numbers or numeric strings.
+fn stored_size(file_attributes: &BTreeMap<String, serde_json::Value>) -> Option<(u32, u32)> {
+    let dimension = |key: &str| match file_attributes.get(key)? {
+        serde_json::Value::Number(value) => value.as_u64().and_then(|value| u32::try_from(value).ok()),
+        serde_json::Value::String(value) => value.trim().parse().ok(),
+        _ => None,
+    };
+    Some((dimension("width")?, dimension("height")?))
+}
+
+// Parse a VGG Image Annotator export. Images without a stored size ask `image_size` for their
+// (width, height), typically by reading the image header.
+pub fn parse(
+    content: &str,
+    labels: &mut LabelRegistry,
+    label_attribute: &str,
+    image_size: &dyn Fn(&str) -> Result<(u32, u32)>,
+) -> Result<Vec<UnifiedImage>> {
+    let images = match serde_json::from_str(content)? {
+        ViaFile::Project { img_metadata } => img_metadata,
+        ViaFile::RegionData(images) => images,
+    };
+    let mut unified_images = Vec::new();
+
+    for image in images.into_values() {
+        let regions = match image.regions {
+            ViaRegions::List(regions) => regions,
+            ViaRegions::Map(regions) => regions.into_values().collect(),
+        };
+
+        let mut unified_annotations = Vec::new();
+        for region in &regions {
+            let extent = match &region.shape_attributes {
+                ViaShape::Rect { x, y, width, height } => Some([*x, *y, x + width, y + height]),
+                ViaShape::Polygon { all_points_x, all_points_y } => {
+                    let points: Vec<Vec<f64>> = all_points_x.iter().zip(all_points_y).map(|(x, y)| vec![*x, *y]).collect();
+                    points_extent(&points)
+                }
+                ViaShape::Other => None,
+            };
+
+            let (Some(extent), Some(label)) = (extent, region_label(region, label_attribute)) else {
+                continue;
+            };
+            unified_annotations.push(UnifiedAnnotation {
+                bbox: extent.to_vec(),
+                category_id: labels.id_for(&label),
+                category_name: Some(labels.name(&label).to_string()),
+                ..Default::default()
+            });
+        }
+
+        let (width, height) = match stored_size(&image.file_attributes) {
+            Some(size) => size,
+            None => image_size(&image.filename)
+                .with_context(|| format!("Failed to determine the size of {}", image.filename))?,
+        };
+        let (file_name, source_url) = split_image_url(image.filename, None);
+        unified_images.push(UnifiedImage { file_name, source_url, height, width, annotations: unified_annotations, ..Default::default() });
+    }
+
+    Ok(unified_images)
+}
+
+pub struct ViaParser {
+    pub label_attribute: String,
+}
+
+impl FormatParser for ViaParser {
+    fn name(&self) -> &'static str {
+        "via"
+    }
+
+    fn description(&self) -> &'static str {
+        "VIA project"
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, labels, &self.label_attribute, &|file_name| ctx.image_size(file_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"{"_via_img_metadata": {
+        "a.jpg1234": {"filename": "a.jpg", "size": 1234, "file_attributes": {"width": "64", "height": 48}, "regions": [
+            {"shape_attributes": {"name": "rect", "x": 4, "y": 4, "width": 20, "height": 10}, "region_attributes": {"label": "car"}}
+        ]},
+        "b.jpg99": {"filename": "b.jpg", "size": 99, "regions": {
+            "0": {"shape_attributes": {"name": "polygon", "all_points_x": [2, 12, 7], "all_points_y": [3, 3, 9]}, "region_attributes": {"label": " dog "}}
+        }}
+    }}"#;
+
+    #[test]
+    fn stored_sizes_are_used_and_missing_ones_read() {
+        let requested = std::cell::RefCell::new(Vec::new());
+        let image_size = |file_name: &str| {
+            requested.borrow_mut().push(file_name.to_string());
+            Ok((32, 24))
+        };
+        let images = parse(PROJECT, &mut LabelRegistry::default(), "label", &image_size).unwrap();
+
+        assert_eq!(requested.into_inner(), ["b.jpg"]);
+        let sizes: Vec<_> = images.iter().map(|image| (image.file_name.as_str(), image.width, image.height)).collect();
+        assert_eq!(sizes, [("a.jpg", 64, 48), ("b.jpg", 32, 24)]);
+        assert_eq!(images[0].annotations[0].bbox, [4.0, 4.0, 24.0, 14.0]);
+        assert_eq!(images[1].annotations[0].bbox, [2.0, 3.0, 12.0, 9.0]);
+        assert_eq!(images[1].annotations[0].category_name.as_deref(), Some("dog"));
+    }
+}
@@ -0,0 +1,255 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::{FormatParser, LabelRegistry, ParseContext, parent_dir_name, points_extent};
+use crate::{IMAGE_EXTENSIONS, UnifiedAnnotation, UnifiedImage};
+
+// Line of a classes.txt that reserves a class id without a class
+const EMPTY_CLASS_SLOT: &str = "-";
+
+// Parse a classes.txt: one class name per line, in class id order. Names are trimmed and trailing
+// blank lines ignored; blank lines in between and duplicate names are errors since they would
+// shift or merge class ids. A line of just "-" is an empty slot (None), which no category matches.
+pub fn parse_class_names(content: &str) -> Result<Vec<Option<String>>> {
+    let mut lines: Vec<&str> = content.lines().map(str::trim).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let mut seen = HashSet::new();
+    let mut names = Vec::with_capacity(lines.len());
+    for (line_number, line) in lines.into_iter().enumerate() {
+        if line.is_empty() {
+            anyhow::bail!(
+                "Blank class name on line {}; mark an empty class slot with \"{}\"",
+                line_number + 1, EMPTY_CLASS_SLOT
+            );
+        }
+        if line == EMPTY_CLASS_SLOT {
+            names.push(None);
+            continue;
+        }
+        if !seen.insert(line) {
+            anyhow::bail!("Duplicate class name {:?} on line {}", line, line_number + 1);
+        }
+        names.push(Some(line.to_string()));
+    }
+
+    Ok(names)
+}
+
+// Class names as written to the output, empty slots named class_<id> like other ids without a class
+pub fn slot_names(names: &[Option<String>]) -> Vec<String> {
+    names.iter()
+        .enumerate()
+        .map(|(id, name)| name.clone().unwrap_or_else(|| format!("class_{}", id)))
+        .collect()
+}
+
+// Image for a label file: <split>/labels/x.txt pairs with <split>/images/x.<ext>,
+// flat layouts keep x.<ext> next to x.txt
+fn find_image(label_path: &Path, ctx: &ParseContext) -> Option<PathBuf> {
+    let label_dir = label_path.parent()?;
+    let stem = label_path.file_stem()?.to_str()?;
+    let mut dirs = vec![label_dir.to_path_buf()];
+    if parent_dir_name(label_path) == Some("labels") {
+        dirs.insert(0, label_dir.parent()?.join("images"));
+    }
+
+    dirs.iter()
+        .flat_map(|dir| IMAGE_EXTENSIONS.iter().map(move |ext| dir.join(format!("{}.{}", stem, ext))))
+        .find(|path| ctx.input.contains(path))
+}
+
+// Parse one YOLO label file. Lines with 4 coordinates are boxes; 6 or more are polygons whose
+// extent becomes the box, and 8 coordinates also count as an oriented box. Coordinates are normalized,
+// so the paired image's size is always read. Files without an image are skipped: with a warning
+// under labels/, silently elsewhere since those are other text files rather than labels.
+pub fn parse(content: &str, ctx: &ParseContext, names: &[Option<String>]) -> Result<Vec<UnifiedImage>> {
+    let Some(image_path) = find_image(ctx.path, ctx) else {
+        if parent_dir_name(ctx.path) == Some("labels") {
+            println!("Warning: no image for {}; skipping it", ctx.path.display());
+        }
+        return Ok(Vec::new());
+    };
+    let (width, height) = ctx.input.image_size(&image_path)?;
+    let (w, h) = (width as f64, height as f64);
+
+    let mut unified_annotations = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some((class, coords)) = fields.split_first() else {
+            continue;
+        };
+        let category_id: u32 = class
+            .parse()
+            .with_context(|| format!("Invalid class id on line {}", line_number + 1))?;
+        let coords = coords
+            .iter()
+            .map(|value| value.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid coordinate on line {}", line_number + 1))?;
+
+        let (mut obb, mut segmentation) = (None, None);
+        let bbox = match coords.len() {
+            4 => {
+                let (cx, cy, bw, bh) = (coords[0] * w, coords[1] * h, coords[2] * w, coords[3] * h);
+                [cx - bw / 2.0, cy - bh / 2.0, cx + bw / 2.0, cy + bh / 2.0]
+            }
+            n if n >= 6 && n % 2 == 0 => {
+                let points: Vec<Vec<f64>> = coords.chunks_exact(2).map(|pair| vec![pair[0] * w, pair[1] * h]).collect();
+                if n == 8 {
+                    let mut corners = [0.0; 8];
+                    for (corner, value) in corners.iter_mut().zip(points.iter().flatten()) {
+                        *corner = *value;
+                    }
+                    obb = Some(corners);
+                }
+                segmentation = Some(points.iter().flatten().copied().collect());
+                points_extent(&points).context("Empty polygon")?
+            }
+            n => anyhow::bail!("Line {} has {} coordinates, expected 4 or an even number of polygon points", line_number + 1, n),
+        };
+
+        unified_annotations.push(UnifiedAnnotation {
+            bbox: bbox.to_vec(),
+            category_id,
+            category_name: names.get(category_id as usize).cloned().flatten(),
+            obb,
+            segmentation,
+            ..Default::default()
+        });
+    }
+
+    let file_name = image_path.file_name().and_then(|s| s.to_str()).context("Non-UTF8 image filename")?.to_string();
+    Ok(vec![UnifiedImage {
+        file_name,
+        height,
+        width,
+        annotations: unified_annotations,
+        source_path: Some(image_path),
+        ..Default::default()
+    }])
+}
+
+// Existing YOLO dataset; `names` come from its classes.txt or data.yaml
+pub struct YoloParser {
+    pub names: Vec<Option<String>>,
+}
+
+impl FormatParser for YoloParser {
+    fn name(&self) -> &'static str {
+        "yolo"
+    }
+
+    fn description(&self) -> &'static str {
+        "YOLO labels"
+    }
+
+    // Label files live under labels/ or next to their image. Whether a text file has an image is
+    // only known from the input source, so `parse` skips the ones that don't
+    fn is_annotation_file(&self, _path: &Path, file_name: &str) -> bool {
+        file_name.ends_with(".txt") && file_name != "classes.txt"
+    }
+
+    fn parse(&self, content: &str, ctx: &ParseContext, _labels: &mut LabelRegistry) -> Result<Vec<UnifiedImage>> {
+        parse(content, ctx, &self.names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[Option<&str>]) -> Vec<Option<String>> {
+        list.iter().map(|name| name.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn class_names_are_trimmed_and_trailing_blank_lines_ignored() {
+        let parsed = parse_class_names("person\n  car \r\ntruck\n\n  \n").unwrap();
+        assert_eq!(parsed, names(&[Some("person"), Some("car"), Some("truck")]));
+    }
+
+    #[test]
+    fn duplicate_class_names_are_an_error() {
+        let error = parse_class_names("person\ncar\nperson\n").unwrap_err();
+        assert!(error.to_string().contains("Duplicate class name \"person\" on line 3"));
+    }
+
+    #[test]
+    fn blank_lines_between_class_names_are_an_error() {
+        assert!(parse_class_names("person\n\ncar\n").is_err());
+    }
+
+    #[test]
+    fn marked_empty_slots_stay_empty() {
+        let parsed = parse_class_names("person\n-\nclass_1\n").unwrap();
+        assert_eq!(parsed, names(&[Some("person"), None, Some("class_1")]));
+        assert_eq!(slot_names(&parsed), ["person", "class_1", "class_1"]);
+    }
+
+    #[test]
+    fn label_lines_are_scaled_to_the_paired_image() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("train/images")).unwrap();
+        std::fs::create_dir_all(dir.path().join("train/labels")).unwrap();
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 64, 48);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0; 64 * 48]).unwrap();
+        writer.finish().unwrap();
+        std::fs::write(dir.path().join("train/images/a.png"), png).unwrap();
+
+        let input = crate::input::InputSource::open(dir.path()).unwrap();
+        let path = dir.path().join("train/labels/a.txt");
+        let ctx = ParseContext { path: &path, file_name: "a.txt", input: &input, read_image_size: false };
+        let labels = "1 0.5 0.5 0.25 0.5\n\n0 0 0 0.5 0 0.5 0.5 0 0.5\n";
+        let images = parse(labels, &ctx, &names(&[Some("person"), Some("car")])).unwrap();
+
+        assert_eq!((images[0].file_name.as_str(), images[0].width, images[0].height), ("a.png", 64, 48));
+        assert_eq!(images[0].source_path.as_deref(), Some(dir.path().join("train/images/a.png").as_path()));
+        let car = &images[0].annotations[0];
+        assert_eq!((car.category_id, car.category_name.as_deref()), (1, Some("car")));
+        assert_eq!(car.bbox, [24.0, 12.0, 40.0, 36.0]);
+        let person = &images[0].annotations[1];
+        assert_eq!(person.bbox, [0.0, 0.0, 32.0, 24.0]);
+        assert_eq!(person.obb, Some([0.0, 0.0, 32.0, 0.0, 32.0, 24.0, 0.0, 24.0]));
+
+        assert!(parse("0 0.5 0.5 0.25\n", &ctx, &[]).is_err());
+    }
+
+    #[test]
+    fn zipped_labels_pair_with_images_next_to_them() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("dataset.zip");
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 64, 48);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[0; 64 * 48]).unwrap();
+        writer.finish().unwrap();
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in [("data/a.png", &png[..]), ("data/a.txt", b"0 0.5 0.5 0.25 0.5\n"), ("data/notes.txt", b"not labels\n")] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let input = crate::input::InputSource::open(&zip_path).unwrap();
+        let parser = YoloParser { names: names(&[Some("person")]) };
+        let mut images = Vec::new();
+        for path in input.annotation_files(&parser, &|_| false) {
+            let file_name = path.file_name().unwrap().to_str().unwrap().to_string();
+            let ctx = ParseContext { path: &path, file_name: &file_name, input: &input, read_image_size: false };
+            images.extend(parser.parse(&input.read_annotation(&path).unwrap(), &ctx, &mut LabelRegistry::default()).unwrap());
+        }
+        assert_eq!(images.len(), 1);
+        assert_eq!((images[0].file_name.as_str(), images[0].annotations.len()), ("a.png", 1));
+    }
+}
@@ -0,0 +1,416 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::formats::{self, FormatParser};
+use crate::IMAGE_EXTENSIONS;
+
+// How images get into the output directory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImageMode {
+    /// Copy, as a copy-on-write clone where the filesystem supports it
+    Copy,
+    /// Hard link, copying instead when the output is on another filesystem
+    Hardlink,
+    /// Symbolic link to the absolute source path
+    Symlink,
+    /// Move the source file into the output
+    Move,
+}
+
+// Where annotation and image files come from: a directory tree, or the entries of a .zip archive
+// that are read on demand instead of being unpacked first. Paths into an archive are entry names.
+// Images are looked up in the --image-dir directories first.
+pub struct InputSource {
+    source: Source,
+    image_dirs: Vec<DirInput>,
+    // Where each image placed with --image-mode move went, for later records of the same file
+    moved: RefCell<HashMap<PathBuf, PathBuf>>,
+}
+
+enum Source {
+    Dir(DirInput),
+    Zip(ZipInput),
+}
+
+pub struct DirInput {
+    root: PathBuf,
+    // First file with each file name in walk order, indexed on the first image lookup so finding
+    // images doesn't walk the tree again for every one
+    by_file_name: OnceCell<HashMap<String, PathBuf>>,
+}
+
+impl DirInput {
+    fn new(root: &Path) -> Self {
+        DirInput { root: root.to_path_buf(), by_file_name: OnceCell::new() }
+    }
+
+    fn file_index(&self) -> &HashMap<String, PathBuf> {
+        self.by_file_name.get_or_init(|| {
+            let mut index = HashMap::new();
+            for entry in WalkDir::new(&self.root).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+                if let Some(file_name) = entry.file_name().to_str() {
+                    index.entry(file_name.to_string()).or_insert_with(|| entry.path().to_path_buf());
+                }
+            }
+            index
+        })
+    }
+}
+
+pub struct ZipInput {
+    archive: RefCell<ZipArchive<fs::File>>,
+    entries: Vec<String>,
+    // First entry with each file name, mirroring the first-match lookup used for directories
+    by_file_name: HashMap<String, String>,
+}
+
+impl InputSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(InputSource { source: Source::Dir(DirInput::new(path)), image_dirs: Vec::new(), moved: RefCell::default() });
+        }
+
+        let file = fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let archive = ZipArchive::new(file).with_context(|| format!("Failed to read ZIP archive: {}", path.display()))?;
+        let entries: Vec<String> = archive
+            .file_names()
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid entry name in {}", path.display()))?
+            .into_iter()
+            .filter(|name| !name.ends_with('/'))
+            .map(|name| name.into_owned())
+            .collect();
+
+        let mut by_file_name = HashMap::new();
+        for entry in &entries {
+            let file_name = entry.rsplit('/').next().unwrap_or(entry);
+            by_file_name.entry(file_name.to_string()).or_insert_with(|| entry.clone());
+        }
+
+        let zip = ZipInput { archive: RefCell::new(archive), entries, by_file_name };
+        Ok(InputSource { source: Source::Zip(zip), image_dirs: Vec::new(), moved: RefCell::default() })
+    }
+
+    // Also search these directories for images, before the input itself
+    pub fn with_image_dirs(mut self, dirs: &[PathBuf]) -> Self {
+        self.image_dirs = dirs.iter().map(|dir| DirInput::new(dir)).collect();
+        self
+    }
+
+    pub fn is_archive(&self) -> bool {
+        matches!(self.source, Source::Zip(_))
+    }
+
+    // Whether a path is a file on disk rather than an archive entry
+    fn on_disk(&self, path: &Path) -> bool {
+        matches!(self.source, Source::Dir(_)) || self.image_dirs.iter().any(|dir| path.starts_with(&dir.root))
+    }
+
+    // Annotation files for the given parser, in directory walk or archive order
+    pub fn annotation_files(&self, parser: &dyn FormatParser, exclude: &dyn Fn(&Path) -> bool) -> Vec<PathBuf> {
+        let is_annotation_file = |path: &Path| {
+            let file_name = formats::decompressed_name(path.file_name().and_then(|s| s.to_str()).unwrap_or(""));
+            parser.is_annotation_file(path, file_name)
+        };
+        match &self.source {
+            Source::Dir(dir) => WalkDir::new(&dir.root)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|entry| entry.into_path())
+                .filter(|path| is_annotation_file(path) && !exclude(path))
+                .collect(),
+            Source::Zip(zip) => zip.entries
+                .iter()
+                .map(PathBuf::from)
+                .filter(|path| is_annotation_file(path))
+                .collect(),
+        }
+    }
+
+    // Read an annotation file, inflating .gz and .zst files on the fly
+    pub fn read_annotation(&self, path: &Path) -> Result<String> {
+        match &self.source {
+            Source::Dir(_) => decode(io::BufReader::new(fs::File::open(path)?), path),
+            Source::Zip(zip) => decode(zip.archive.borrow_mut().by_name(&entry_name(path))?, path),
+        }
+    }
+
+    // Path of a file at the top of the input, e.g. a dataset's classes.txt
+    pub fn root_file(&self, name: &str) -> PathBuf {
+        match &self.source {
+            Source::Dir(dir) => dir.root.join(name),
+            Source::Zip(_) => PathBuf::from(name),
+        }
+    }
+
+    // First file whose name matches, in directory walk or archive order
+    pub fn find_file(&self, matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+        let name_matches = |path: &Path| path.file_name().and_then(|s| s.to_str()).is_some_and(&matches);
+        match &self.source {
+            Source::Dir(dir) => WalkDir::new(&dir.root)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.into_path())
+                .find(|path| name_matches(path)),
+            Source::Zip(zip) => zip.entries.iter().map(PathBuf::from).find(|path| name_matches(path)),
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        match &self.source {
+            Source::Zip(zip) if !self.on_disk(path) => zip.entries.contains(&entry_name(path)),
+            _ => path.exists(),
+        }
+    }
+
+    // Every file with an image extension, in directory walk or archive order, --image-dir
+    // directories first
+    pub fn image_files(&self) -> Vec<PathBuf> {
+        let is_image = |path: &Path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        };
+        let walk = |dir: &DirInput| -> Vec<PathBuf> {
+            WalkDir::new(&dir.root)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|entry| entry.into_path())
+                .filter(|path| is_image(path))
+                .collect()
+        };
+        let mut files: Vec<PathBuf> = self.image_dirs.iter().flat_map(walk).collect();
+        files.extend(match &self.source {
+            Source::Dir(dir) => walk(dir),
+            Source::Zip(zip) => zip.entries.iter().map(PathBuf::from).filter(|path| is_image(path)).collect(),
+        });
+        files
+    }
+
+    // Locate an image by file name, falling back to the same stem with another image extension.
+    // Each --image-dir is searched before the input.
+    pub fn find_image(&self, image_filename: &str) -> Option<PathBuf> {
+        let base_name = Path::new(image_filename).file_stem()?.to_str()?;
+        let candidates: Vec<String> = std::iter::once(image_filename.to_string())
+            .chain(IMAGE_EXTENSIONS.iter().map(|ext| format!("{}.{}", base_name, ext)))
+            .collect();
+        let in_dir = |dir: &DirInput| candidates.iter().find_map(|name| dir.file_index().get(name).cloned());
+        self.image_dirs.iter().find_map(in_dir).or_else(|| match &self.source {
+            Source::Dir(dir) => in_dir(dir),
+            Source::Zip(zip) => candidates.iter().find_map(|name| zip.by_file_name.get(name)).map(PathBuf::from),
+        })
+    }
+
+    pub fn image_size(&self, path: &Path) -> Result<(u32, u32)> {
+        let size = if self.on_disk(path) {
+            imagesize::size(path)
+        } else {
+            imagesize::blob_size(&self.read_bytes(path)?)
+        }
+        .with_context(|| format!("Failed to read image header: {}", path.display()))?;
+        Ok((size.width as u32, size.height as u32))
+    }
+
+    // Put an image at `dest` the `mode` way; archive entries are always extracted. Returns false
+    // when a hardlink or move across filesystems had to copy instead. A source that was already
+    // moved is copied from where it went, so records sharing an image file all get it.
+    pub fn place_image(&self, path: &Path, dest: &Path, mode: ImageMode) -> Result<bool> {
+        if !self.on_disk(path) {
+            fs::write(dest, self.read_bytes(path)?).with_context(|| format!("Failed to copy image: {}", path.display()))?;
+            return Ok(true);
+        }
+        let moved_to = self.moved.borrow().get(path).cloned();
+        if moved_to.as_deref() == Some(dest) {
+            return Ok(true);
+        }
+        // Replace what an earlier run left, which may be a link to the source itself
+        if dest.exists() || dest.is_symlink() {
+            fs::remove_file(dest)?;
+        }
+        if let Some(moved_to) = moved_to {
+            copy_file(&moved_to, dest).with_context(|| format!("Failed to copy image: {}", moved_to.display()))?;
+            return Ok(true);
+        }
+        let placed = match mode {
+            ImageMode::Copy => copy_file(path, dest).map(|_| true),
+            ImageMode::Hardlink => match fs::hard_link(path, dest) {
+                Err(error) if error.kind() == io::ErrorKind::CrossesDevices => copy_file(path, dest).map(|_| false),
+                linked => linked.map(|_| true),
+            },
+            ImageMode::Symlink => fs::canonicalize(path).and_then(|target| symlink(&target, dest)).map(|_| true),
+            ImageMode::Move => match fs::rename(path, dest) {
+                Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+                    copy_file(path, dest).and_then(|_| fs::remove_file(path)).map(|_| false)
+                }
+                moved => moved.map(|_| true),
+            },
+        };
+        let placed = placed.with_context(|| format!("Failed to {} image: {}", mode.verb(), path.display()))?;
+        if mode == ImageMode::Move {
+            self.moved.borrow_mut().insert(path.to_path_buf(), dest.to_path_buf());
+        }
+        Ok(placed)
+    }
+
+    pub fn read_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        let Source::Zip(zip) = &self.source else {
+            return Ok(fs::read(path)?);
+        };
+        if self.on_disk(path) {
+            return Ok(fs::read(path)?);
+        }
+        let mut archive = zip.archive.borrow_mut();
+        let mut entry = archive.by_name(&entry_name(path))?;
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl ImageMode {
+    fn verb(self) -> &'static str {
+        match self {
+            ImageMode::Copy => "copy",
+            ImageMode::Hardlink => "hard link",
+            ImageMode::Symlink => "symlink",
+            ImageMode::Move => "move",
+        }
+    }
+}
+
+// Copy a file as a copy-on-write clone where the filesystem supports it (Btrfs, XFS, APFS), so no
+// data is duplicated, and as a plain copy elsewhere
+fn copy_file(path: &Path, dest: &Path) -> io::Result<()> {
+    if reflink(path, dest).is_ok() {
+        return Ok(());
+    }
+    fs::copy(path, dest).map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(path: &Path, dest: &Path) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let source = fs::File::open(path)?;
+    let target = fs::File::create(dest)?;
+    // SAFETY: both descriptors stay open for the duration of the call
+    if unsafe { libc::ioctl(target.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) } != 0 {
+        let error = io::Error::last_os_error();
+        drop(target);
+        let _ = fs::remove_file(dest);
+        return Err(error);
+    }
+    fs::set_permissions(dest, source.metadata()?.permissions())
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(path: &Path, dest: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let source = CString::new(path.as_os_str().as_bytes())?;
+    let target = CString::new(dest.as_os_str().as_bytes())?;
+    // SAFETY: both are valid NUL-terminated paths
+    if unsafe { libc::clonefile(source.as_ptr(), target.as_ptr(), 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_path: &Path, _dest: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+// Archive entry names always use forward slashes
+fn entry_name(path: &Path) -> String {
+    formats::normalize_path(&path.to_string_lossy())
+}
+
+fn decode(mut reader: impl Read, path: &Path) -> Result<String> {
+    let mut content = String::new();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => flate2::read::MultiGzDecoder::new(reader).read_to_string(&mut content)?,
+        Some("zst") => zstd::Decoder::new(reader)?.read_to_string(&mut content)?,
+        _ => reader.read_to_string(&mut content)?,
+    };
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn compressed_annotation_files_are_inflated() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "{\"images\": []}";
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(content.as_bytes()).unwrap();
+        fs::write(dir.path().join("a.json.gz"), gz.finish().unwrap()).unwrap();
+        fs::write(dir.path().join("b.json.zst"), zstd::encode_all(content.as_bytes(), 0).unwrap()).unwrap();
+        fs::write(dir.path().join("c.json"), content).unwrap();
+
+        let input = InputSource::open(dir.path()).unwrap();
+        for name in ["a.json.gz", "b.json.zst", "c.json"] {
+            assert_eq!(input.read_annotation(&dir.path().join(name)).unwrap(), content, "{}", name);
+        }
+        assert_eq!(formats::decompressed_name("a.json.gz"), "a.json");
+        assert_eq!(formats::decompressed_name("b.json.zst"), "b.json");
+    }
+
+    #[test]
+    fn zip_entries_are_read_without_unpacking() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("dataset.zip");
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 64, 48);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut image = encoder.write_header().unwrap();
+        image.write_image_data(&[0; 64 * 48]).unwrap();
+        image.finish().unwrap();
+        let mut writer = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("data/", options).unwrap();
+        writer.start_file("data/classes.txt", options).unwrap();
+        writer.write_all(b"person\n").unwrap();
+        writer.start_file("data/images/a.png", options).unwrap();
+        writer.write_all(&png).unwrap();
+        writer.finish().unwrap();
+
+        let input = InputSource::open(&zip_path).unwrap();
+        assert!(input.is_archive());
+        let classes = Path::new("data/classes.txt");
+        assert!(input.contains(classes));
+        assert!(!input.contains(Path::new("classes.txt")));
+        assert_eq!(input.read_annotation(classes).unwrap(), "person\n");
+        assert_eq!(input.find_file(|name| name.ends_with(".txt")).as_deref(), Some(classes));
+
+        // Images are found by file name, also under another image extension
+        let image_path = input.find_image("a.jpg").unwrap();
+        assert_eq!(image_path, Path::new("data/images/a.png"));
+        assert_eq!(input.image_size(&image_path).unwrap(), (64, 48));
+        let dest = dir.path().join("a.png");
+        input.place_image(&image_path, &dest, ImageMode::Copy).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), png);
+    }
+}
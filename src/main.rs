@@ -1,115 +1,936 @@
 use anyhow::{Context, Result};
-use clap::Parser;
-use serde::Deserialize;
-use std::collections::HashMap;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use indicatif::{ProgressBar, ProgressStyle};
+use regex::Regex;
+use std::fmt;
+use std::time::Instant;
+
+mod archive;
+mod download;
+mod formats;
+mod input;
+mod mask;
+mod stats;
+mod writers;
+
+use input::{ImageMode, InputSource};
 
 #[derive(Parser)]
 #[command(name = "coco-to-yolo")]
 #[command(about = "Convert COCO format annotations to YOLO format")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    /// Input directory containing COCO JSON files
-    #[arg(short, long)]
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Input directory containing COCO JSON files (or XML files for --format cvat), or a .zip archive of one
+    #[arg(short, long, required_unless_present_any = ["check_only", "print_schema"])]
+    input: Option<PathBuf>,
 
     /// Output directory for YOLO format files
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(short, long, required_unless_present = "print_schema")]
+    output: Option<PathBuf>,
 
     /// Create classes.txt file with class names
     #[arg(long, default_value_t = true)]
     create_classes: bool,
 
-    /// Format type: 'standard' for standard COCO format, 'damm' for DAMM dataset format
+    /// Format type: 'standard' (COCO), 'damm', 'labelme', 'cvat' (CVAT for images 1.1 XML), 'label-studio',
+    /// 'via' (VGG Image Annotator), 'open-images' (*-annotations-bbox.csv), 'kitti' (label_2/*.txt),
+    /// 'supervisely' (project with meta.json and <dataset>/ann/*.json), 'createml' (CreateML / Roboflow JSON),
+    /// 'sagemaker' (Ground Truth augmented manifest), 'bdd100k' (BDD100K detection labels),
+    /// 'lvis' (LVIS v1, category ids remapped to contiguous indices), 'coco-results' (detections, needs --images-index),
+    /// 'dota' (labelTxt/*.txt oriented boxes), 'mot' (MOTChallenge <sequence>/gt/gt.txt),
+    /// 'datumaro' (annotations/<subset>.json), 'yolo' (existing images/labels tree with classes.txt),
+    /// 'coco-panoptic' (thing segments of a COCO panoptic JSON),
+    /// 'auto' (detect standard, damm, coco-panoptic, lvis, labelme, datumaro, createml, label-studio or bdd100k per JSON file)
     #[arg(long, default_value = "damm")]
     format: String,
     
-    /// Training split ratio (0.0 to 1.0)
-    #[arg(long, default_value = "0.8")]
-    train_split: f64,
+    #[command(flatten)]
+    split: SplitArgs,
+
+    /// Keep the split of the source annotation files: comma-separated split=glob entries, e.g.
+    /// 'train=instances_train*.json,val=instances_val*.json'. Images of other files are skipped
+    #[arg(long, conflicts_with_all = [
+        "stratify", "split_group", "train_list", "split_regex", "split_strategy", "test_split", "kfold",
+    ])]
+    split_by_file: Option<SplitByFile>,
     
     /// Create YOLO directory structure (images/labels with train/val splits)
     #[arg(long, default_value_t = true)]
     yolo_structure: bool,
+
+    /// Label type to write: 'detect' for boxes, 'obb' for oriented boxes (4 corner points),
+    /// 'segment' for polygons, 'pose' for boxes with COCO keypoints
+    #[arg(long, value_enum, default_value_t = Task::Detect)]
+    task: Task,
+
+    /// Corner coordinates of --task obb labels: 'normalized' by the image size, or 'pixel' for
+    /// rounded integer pixels (DOTA-style tooling)
+    #[arg(long, value_enum, default_value_t = ObbCoords::Normalized)]
+    obb_coords: ObbCoords,
+
+    /// Pixels a box may reach past the image borders before --oob-policy applies; such boxes are
+    /// clipped silently
+    #[arg(long, default_value_t = 1.0)]
+    bounds_tolerance: f64,
+
+    /// Class list (one name per line) fixing class indices by category name; annotations of other
+    /// classes are dropped, or are an error with --strict. A line of just '-' is an empty slot
+    #[arg(long, conflicts_with_all = ["class_id_map", "keep_category_ids"])]
+    names: Option<PathBuf>,
+
+    /// Where to write the class index → source category id JSON for translating predictions back,
+    /// written whenever the two differ [default: <output>/yolo_to_coco_ids.json]
+    #[arg(long)]
+    category_id_map_out: Option<PathBuf>,
+
+    /// Directory layout used with --yolo-structure
+    #[arg(long, value_enum, default_value_t = Layout::Ultralytics)]
+    layout: Layout,
+
+    /// Convert only the images listed in this file, one image id or file name per line
+    #[arg(long)]
+    only_images: Option<PathBuf>,
+
+    /// Keep only annotations whose category name matches this regex (e.g. 'vehicle.*')
+    #[arg(long)]
+    keep_categories_regex: Option<String>,
+
+    /// Keep only annotations of these classes, given as category names or source category ids
+    /// (e.g. 'person,car' or '1,3')
+    #[arg(long, value_delimiter = ',')]
+    include_classes: Vec<String>,
+
+    /// Drop annotations of these classes, given as category names or source category ids
+    #[arg(long, value_delimiter = ',')]
+    exclude_classes: Vec<String>,
+
+    /// YAML file merging and renaming classes: each key is a new class name and its value one or a
+    /// list of source category names or ids (e.g. 'vehicle: [car, truck, bus]')
+    #[arg(long)]
+    class_map: Option<PathBuf>,
+
+    /// What the YOLO classes are: source categories, or their COCO supercategories (e.g. vehicle, animal)
+    #[arg(long, value_enum, default_value_t = GroupBy::Category, conflicts_with = "class_map")]
+    group_by: GroupBy,
+
+    /// YAML class hierarchy of nested mappings (e.g. 'animal: {dog: [golden retriever, poodle]}');
+    /// categories below --level are renamed to their ancestor at that level
+    #[arg(long, conflicts_with_all = ["class_map", "group_by"])]
+    taxonomy: Option<PathBuf>,
+
+    /// Depth of the --taxonomy to emit classes at, 0 being the top level. Categories labeled at a
+    /// coarser level keep their class
+    #[arg(long, default_value_t = 0, requires = "taxonomy")]
+    level: usize,
+
+    /// Keep at most this many images containing each class. Images are taken in random order and
+    /// skipped once any of their classes is full; images without annotations are kept
+    #[arg(long)]
+    max_per_class: Option<usize>,
+
+    /// Convert only a random subset: a number of images (500) or a fraction of them (0.1), picked
+    /// with --seed after filtering
+    #[arg(long)]
+    sample: Option<Sample>,
+
+    /// Duplicate training images containing rarer classes until every class has at least this many
+    /// instances in train. Copies are named <stem>_dup<n>.<ext>
+    #[arg(long, conflicts_with = "kfold")]
+    oversample_min: Option<usize>,
+
+    /// Class balancing of the training split: 'undersample' randomly drops training images whose
+    /// classes all have more instances than the median class
+    #[arg(long, value_enum, conflicts_with = "kfold")]
+    balance: Option<Balance>,
+
+    /// Seed for the random choices of --balance [default: --seed]
+    #[arg(long)]
+    balance_seed: Option<u64>,
+
+    /// Background (negative) images without annotations: 'include' keeps them and adds images under
+    /// --input that no annotation file references, 'exclude' drops them, 'ratio=0.1' includes them
+    /// but caps them at that fraction of the output. Without it, only listed images are kept
+    #[arg(long)]
+    background_images: Option<BackgroundImages>,
+
+    /// What to do with COCO iscrowd annotations: skip, keep, or separate-class (<class>_crowd classes)
+    #[arg(long, value_enum, default_value_t = Crowd::Keep)]
+    iscrowd: Crowd,
+
+    /// Annotations reaching past the image borders with their box, polygon, oriented box or labeled
+    /// keypoints: clip them, drop them, or fail
+    #[arg(long, value_enum, default_value_t = OobPolicy::Clip)]
+    oob_policy: OobPolicy,
+
+    /// Drop boxes smaller than this area, in pixels or as a percentage of the image area (e.g. 0.01%)
+    #[arg(long)]
+    min_area: Option<AreaThreshold>,
+
+    /// Drop boxes larger than this area, in pixels or as a percentage of the image area (e.g. 90%)
+    #[arg(long)]
+    max_area: Option<AreaThreshold>,
+
+    /// Drop boxes narrower or shorter than WxH pixels (e.g. 4x4, or 4 for both)
+    #[arg(long)]
+    min_box_size: Option<BoxSize>,
+
+    /// Remove same-class boxes overlapping a larger one in the same image with at least this IoU
+    /// (e.g. 0.95); of equal boxes the first is kept
+    #[arg(long)]
+    dedup_iou: Option<f64>,
+
+    /// Drop images left without annotations by the category, class, area, size and duplicate filters
+    #[arg(long)]
+    drop_filtered_images: bool,
+
+    /// Write conversion counters to this file in Prometheus textfile format
+    #[arg(long)]
+    metrics_file: Option<PathBuf>,
+
+    /// Directory searched for image files before --input (repeatable), for images kept apart from
+    /// the annotations such as images/train2017
+    #[arg(long)]
+    image_dir: Vec<PathBuf>,
+
+    /// Read JSON file paths (one per line) from this manifest instead of scanning the input directory
+    #[arg(long)]
+    json_list: Option<PathBuf>,
+
+    /// Check that every label file has a matching image and vice versa after conversion
+    #[arg(long, conflicts_with = "kfold")]
+    check_pairing: bool,
+
+    /// Skip conversion and only run --check-pairing on the existing dataset in --output
+    #[arg(long)]
+    check_only: bool,
+
+    /// Exit with an error when validation finds problems instead of only reporting them
+    #[arg(long)]
+    strict: bool,
+
+    /// Only parse the JSON files and report counts and timing; nothing is written
+    #[arg(long)]
+    parse_only: bool,
+
+    /// Print the JSON schema of the accepted input format to stdout and exit
+    #[arg(long, value_enum)]
+    print_schema: Option<SchemaFormat>,
+
+    /// Write a .gitignore (ignoring image directories) and a configs/ placeholder next to the dataset
+    #[arg(long)]
+    scaffold: bool,
+
+    /// Write a CSV of yolo_index,original_id,name,annotation_count for every class
+    #[arg(long)]
+    class_mapping_csv: Option<PathBuf>,
+
+    /// Class mapping CSV (yolo_index,original_id columns, e.g. the --class-mapping-csv output of an earlier run)
+    /// fixing the class index of each category id; unmapped categories are dropped
+    #[arg(long)]
+    class_id_map: Option<PathBuf>,
+
+    /// Use source category ids as class indices instead of numbering them from 0; classes.txt and
+    /// data.yaml name unused indices class_<id>
+    #[arg(long, conflicts_with = "class_id_map")]
+    keep_category_ids: bool,
+
+    /// Download images whose file_name or coco_url is an HTTP(S) URL instead of searching the input directory
+    #[arg(long)]
+    download_images: bool,
+
+    /// Maximum number of concurrent downloads with --download-images
+    #[arg(long, default_value_t = 8)]
+    download_concurrency: usize,
+
+    /// Number of retries per image with --download-images
+    #[arg(long, default_value_t = 3)]
+    download_retries: u32,
+
+    /// Run parsing, filtering, class mapping and splitting, and report what would be converted
+    /// without writing any files
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Compare the classes that would be written (after filtering and class mapping) against the
+    /// `names` of an existing data.yaml and report drift before anything is written
+    #[arg(long)]
+    from_data_yaml: Option<PathBuf>,
+
+    /// Read image width/height from the image file headers for formats that don't store them
+    #[arg(long)]
+    read_image_size: bool,
+
+    /// Region attribute holding the class name for --format via
+    #[arg(long, default_value = "label")]
+    via_label_attribute: String,
+
+    /// Open Images class description CSV (LabelName,DisplayName); searched for in --input when omitted
+    #[arg(long)]
+    class_descriptions: Option<PathBuf>,
+
+    /// KITTI type → class index mapping file ("<type> <index>" per line); unmapped types are dropped.
+    /// The indices are written as they are; types sharing one are named after the first listed
+    #[arg(long)]
+    kitti_class_map: Option<PathBuf>,
+
+    /// How --format lvis treats images listing not_exhaustive_category_ids
+    #[arg(long, value_enum, default_value_t = formats::lvis::NotExhaustivePolicy::Keep)]
+    lvis_not_exhaustive: formats::lvis::NotExhaustivePolicy,
+
+    /// Original COCO file providing images and categories for --format coco-results
+    #[arg(long)]
+    images_index: Option<PathBuf>,
+
+    /// Minimum detection score kept as a pseudo-label with --format coco-results
+    #[arg(long, default_value_t = 0.5)]
+    score_threshold: f64,
+
+    /// Simplification tolerance in pixels when tracing RLE masks into --task segment/obb polygons
+    #[arg(long, default_value_t = 1.0)]
+    polygon_tolerance: f64,
+
+    /// Extra annotation formats to write next to the YOLO labels (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    export: Vec<ExportFormat>,
+
+    /// Images per shard for sharded exports (tfrecord, webdataset)
+    #[arg(long, default_value_t = 1000)]
+    shard_size: usize,
+
+    /// Decimal places for the normalized coordinates in label files
+    #[arg(long, default_value_t = 6)]
+    precision: usize,
+
+    /// Added to every class index in label files and data.yaml (1 for 1-based class indices); the
+    /// slots below it are named class_<index> in classes.txt, data.yaml and obj.names
+    #[arg(long, default_value_t = 0)]
+    class_offset: u32,
+
+    /// Also write Darknet training files: train.txt/val.txt image lists, obj.names and obj.data
+    #[arg(long, conflicts_with = "kfold")]
+    darknet: bool,
+
+    /// Write train.txt/val.txt image lists with absolute paths and point data.yaml at them instead
+    /// of the image directories
+    #[arg(long)]
+    lists: bool,
+
+    /// Leave images where they are instead of copying them into the output; the lists reference
+    /// the source images and labels are still written under <output>/<split>/labels, so nothing is
+    /// written into the input
+    #[arg(long, requires = "lists", conflicts_with = "check_pairing")]
+    no_copy: bool,
+
+    /// How images get into the output: 'copy', 'hardlink' (copies across filesystems), 'symlink' to
+    /// the source, or 'move'. Images inside a .zip input are always extracted
+    #[arg(long, value_enum, default_value_t = ImageMode::Copy, conflicts_with = "no_copy")]
+    image_mode: ImageMode,
+
+    /// Pack the finished output directory into <output>.tar.gz or <output>.zip next to it
+    #[arg(long, value_enum)]
+    archive: Option<archive::ArchiveFormat>,
+
+    /// Delete the output directory once --archive has packed it
+    #[arg(long, requires = "archive")]
+    archive_only: bool,
+
+    /// Write annotation fields YOLO can't hold (attributes, score, track_id, custom fields) to
+    /// labels_meta/<image>.json, keyed by label line number
+    #[arg(long)]
+    labels_meta: bool,
+
+    /// Write inverse-frequency class weights of the train split to class_weights.txt and data.yaml
+    #[arg(long, conflicts_with = "kfold")]
+    class_weights: bool,
+
+    /// Write track ids from --format mot to tracks/<image>.txt, one per label line
+    #[arg(long)]
+    track_ids: bool,
 }
 
-// DAMM format annotation (custom format)
-#[derive(Debug, Deserialize)]
-struct DammAnnotation {
-    bbox: Vec<Vec<f64>>, // [[x1, y1], [x2, y2]] format
-    category_id: u32,
-    #[serde(default)]
-    bbox_mode: Option<String>, // BoxMode.XYXY_ABS
-    #[serde(default)]
-    segmentation: Option<Vec<Vec<f64>>>,
+// Options deciding which split each image goes to, shared by conversion and the split subcommand
+#[derive(clap::Args)]
+struct SplitArgs {
+    /// Training split ratio (0.0 to 1.0)
+    #[arg(long, default_value = "0.8")]
+    train_split: f64,
+
+    /// Test split ratio (0.0 to 1.0); a test/ split is created when above 0 and validation gets the rest
+    #[arg(long, default_value_t = 0.0)]
+    test_split: f64,
+
+    /// Seed for the split shuffle and the other random choices, so reruns produce the same dataset
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// How images are assigned to splits: 'random' shuffles with --seed; 'hash' puts each image by a
+    /// hash of its file name, so it stays in the same split as images are added in later versions
+    #[arg(long, value_enum, default_value_t = SplitStrategy::Random, conflicts_with_all = [
+        "stratify", "split_group", "train_list", "split_regex",
+    ])]
+    split_strategy: SplitStrategy,
+
+    /// Fewest images the val split (every fold with --kfold) may get; 0 takes the split as the ratios
+    /// give it. A ratio-based split short of it moves images over from train; lists, rules and groups
+    /// fail instead
+    #[arg(long, default_value_t = 0)]
+    min_val: usize,
+
+    /// Image field ordering images for --split-strategy date: a date string that sorts in time order
+    /// (ISO 8601 like COCO's '2013-11-14 16:28:13') or a number such as a Unix timestamp
+    #[arg(long, default_value = "date_captured")]
+    date_field: String,
+
+    /// Split so each class's instances are divided between the splits in the split ratios
+    /// (iterative stratification) instead of by plain shuffling
+    #[arg(long)]
+    stratify: bool,
+
+    /// Keep related images in one split: 'dirname', 'video_id' (COCO video_id, MOT sequence) or
+    /// 'regex=<pattern>' (first capture group of the file name)
+    #[arg(long, conflicts_with = "stratify")]
+    split_group: Option<SplitGroup>,
+
+    /// Image file names (one per line) making up the train split, instead of random splitting.
+    /// Images in none of the lists are skipped
+    #[arg(long, requires = "val_list", conflicts_with_all = ["stratify", "split_group", "test_split"])]
+    train_list: Option<PathBuf>,
+
+    /// Image file names making up the val split, with --train-list
+    #[arg(long, requires = "train_list")]
+    val_list: Option<PathBuf>,
+
+    /// Image file names making up a test split, with --train-list
+    #[arg(long, requires = "train_list")]
+    test_list: Option<PathBuf>,
+
+    /// Split into N folds of their own directories instead of train/val, each fold directory getting
+    /// train.txt/val.txt and a data.yaml that validates on it and trains on the others
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..), conflicts_with_all = [
+        "test_split", "train_list",
+    ])]
+    kfold: Option<u32>,
+
+    /// Route images whose file name matches a regex to a split, e.g. 'val=^cam03_' (repeatable; the
+    /// first matching rule wins). The other images are split by the split ratios
+    #[arg(long, conflicts_with_all = ["stratify", "split_group", "train_list", "kfold"])]
+    split_regex: Vec<SplitRule>,
 }
 
-// DAMM format image structure
-#[derive(Debug, Deserialize)]
-struct DammImage {
-    file_name: String,
-    height: u32,
-    width: u32,
-    image_id: u32,
-    annotations: Vec<DammAnnotation>,
+impl SplitArgs {
+    fn check(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.train_split) {
+            anyhow::bail!("--train-split must be between 0.0 and 1.0, got {}", self.train_split);
+        }
+        if self.test_split < 0.0 || self.train_split + self.test_split > 1.0 {
+            anyhow::bail!("--train-split and --test-split must add up to at most 1.0");
+        }
+        Ok(())
+    }
+
+    // --kfold puts each fold in a split of its own; `file_test` is whether --split-by-file sends
+    // files to test
+    fn split_names(&self, file_test: bool) -> Vec<String> {
+        if let Some(folds) = self.kfold {
+            return (0..folds).map(|fold| format!("fold_{}", fold)).collect();
+        }
+        let mut names = vec!["train".to_string(), "val".to_string()];
+        if self.test_split > 0.0 || self.test_list.is_some() || file_test || self.split_regex.iter().any(|rule| rule.split == 2) {
+            names.push("test".to_string());
+        }
+        names
+    }
 }
 
-// DAMM format dataset
-#[derive(Debug, Deserialize)]
-struct DammDataset {
-    annotations: Vec<DammImage>,
+#[derive(Subcommand)]
+enum Command {
+    /// Convert a YOLO images/labels tree with classes.txt (or data.yaml) back to a standard COCO JSON
+    Yolo2coco(Yolo2CocoArgs),
+    /// Split a flat YOLO dataset (images and their label files in one folder) into train/val/test
+    Split(Box<SplitCommandArgs>),
+}
+
+#[derive(clap::Args)]
+struct SplitCommandArgs {
+    /// Folder with the images, their <stem>.txt labels and classes.txt (or data.yaml), or a .zip archive of one
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// Output directory for the split dataset
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// How images get into the output: 'copy', 'hardlink' (copies across filesystems), 'symlink' or 'move'
+    #[arg(long, value_enum, default_value_t = ImageMode::Copy)]
+    image_mode: ImageMode,
+
+    #[command(flatten)]
+    split: SplitArgs,
+}
+
+#[derive(clap::Args)]
+struct Yolo2CocoArgs {
+    /// YOLO dataset directory, or a .zip archive of one
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// COCO JSON file to write
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Task {
+    /// class x_center y_center width height
+    Detect,
+    /// class x1 y1 x2 y2 x3 y3 x4 y4
+    Obb,
+    /// class x1 y1 x2 y2 ... (polygon)
+    Segment,
+    /// class x_center y_center width height px1 py1 v1 ... (keypoints)
+    Pose,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ObbCoords {
+    /// Corners divided by the image width and height
+    Normalized,
+    /// Corners in pixels, rounded to integers
+    Pixel,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    /// Pascal VOC XML per image, in Annotations/ next to the labels
+    Voc,
+    /// annotations.csv with one row per box across all splits
+    Csv,
+    /// dataset.db with images, annotations and categories tables
+    Sqlite,
+    /// annotations.parquet with one row per box and its image's metadata
+    Parquet,
+    /// TF Object Detection API records in tfrecord/, with images embedded
+    Tfrecord,
+    /// WebDataset tar shards of image + label pairs in webdataset/<split>/
+    Webdataset,
+    /// Hugging Face imagefolder metadata.jsonl in each split directory
+    Huggingface,
+    /// CVAT for images 1.1 XML (cvat/annotations.xml) for re-annotation
+    Cvat,
+    /// Standard COCO JSON per split (coco/<split>.json) after filtering
+    Coco,
+    /// FiftyOneImageDetectionDataset per split in fiftyone/<split>/
+    Fiftyone,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Layout {
+    /// <split>/images/img.jpg and <split>/labels/img.txt
+    Ultralytics,
+    /// <split>/img.jpg and <split>/img.txt side by side
+    FlatPerSplit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum GroupBy {
+    /// One class per category
+    Category,
+    /// One class per COCO supercategory
+    Supercategory,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OobPolicy {
+    /// Clip boxes, polygons and oriented boxes to the image; drop boxes left with no area
+    Clip,
+    /// Drop annotations reaching past the image
+    Drop,
+    /// Fail on the first annotation reaching past the image
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Crowd {
+    /// Drop crowd annotations
+    Skip,
+    /// Convert them like any other annotation
+    Keep,
+    /// Give each class's crowd annotations their own class, named <class>_crowd
+    SeparateClass,
+}
+
+// --min-area / --max-area: box area in pixels, or a percentage of the image area with a % suffix
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AreaThreshold {
+    Pixels(f64),
+    Percent(f64),
+}
+
+impl AreaThreshold {
+    fn pixels(self, image: &UnifiedImage) -> f64 {
+        match self {
+            AreaThreshold::Pixels(pixels) => pixels,
+            AreaThreshold::Percent(percent) => percent / 100.0 * image.width as f64 * image.height as f64,
+        }
+    }
+}
+
+impl std::str::FromStr for AreaThreshold {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (number, percent) = match value.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (value, false),
+        };
+        match number.parse::<f64>() {
+            Ok(number) if number >= 0.0 => Ok(if percent { AreaThreshold::Percent(number) } else { AreaThreshold::Pixels(number) }),
+            _ => Err(format!("expected a non-negative pixel area or percentage like 0.5%, got '{}'", value)),
+        }
+    }
+}
+
+// --min-box-size: WxH in pixels, or a single number for both
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BoxSize {
+    width: f64,
+    height: f64,
+}
+
+impl std::str::FromStr for BoxSize {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse = |number: &str| number.trim().parse::<f64>().ok().filter(|number| *number >= 0.0);
+        let size = match value.split_once(['x', 'X']) {
+            Some((width, height)) => parse(width).zip(parse(height)),
+            None => parse(value).map(|size| (size, size)),
+        };
+        size.map(|(width, height)| BoxSize { width, height })
+            .ok_or_else(|| format!("expected WxH in pixels like 4x4, got '{}'", value))
+    }
+}
+
+// --sample: a number of images, or a fraction of them when written with a decimal point
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Sample {
+    Count(usize),
+    Fraction(f64),
+}
+
+impl std::str::FromStr for Sample {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected an image count like 500 or a fraction like 0.1, got '{}'", value);
+        if value.contains('.') {
+            match value.parse::<f64>() {
+                Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(Sample::Fraction(fraction)),
+                _ => Err(invalid()),
+            }
+        } else {
+            value.parse::<usize>().map(Sample::Count).map_err(|_| invalid())
+        }
+    }
+}
+
+// --split-group: what keeps images together in one split
+#[derive(Clone, Debug)]
+enum SplitGroup {
+    /// Directory part of the image file name (or of its source path)
+    Dirname,
+    /// The video or sequence of the frame (COCO video_id, MOT sequence)
+    VideoId,
+    /// First capture group of a regex over the file name, or the whole match
+    Regex(Regex),
+}
+
+impl std::str::FromStr for SplitGroup {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "dirname" => Ok(SplitGroup::Dirname),
+            "video_id" | "video-id" => Ok(SplitGroup::VideoId),
+            _ => {
+                let pattern = value
+                    .strip_prefix("regex=")
+                    .ok_or_else(|| format!("expected dirname, video_id or regex=<pattern>, got '{}'", value))?;
+                Regex::new(pattern).map(SplitGroup::Regex).map_err(|error| format!("invalid regex: {}", error))
+            }
+        }
+    }
+}
+
+impl SplitGroup {
+    // Images without a key are groups of their own
+    fn key(&self, image: &UnifiedImage) -> Option<String> {
+        match self {
+            SplitGroup::Dirname => {
+                let parent = |path: &Path| path.parent().map(|dir| dir.to_string_lossy().into_owned()).filter(|dir| !dir.is_empty());
+                parent(Path::new(&image.file_name)).or_else(|| image.source_path.as_deref().and_then(parent))
+            }
+            SplitGroup::VideoId => image.video_id.clone(),
+            SplitGroup::Regex(pattern) => pattern.captures(&image.file_name).map(|captures| {
+                captures.get(1).or_else(|| captures.get(0)).map_or("", |m| m.as_str()).to_string()
+            }),
+        }
+    }
+}
+
+// --split-by-file: the split of each source annotation file, by file name glob
+#[derive(Clone, Debug)]
+struct SplitByFile(Vec<(usize, Regex)>); // (index in train/val/test, file name pattern)
+
+impl std::str::FromStr for SplitByFile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (split, glob) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("expected split=glob, got '{}'", entry))?;
+            let split = ["train", "val", "test"]
+                .iter()
+                .position(|name| *name == split.trim())
+                .ok_or_else(|| format!("unknown split '{}', expected train, val or test", split.trim()))?;
+            let pattern: String = glob
+                .trim()
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    _ => regex::escape(&c.to_string()),
+                })
+                .collect();
+            let pattern = Regex::new(&format!("^{}$", pattern)).map_err(|error| format!("invalid glob '{}': {}", glob, error))?;
+            entries.push((split, pattern));
+        }
+        if entries.is_empty() {
+            return Err("expected at least one split=glob entry".to_string());
+        }
+        Ok(SplitByFile(entries))
+    }
+}
+
+impl SplitByFile {
+    // Split of the first entry matching the annotation file name
+    fn split_of(&self, file_name: &str) -> Option<usize> {
+        self.0.iter().find(|(_, pattern)| pattern.is_match(file_name)).map(|(split, _)| *split)
+    }
+
+    fn has_test(&self) -> bool {
+        self.0.iter().any(|(split, _)| *split == 2)
+    }
+}
+
+// --split-regex: images whose file name matches `pattern` go to `split` (index in train/val/test)
+#[derive(Clone, Debug)]
+struct SplitRule {
+    split: usize,
+    pattern: Regex,
+}
+
+impl std::str::FromStr for SplitRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (split, pattern) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected split=regex, got '{}'", value))?;
+        let split = ["train", "val", "test"]
+            .iter()
+            .position(|name| *name == split)
+            .ok_or_else(|| format!("unknown split '{}', expected train, val or test", split))?;
+        let pattern = Regex::new(pattern).map_err(|error| format!("invalid regex: {}", error))?;
+        Ok(SplitRule { split, pattern })
+    }
+}
+
+// --background-images: what happens to images without annotations
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BackgroundImages {
+    /// Keep them, and add images under --input that no annotation file references
+    Include,
+    /// Drop them
+    Exclude,
+    /// Like include, but randomly keep only enough that they make up at most this fraction of the images
+    Ratio(f64),
+}
+
+impl std::str::FromStr for BackgroundImages {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "include" => Ok(BackgroundImages::Include),
+            "exclude" => Ok(BackgroundImages::Exclude),
+            _ => {
+                let ratio = value
+                    .strip_prefix("ratio=")
+                    .ok_or_else(|| format!("expected include, exclude or ratio=<fraction>, got '{}'", value))?;
+                match ratio.parse::<f64>() {
+                    Ok(ratio) if (0.0..1.0).contains(&ratio) => Ok(BackgroundImages::Ratio(ratio)),
+                    _ => Err(format!("ratio must be a number in [0, 1), got '{}'", ratio)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SplitStrategy {
+    /// Seeded shuffle, then cut by the split ratios
+    Random,
+    /// Hash of the image file name against the split ratios
+    Hash,
+    /// Chronological by --date-field: the earliest images train, the latest val (then test)
+    Date,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Balance {
+    /// Drop training images containing only over-represented classes
+    Undersample,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SchemaFormat {
+    Damm,
+    #[value(alias = "standard")]
+    Coco,
+}
+
+// Image/annotation id: Objects365 and others exceed u32, and some tools emit strings
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum CocoId {
+    Int(u64),
+    Str(String),
+}
+
+impl std::fmt::Display for CocoId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CocoId::Int(id) => write!(f, "{}", id),
+            CocoId::Str(id) => write!(f, "{}", id),
+        }
+    }
 }
 
 // Standard COCO format annotation
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct CocoAnnotation {
-    id: u32,
-    image_id: u32,
+    id: CocoId,
+    image_id: CocoId,
+    #[serde(flatten)]
+    fields: CocoAnnotationFields,
+}
+
+// An annotation apart from its ids, shared with the annotations nested in NDJSON image records
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CocoAnnotationFields {
     category_id: u32,
     bbox: Vec<f64>, // [x, y, width, height] format (standard COCO)
+    #[serde(default)]
     area: f64,
     #[serde(default)]
     iscrowd: u32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     segmentation: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keypoints: Option<Vec<f64>>, // [x1, y1, v1, x2, y2, v2, ...] with v 0 = unlabeled, 1 = occluded, 2 = visible
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>, // attributes, score, track_id and any custom fields
 }
 
 // Standard COCO format image
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct CocoImageInfo {
-    id: u32,
+    id: CocoId,
     file_name: String,
     height: u32,
     width: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    coco_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    video_id: Option<CocoId>, // COCO-VID style video the frame belongs to
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>, // date_captured, license and any custom fields
+}
+
+// Standard COCO format category
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+struct CocoCategory {
+    id: u32,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    supercategory: Option<String>,
 }
 
 // Standard COCO format dataset
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 struct CocoDataset {
     images: Vec<CocoImageInfo>,
     annotations: Vec<CocoAnnotation>,
-    #[serde(default)]
-    categories: Option<Vec<serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    categories: Option<Vec<CocoCategory>>,
+}
+
+// Ultralytics data.yaml, only the parts needed to compare class lists
+#[derive(Debug, Deserialize)]
+struct DataYaml {
+    names: DataYamlNames,
+}
+
+// `names` may be a list or an index → name map
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DataYamlNames {
+    List(Vec<String>),
+    Map(BTreeMap<u32, String>),
 }
 
 // Unified annotation format for processing
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 struct UnifiedAnnotation {
     bbox: Vec<f64>, // Always in [x1, y1, x2, y2] format
     category_id: u32,
+    category_name: Option<String>, // Only known when the source format carries names
+    supercategory: Option<String>, // Parent category, from COCO-style categories
+    iscrowd: bool, // COCO crowd region covering several objects
+    obb: Option<[f64; 8]>, // Oriented box corners [x1, y1, ..., x4, y4] in pixels, from rotated-box sources
+    track_id: Option<i64>, // Object identity across frames, from tracking sources
+    segmentation: Option<Vec<f64>>, // Polygon [x1, y1, x2, y2, ...] in pixels, from mask sources
+    keypoints: Option<Vec<f64>>, // [x1, y1, v1, ...] in pixels with COCO visibility flags
+    metadata: serde_json::Map<String, serde_json::Value>, // Source fields with no unified equivalent
 }
 
 // Unified image format for processing
-#[derive(Debug)]
+#[derive(Clone, Debug, Default)]
 struct UnifiedImage {
     file_name: String,
+    source_url: Option<String>, // Remote location of the image, if the annotation file references one
+    source_path: Option<PathBuf>, // Local image path, for sources whose file_name isn't unique under --input
+    image_id: Option<String>, // Id of the image in the source annotation file, for formats that have one
+    video_id: Option<String>, // Video or sequence the frame belongs to
+    annotation_file: Option<String>, // Name of the annotation file the image was read from
     height: u32,
     width: u32,
     annotations: Vec<UnifiedAnnotation>,
+    metadata: serde_json::Map<String, serde_json::Value>, // Source image fields with no unified equivalent
 }
 
 #[derive(Debug)]
@@ -146,358 +967,3321 @@ impl YoloAnnotation {
         }
     }
 
-    fn to_string(&self) -> String {
-        format!(
-            "{} {:.6} {:.6} {:.6} {:.6}",
-            self.class_id, self.x_center, self.y_center, self.width, self.height
-        )
-    }
 }
 
-
-fn parse_damm_format(content: &str) -> Result<Vec<UnifiedImage>> {
-    let dataset: DammDataset = serde_json::from_str(content)?;
-    let mut unified_images = Vec::new();
+// Convex hull of flat [x1, y1, x2, y2, ...] points (monotone chain), counter-clockwise in image coordinates
+fn convex_hull(points: &[f64]) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = points.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect();
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
     
-    for damm_image in dataset.annotations {
-        let mut unified_annotations = Vec::new();
-        
-        for damm_ann in damm_image.annotations {
-            // Convert DAMM [[x1, y1], [x2, y2]] to unified [x1, y1, x2, y2]
-            let unified_ann = UnifiedAnnotation {
-                bbox: vec![damm_ann.bbox[0][0], damm_ann.bbox[0][1], damm_ann.bbox[1][0], damm_ann.bbox[1][1]],
-                category_id: damm_ann.category_id,
-            };
-            unified_annotations.push(unified_ann);
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    let mut hull: Vec<(f64, f64)> = Vec::new();
+    for pass in [&points[..], &points.iter().rev().copied().collect::<Vec<_>>()[..]] {
+        let start = hull.len();
+        for &point in pass {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
         }
-        
-        let unified_image = UnifiedImage {
-            file_name: damm_image.file_name,
-            height: damm_image.height,
-            width: damm_image.width,
-            annotations: unified_annotations,
-        };
-        unified_images.push(unified_image);
+        hull.pop();
     }
-    
-    Ok(unified_images)
+    hull
 }
 
-fn parse_standard_format(content: &str) -> Result<Vec<UnifiedImage>> {
-    let dataset: CocoDataset = serde_json::from_str(content)?;
-    let mut unified_images = Vec::new();
-    
-    // Create a map of image_id to image info
-    let mut image_map: HashMap<u32, &CocoImageInfo> = HashMap::new();
-    for image in &dataset.images {
-        image_map.insert(image.id, image);
+// Minimum-area rectangle around a polygon. One side of the optimal rectangle is collinear with a
+// convex hull edge, so each edge direction is tried. None for degenerate polygons.
+fn min_area_rect(polygon: &[f64]) -> Option<[f64; 8]> {
+    let hull = convex_hull(polygon);
+    if hull.len() < 3 {
+        return None;
     }
     
-    // Group annotations by image_id
-    let mut annotations_by_image: HashMap<u32, Vec<&CocoAnnotation>> = HashMap::new();
-    for annotation in &dataset.annotations {
-        annotations_by_image.entry(annotation.image_id)
-            .or_insert_with(Vec::new)
-            .push(annotation);
+    let mut best: Option<(f64, [f64; 8])> = None;
+    for (i, &a) in hull.iter().enumerate() {
+        let b = hull[(i + 1) % hull.len()];
+        let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+        let (ux, uy) = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+        let (mut u_min, mut u_max, mut v_min, mut v_max) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for &(x, y) in &hull {
+            let (u, v) = (x * ux + y * uy, -x * uy + y * ux);
+            u_min = u_min.min(u);
+            u_max = u_max.max(u);
+            v_min = v_min.min(v);
+            v_max = v_max.max(v);
+        }
+        let area = (u_max - u_min) * (v_max - v_min);
+        if best.is_none_or(|(best_area, _)| area < best_area) {
+            let corner = |u: f64, v: f64| (u * ux - v * uy, u * uy + v * ux);
+            let [(x1, y1), (x2, y2), (x3, y3), (x4, y4)] =
+                [corner(u_min, v_min), corner(u_max, v_min), corner(u_max, v_max), corner(u_min, v_max)];
+            best = Some((area, [x1, y1, x2, y2, x3, y3, x4, y4]));
+        }
     }
-    
-    // Convert to unified format
-    for (image_id, image_info) in image_map {
-        let mut unified_annotations = Vec::new();
-        
-        if let Some(annotations) = annotations_by_image.get(&image_id) {
-            for coco_ann in annotations {
-                // Convert COCO [x, y, width, height] to unified [x1, y1, x2, y2]
-                let x1 = coco_ann.bbox[0];
-                let y1 = coco_ann.bbox[1];
-                let x2 = x1 + coco_ann.bbox[2];
-                let y2 = y1 + coco_ann.bbox[3];
-                
-                let unified_ann = UnifiedAnnotation {
-                    bbox: vec![x1, y1, x2, y2],
-                    category_id: coco_ann.category_id,
-                };
-                unified_annotations.push(unified_ann);
-            }
-        }
-        
-        let unified_image = UnifiedImage {
-            file_name: image_info.file_name.clone(),
-            height: image_info.height,
-            width: image_info.width,
-            annotations: unified_annotations,
-        };
-        unified_images.push(unified_image);
+    best.map(|(_, corners)| corners)
+}
+
+// How label lines are written: the task's line layout, decimals per coordinate, the value added
+// to every class id and whether OBB corners are normalized or pixels
+#[derive(Clone, Copy)]
+struct LabelStyle {
+    task: Task,
+    precision: usize,
+    class_offset: u32,
+    obb_coords: ObbCoords,
+}
+
+// Append pixel points [x1, y1, x2, y2, ...] to a label line, normalized by the image size
+fn push_normalized_points(line: &mut String, points: &[f64], img_width: u32, img_height: u32, precision: usize) {
+    for (i, value) in points.iter().enumerate() {
+        let size = if i % 2 == 0 { img_width } else { img_height };
+        line.push_str(&format!(" {:.*}", precision, value / size as f64));
     }
-    
-    Ok(unified_images)
 }
 
-fn find_image_file(input_dir: &Path, image_filename: &str) -> Option<PathBuf> {
-    // Common image extensions to search for
-    let extensions = ["jpg", "jpeg", "png", "bmp", "tiff", "tif"];
+// YOLO-OBB line with the four corners normalized, or as integer pixels with --obb-coords pixel.
+// Annotations without a rotated box (see `fit_obbs`) use the corners of their axis-aligned box.
+fn obb_line(ann: &UnifiedAnnotation, img_width: u32, img_height: u32, style: LabelStyle) -> String {
+    let corners = ann.obb
+        .unwrap_or_else(|| {
+            let (x1, y1, x2, y2) = (ann.bbox[0], ann.bbox[1], ann.bbox[2], ann.bbox[3]);
+            [x1, y1, x2, y1, x2, y2, x1, y2]
+        });
     
-    // Try with the exact filename first
-    for entry in WalkDir::new(input_dir).into_iter().filter_map(|e| e.ok()) {
-        if let Some(file_name) = entry.path().file_name() {
-            if file_name.to_str().unwrap_or("") == image_filename {
-                return Some(entry.path().to_path_buf());
+    let mut line = (ann.category_id + style.class_offset).to_string();
+    match style.obb_coords {
+        ObbCoords::Normalized => push_normalized_points(&mut line, &corners, img_width, img_height, style.precision),
+        ObbCoords::Pixel => {
+            for value in corners {
+                line.push_str(&format!(" {}", value.round() as i64));
             }
         }
     }
+    line
+}
+
+// YOLO-seg line with the polygon points normalized. Annotations without a polygon use the corners of their box.
+fn segment_line(ann: &UnifiedAnnotation, img_width: u32, img_height: u32, style: LabelStyle) -> String {
+    let points = ann.segmentation.clone().unwrap_or_else(|| {
+        let (x1, y1, x2, y2) = (ann.bbox[0], ann.bbox[1], ann.bbox[2], ann.bbox[3]);
+        vec![x1, y1, x2, y1, x2, y2, x1, y2]
+    });
     
-    // If not found, try with different extensions
-    let base_name = Path::new(image_filename).file_stem()?.to_str()?;
-    for ext in &extensions {
-        let search_name = format!("{}.{}", base_name, ext);
-        for entry in WalkDir::new(input_dir).into_iter().filter_map(|e| e.ok()) {
-            if let Some(file_name) = entry.path().file_name() {
-                if file_name.to_str().unwrap_or("") == search_name {
-                    return Some(entry.path().to_path_buf());
-                }
-            }
+    let mut line = (ann.category_id + style.class_offset).to_string();
+    push_normalized_points(&mut line, &points, img_width, img_height, style.precision);
+    line
+}
+
+fn detect_line(ann: &UnifiedAnnotation, img_width: u32, img_height: u32, style: LabelStyle) -> String {
+    let mut yolo = YoloAnnotation::from_unified(ann, img_width, img_height);
+    yolo.class_id += style.class_offset;
+    format!("{:.*}", style.precision, yolo)
+}
+
+// YOLO pose line: the detect line followed by normalized keypoint triplets. Unlabeled keypoints
+// (visibility 0) are written as 0 0 0, as Ultralytics expects.
+fn pose_line(ann: &UnifiedAnnotation, img_width: u32, img_height: u32, style: LabelStyle) -> String {
+    let mut line = detect_line(ann, img_width, img_height, style);
+    for keypoint in ann.keypoints.as_deref().unwrap_or_default().chunks_exact(3) {
+        let (x, y, visibility) = (keypoint[0], keypoint[1], keypoint[2]);
+        if visibility == 0.0 {
+            line.push_str(" 0 0 0");
+        } else {
+            push_normalized_points(&mut line, &[x, y], img_width, img_height, style.precision);
+            line.push_str(&format!(" {}", visibility));
         }
     }
-    
-    None
+    line
 }
 
-fn convert_coco_to_yolo(
-    input_dir: &Path, 
-    output_dir: &Path, 
-    create_classes: bool, 
-    format: &str,
-    train_split: f64,
-    yolo_structure: bool
-) -> Result<()> {
-    fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+// Pad every annotation's keypoints with unlabeled triplets to the largest keypoint count, since
+// YOLO pose labels need the same number of keypoints on every line. Returns that count.
+fn pad_keypoints(images: &mut [UnifiedImage]) -> usize {
+    let keypoint_count = images
+        .iter()
+        .flat_map(|image| &image.annotations)
+        .filter_map(|annotation| annotation.keypoints.as_ref())
+        .map(|keypoints| keypoints.len() / 3)
+        .max()
+        .unwrap_or(0);
+    for annotation in images.iter_mut().flat_map(|image| &mut image.annotations) {
+        let keypoints = annotation.keypoints.get_or_insert_with(Vec::new);
+        keypoints.truncate(keypoints.len() / 3 * 3);
+        keypoints.resize(keypoint_count * 3, 0.0);
+    }
+    keypoint_count
+}
 
-    let mut all_images = Vec::new();
-    let mut class_names = HashMap::new();
-    let mut processed_files = 0;
-    let mut total_annotations = 0;
+// Label line for one annotation in the requested task format
+fn label_line(ann: &UnifiedAnnotation, image: &UnifiedImage, style: LabelStyle) -> String {
+    match style.task {
+        Task::Detect => detect_line(ann, image.width, image.height, style),
+        Task::Obb => obb_line(ann, image.width, image.height, style),
+        Task::Segment => segment_line(ann, image.width, image.height, style),
+        Task::Pose => pose_line(ann, image.width, image.height, style),
+    }
+}
 
-    println!("Using format: {}", format);
-    println!("Scanning for metadata files...");
+// Coordinates use the formatter's precision, 6 decimals by default
+impl fmt::Display for YoloAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(6);
+        write!(
+            f,
+            "{} {:.*} {:.*} {:.*} {:.*}",
+            self.class_id, precision, self.x_center, precision, self.y_center, precision, self.width, precision, self.height
+        )
+    }
+}
+
+
+// When file_name is itself a URL, use its last path segment as the local filename and keep the URL as the source
+fn split_image_url(file_name: String, url: Option<String>) -> (String, Option<String>) {
+    if download::is_url(&file_name) {
+        (download::url_file_name(&file_name).to_string(), Some(file_name))
+    } else {
+        (file_name, url.filter(|url| download::is_url(url)))
+    }
+}
+
+// Common image extensions to search for
+const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "bmp", "tiff", "tif"];
+
+// Read the image file names of a --train-list/--val-list/--test-list or --only-images. Entries may
+// be paths; only their file name is matched. Blank lines and lines starting with '#' are ignored.
+fn read_image_list(path: &Path) -> Result<HashSet<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read image list: {}", path.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Path::new(line).file_name().map_or(line.to_string(), |name| name.to_string_lossy().into_owned()))
+        .collect())
+}
+
+// Assign images to splits from the split image lists, dropping images in none of them. Returns the
+// size of each split and the number of dropped images.
+fn list_split(images: &mut Vec<UnifiedImage>, lists: &[HashSet<String>]) -> (Vec<usize>, usize) {
+    let before = images.len();
+    let split_of = |image: &UnifiedImage| {
+        let file_name = Path::new(&image.file_name).file_name()?.to_str()?;
+        lists.iter().position(|list| list.contains(file_name))
+    };
+    images.retain(|image| split_of(image).is_some());
+    let assigned: Vec<usize> = images.iter().map(|image| split_of(image).unwrap_or(0)).collect();
+    let dropped = before - images.len();
+    (order_by_split(images, &assigned, lists.len()), dropped)
+}
+
+// Assign images to the split of the annotation file they came from, dropping images of files no
+// --split-by-file entry matches. Returns the size of each split and the number of dropped images.
+fn file_split(images: &mut Vec<UnifiedImage>, split_by_file: &SplitByFile, split_count: usize) -> (Vec<usize>, usize) {
+    let before = images.len();
+    let split_of = |image: &UnifiedImage| image.annotation_file.as_deref().and_then(|file| split_by_file.split_of(file));
+    images.retain(|image| split_of(image).is_some());
+    let assigned: Vec<usize> = images.iter().map(|image| split_of(image).unwrap_or(0)).collect();
+    let dropped = before - images.len();
+    (order_by_split(images, &assigned, split_count), dropped)
+}
+
+// Read annotation file paths from a manifest, one per line, preserving their order.
+// Blank lines and lines starting with '#' are ignored; relative paths resolve against the manifest's directory.
+fn read_json_list(manifest: &Path) -> Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(manifest)
+        .with_context(|| format!("Failed to read JSON list: {}", manifest.display()))?;
+    let base_dir = manifest.parent().unwrap_or_else(|| Path::new(""));
     
-    // Find all JSON files first
     let mut json_files = Vec::new();
-    for entry in WalkDir::new(input_dir).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) == Some("json") {
-            json_files.push(path.to_path_buf());
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        
+        let path = base_dir.join(line);
+        if !path.is_file() {
+            anyhow::bail!("JSON file listed in {} does not exist: {}", manifest.display(), path.display());
         }
+        json_files.push(path);
     }
     
-    if json_files.is_empty() {
-        anyhow::bail!("No JSON files found in input directory");
+    Ok(json_files)
+}
+
+// Name written to classes.txt for an annotation's class: the source's category name, or class_<id>
+// for formats that don't carry names
+fn class_name(annotation: &UnifiedAnnotation) -> String {
+    annotation.category_name.clone().unwrap_or_else(|| format!("class_{}", annotation.category_id))
+}
+
+// Name the class indices no annotation uses: with `fill_gaps` (indices chosen by the user) every
+// index below the highest one is named class_<id>, and every --names entry keeps its index
+fn fill_class_names(class_names: &mut HashMap<u32, String>, names: &[Option<String>], fill_gaps: bool) {
+    if fill_gaps && let Some(&max_id) = class_names.keys().max() {
+        let gaps: Vec<u32> = (0..max_id).filter(|id| !class_names.contains_key(id)).collect();
+        if !gaps.is_empty() {
+            println!(
+                "Warning: {} class indices below {} have no category; classes.txt and data.yaml name them class_<id>",
+                gaps.len(), max_id
+            );
+        }
+        for id in gaps {
+            class_names.insert(id, format!("class_{}", id));
+        }
+    }
+    for (index, name) in formats::yolo::slot_names(names).into_iter().enumerate() {
+        class_names.entry(index as u32).or_insert(name);
+    }
+}
+
+// Class names by written index. --class-offset shifts every class, and the slots below it are named
+// class_<index> so classes.txt, data.yaml and obj.names still list all nc classes from index 0
+fn offset_class_names(class_names: &HashMap<u32, String>, class_offset: u32) -> BTreeMap<u32, String> {
+    let mut names: BTreeMap<u32, String> = (0..class_offset).map(|index| (index, format!("class_{}", index))).collect();
+    names.extend(class_names.iter().map(|(id, name)| (id + class_offset, name.clone())));
+    names
+}
+
+// Class names in class index order
+fn sorted_class_names(class_names: &HashMap<u32, String>) -> Vec<String> {
+    let mut sorted_classes: Vec<_> = class_names.iter().collect();
+    sorted_classes.sort_by_key(|(id, _)| **id);
+    sorted_classes.into_iter().map(|(_, name)| name.clone()).collect()
+}
+
+// Weight of each class in class id order: total instances / (classes * class instances), so a
+// class with the average count weighs 1. Classes without instances get 0. Rounded to 6 decimals.
+fn inverse_frequency_weights(class_names: &HashMap<u32, String>, instance_counts: &HashMap<u32, usize>) -> Vec<f64> {
+    let mut ids: Vec<u32> = class_names.keys().copied().collect();
+    ids.sort();
+    let total: usize = ids.iter().map(|id| instance_counts.get(id).copied().unwrap_or(0)).sum();
+    ids.iter()
+        .map(|id| match instance_counts.get(id).copied().unwrap_or(0) {
+            0 => 0.0,
+            count => (total as f64 / (ids.len() * count) as f64 * 1e6).round() / 1e6,
+        })
+        .collect()
+}
+
+fn read_data_yaml_names(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read data.yaml: {}", path.display()))?;
+    parse_data_yaml_names(&content)
+        .with_context(|| format!("Failed to parse data.yaml: {}", path.display()))
+}
+
+fn parse_data_yaml_names(content: &str) -> Result<Vec<String>> {
+    let data: DataYaml = serde_yaml::from_str(content)?;
+    
+    Ok(match data.names {
+        DataYamlNames::List(names) => names,
+        DataYamlNames::Map(names) => names.into_values().collect(),
+    })
+}
+
+// Class names of an existing YOLO dataset, from classes.txt or else data.yaml at the top of the input
+fn load_yolo_class_names(input: &InputSource) -> Result<Vec<Option<String>>> {
+    let classes_path = input.root_file("classes.txt");
+    if input.contains(&classes_path) {
+        let content = input.read_annotation(&classes_path)?;
+        return formats::yolo::parse_class_names(&content)
+            .with_context(|| format!("Invalid classes file: {}", classes_path.display()));
     }
     
-    println!("Found {} JSON files", json_files.len());
+    let data_yaml_path = input.root_file("data.yaml");
+    if input.contains(&data_yaml_path) {
+        let content = input.read_annotation(&data_yaml_path)?;
+        let names = parse_data_yaml_names(&content)
+            .with_context(|| format!("Failed to parse data.yaml: {}", data_yaml_path.display()))?;
+        return Ok(names.into_iter().map(Some).collect());
+    }
     
-    // Create progress bar for JSON parsing
-    let pb_parse = ProgressBar::new(json_files.len() as u64);
-    pb_parse.set_style(
-        ProgressStyle::with_template(
-            "Parsing JSON    [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} {msg}"
-        )?
-        .progress_chars("#>-")
-    );
+    println!("Warning: no classes.txt or data.yaml in the input; classes are named by index");
+    Ok(Vec::new())
+}
+
+// Print added/removed/reordered classes between an existing class list and the new one.
+// Returns the number of differences found.
+fn report_class_drift(existing: &[String], new: &[String]) -> usize {
+    let existing_index: HashMap<&str, usize> = existing.iter().enumerate().map(|(i, name)| (name.as_str(), i)).collect();
+    let new_index: HashMap<&str, usize> = new.iter().enumerate().map(|(i, name)| (name.as_str(), i)).collect();
+    let mut differences = 0;
     
-    // Parse all JSON files with progress bar
-    for json_file in &json_files {
-        let filename = json_file.file_name().unwrap_or_default().to_string_lossy();
-        pb_parse.set_message(format!("Processing {}", filename));
-        
-        let content = fs::read_to_string(json_file)
-            .with_context(|| format!("Failed to read file: {}", json_file.display()))?;
-        
-        let unified_images = match format {
-            "standard" => {
-                parse_standard_format(&content)
-                    .with_context(|| format!("Failed to parse as standard COCO format: {}", json_file.display()))?
-            },
-            "damm" => {
-                parse_damm_format(&content)
-                    .with_context(|| format!("Failed to parse as DAMM format: {}", json_file.display()))?
-            },
-            _ => {
-                anyhow::bail!("Invalid format '{}'. Use 'standard' or 'damm'", format);
+    for (idx, name) in new.iter().enumerate() {
+        match existing_index.get(name.as_str()) {
+            None => {
+                println!("  added:     {} (index {})", name, idx);
+                differences += 1;
             }
-        };
-
-        all_images.extend(unified_images);
-        processed_files += 1;
-        pb_parse.inc(1);
+            Some(&old_idx) if old_idx != idx => {
+                println!("  reordered: {} (index {} -> {})", name, old_idx, idx);
+                differences += 1;
+            }
+            Some(_) => {}
+        }
+    }
+    for (idx, name) in existing.iter().enumerate() {
+        if !new_index.contains_key(name.as_str()) {
+            println!("  removed:   {} (index {})", name, idx);
+            differences += 1;
+        }
     }
     
-    pb_parse.finish_with_message("JSON parsing complete");
+    differences
+}
 
-    let total_images = all_images.len();
-    println!("Found {} images total", total_images);
+// Drop annotations whose category name does not match the pattern.
+// Returns (kept, dropped) annotation counts.
+fn filter_categories_by_regex(images: &mut [UnifiedImage], pattern: &Regex) -> Result<(usize, usize)> {
+    let mut kept = 0;
+    let mut dropped = 0;
     
-    if yolo_structure {
-        // Create professional YOLO directory structure
-        let train_images_dir = output_dir.join("train").join("images");
-        let train_labels_dir = output_dir.join("train").join("labels");
-        let val_images_dir = output_dir.join("val").join("images");
-        let val_labels_dir = output_dir.join("val").join("labels");
-        
-        fs::create_dir_all(&train_images_dir)?;
-        fs::create_dir_all(&train_labels_dir)?;
-        fs::create_dir_all(&val_images_dir)?;
-        fs::create_dir_all(&val_labels_dir)?;
-        
-        // Shuffle images for random split
-        let mut rng = rand::thread_rng();
-        let mut images = all_images;
-        images.shuffle(&mut rng);
-        
-        let train_count = (images.len() as f64 * train_split) as usize;
-        
-        println!("Split: {} training, {} validation images", train_count, images.len() - train_count);
-        
-        // Create progress bar for image processing
-        let pb_images = ProgressBar::new(images.len() as u64);
-        pb_images.set_style(
-            ProgressStyle::with_template(
-                "Processing     [{elapsed_precise}] [{bar:40.green/blue}] {pos:>7}/{len:7} {msg}"
-            )?
-            .progress_chars("#>-")
-        );
-        
-        let mut missing_images = 0;
-        
-        for (idx, image) in images.iter().enumerate() {
-            let is_train = idx < train_count;
-            let (images_dir, labels_dir, split_name) = if is_train {
-                (&train_images_dir, &train_labels_dir, "train")
-            } else {
-                (&val_images_dir, &val_labels_dir, "val")
-            };
-            
-            // Extract filename from path
-            let image_filename = Path::new(&image.file_name)
-                .file_name()
-                .context("Invalid image filename")?
-                .to_str()
-                .context("Non-UTF8 filename")?;
-            
-            pb_images.set_message(format!("{} - {} ({} ann)", split_name, image_filename, image.annotations.len()));
-            
-            // Find the actual image file
-            if let Some(source_image_path) = find_image_file(input_dir, image_filename) {
-                let dest_image_path = images_dir.join(image_filename);
-                fs::copy(&source_image_path, &dest_image_path)
-                    .with_context(|| format!("Failed to copy image: {}", source_image_path.display()))?;
-                
-                // Create annotation file
-                let base_name = Path::new(image_filename)
-                    .file_stem()
-                    .unwrap()
-                    .to_str()
-                    .unwrap();
-                let annotation_path = labels_dir.join(format!("{}.txt", base_name));
-                
-                let mut yolo_annotations = Vec::new();
-                for annotation in &image.annotations {
-                    let yolo_ann = YoloAnnotation::from_unified(annotation, image.width, image.height);
-                    yolo_annotations.push(yolo_ann.to_string());
-                    class_names.insert(annotation.category_id, format!("class_{}", annotation.category_id));
-                    total_annotations += 1;
-                }
-                
-                let content = if yolo_annotations.is_empty() { 
-                    String::new() 
-                } else { 
-                    yolo_annotations.join("\n") + "\n"
-                };
-                
-                fs::write(&annotation_path, content)
-                    .with_context(|| format!("Failed to write annotation file: {}", annotation_path.display()))?;
-            } else {
-                missing_images += 1;
+    for image in images.iter_mut() {
+        for annotation in &image.annotations {
+            if annotation.category_name.is_none() {
+                anyhow::bail!(
+                    "Category {} in {} has no name; --keep-categories-regex requires category names",
+                    annotation.category_id, image.file_name
+                );
             }
-            
-            pb_images.inc(1);
         }
         
-        pb_images.finish_with_message("Image processing complete");
-        
-        if missing_images > 0 {
-            println!("Warning: {} image files not found", missing_images);
+        let before = image.annotations.len();
+        image.annotations.retain(|annotation| {
+            annotation.category_name.as_deref().is_some_and(|name| pattern.is_match(name))
+        });
+        kept += image.annotations.len();
+        dropped += before - image.annotations.len();
+    }
+    
+    Ok((kept, dropped))
+}
+
+// Shuffle images for the train/val split. Sort by filename first so the shuffle starts from the
+// same order on every run and platform, regardless of HashMap iteration order during parsing.
+fn shuffle_for_split(images: &mut [UnifiedImage], rng: &mut impl rand::Rng) {
+    images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    images.shuffle(rng);
+}
+
+// Empty images for the files under the input that no parsed image refers to
+fn unreferenced_images(images: &[UnifiedImage], input: &InputSource) -> Result<Vec<UnifiedImage>> {
+    let referenced: HashSet<&str> = images
+        .iter()
+        .filter_map(|image| Path::new(&image.file_name).file_name()?.to_str())
+        .collect();
+
+    let mut unreferenced = Vec::new();
+    for path in input.image_files() {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else { continue };
+        if referenced.contains(file_name) {
+            continue;
         }
-    } else {
-        // Legacy flat structure
-        for image in &all_images {
-            let image_name = Path::new(&image.file_name)
-                .file_stem()
-                .unwrap_or_default()
-                .to_str()
-                .unwrap_or("unknown");
-            
-            let output_file = output_dir.join(format!("{}.txt", image_name));
-            let mut yolo_annotations = Vec::new();
+        let (width, height) = input.image_size(&path)?;
+        unreferenced.push(UnifiedImage {
+            file_name: file_name.to_string(),
+            source_path: Some(path.clone()),
+            width,
+            height,
+            ..Default::default()
+        });
+    }
+    Ok(unreferenced)
+}
+
+// Apply --background-images. Returns the number of background images added and dropped.
+fn apply_background_policy(images: &mut Vec<UnifiedImage>, policy: BackgroundImages, input: &InputSource, seed: u64) -> Result<(usize, usize)> {
+    let before = images.len();
+    if policy == BackgroundImages::Exclude {
+        images.retain(|image| !image.annotations.is_empty());
+        return Ok((0, before - images.len()));
+    }
+
+    let added = unreferenced_images(images, input)?;
+    let added_count = added.len();
+    images.extend(added);
+    let BackgroundImages::Ratio(ratio) = policy else {
+        return Ok((added_count, 0));
+    };
+
+    let (mut backgrounds, annotated): (Vec<UnifiedImage>, Vec<UnifiedImage>) =
+        images.drain(..).partition(|image| image.annotations.is_empty());
+    // backgrounds / (annotated + backgrounds) <= ratio
+    let allowed = (ratio * annotated.len() as f64 / (1.0 - ratio)).floor() as usize;
+    backgrounds.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    backgrounds.shuffle(&mut StdRng::seed_from_u64(seed));
+    let dropped = backgrounds.len().saturating_sub(allowed);
+    backgrounds.truncate(allowed);
+    images.extend(annotated);
+    images.extend(backgrounds);
+    Ok((added_count, dropped))
+}
+
+// Randomly pick images so no class appears in more than `max_per_class` of them.
+// Returns the number of skipped images.
+fn cap_images_per_class(images: &mut Vec<UnifiedImage>, max_per_class: usize, seed: u64) -> usize {
+    images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    images.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let mut image_counts: HashMap<u32, usize> = HashMap::new();
+    let before = images.len();
+    images.retain(|image| {
+        let classes: HashSet<u32> = image.annotations.iter().map(|annotation| annotation.category_id).collect();
+        if classes.iter().any(|id| image_counts.get(id).copied().unwrap_or(0) >= max_per_class) {
+            return false;
+        }
+        for id in classes {
+            *image_counts.entry(id).or_default() += 1;
+        }
+        true
+    });
+    before - images.len()
+}
 
+// Keep a random subset of the images for --sample. Returns the number of skipped images.
+fn sample_images(images: &mut Vec<UnifiedImage>, sample: Sample, seed: u64) -> usize {
+    let keep = match sample {
+        Sample::Count(count) => count,
+        Sample::Fraction(fraction) => (images.len() as f64 * fraction).round() as usize,
+    };
+    images.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    images.shuffle(&mut StdRng::seed_from_u64(seed));
+    let before = images.len();
+    images.truncate(keep);
+    before - images.len()
+}
+
+// Iterative stratification: the class with the fewest unassigned instances goes first, and each
+// of its images joins the split still wanting the most instances of that class (then the one with
+// the most room). Images end up ordered by split; returns the number of images in each split.
+fn stratify(images: &mut Vec<UnifiedImage>, ratios: &[f64]) -> Vec<usize> {
+    let class_counts: Vec<HashMap<u32, f64>> = images
+        .iter()
+        .map(|image| {
+            let mut counts = HashMap::new();
             for annotation in &image.annotations {
-                let yolo_ann = YoloAnnotation::from_unified(annotation, image.width, image.height);
-                yolo_annotations.push(yolo_ann.to_string());
-                class_names.insert(annotation.category_id, format!("class_{}", annotation.category_id));
-                total_annotations += 1;
+                *counts.entry(annotation.category_id).or_default() += 1.0;
+            }
+            counts
+        })
+        .collect();
+    let mut class_images: BTreeMap<u32, Vec<usize>> = BTreeMap::new();
+    let mut desired: HashMap<u32, Vec<f64>> = HashMap::new();
+    for (idx, counts) in class_counts.iter().enumerate() {
+        for (&id, &count) in counts {
+            class_images.entry(id).or_default().push(idx);
+            let wanted = desired.entry(id).or_insert_with(|| vec![0.0; ratios.len()]);
+            for (split, ratio) in ratios.iter().enumerate() {
+                wanted[split] += ratio * count;
             }
-
-            let content = if yolo_annotations.is_empty() { 
-                String::new() 
-            } else { 
-                yolo_annotations.join("\n") + "\n"
-            };
-            
-            fs::write(&output_file, content)
-                .with_context(|| format!("Failed to write output file: {}", output_file.display()))?;
-            
-            println!("  -> Generated: {} ({} annotations)", output_file.display(), image.annotations.len());
         }
     }
+    let mut capacity: Vec<f64> = ratios.iter().map(|ratio| ratio * images.len() as f64).collect();
+    let mut assigned: Vec<Option<usize>> = vec![None; images.len()];
 
-    // Create classes.txt file
-    if create_classes && !class_names.is_empty() {
-        let classes_file = output_dir.join("classes.txt");
-        let mut sorted_classes: Vec<_> = class_names.into_iter().collect();
-        sorted_classes.sort_by_key(|(id, _)| *id);
-        
-        let class_content = sorted_classes
-            .into_iter()
-            .map(|(_, name)| name)
-            .collect::<Vec<_>>()
-            .join("\n") + "\n";
-        
-        fs::write(&classes_file, class_content)
-            .with_context(|| format!("Failed to write classes file: {}", classes_file.display()))?;
-        
-        println!("\nGenerated classes file: {}", classes_file.display());
+    let best_split = |wanted: Option<&Vec<f64>>, capacity: &[f64]| {
+        (0..ratios.len())
+            .filter(|&split| ratios[split] > 0.0)
+            .max_by(|&a, &b| {
+                let by_wanted = wanted.map_or(std::cmp::Ordering::Equal, |wanted| wanted[a].total_cmp(&wanted[b]));
+                by_wanted.then(capacity[a].total_cmp(&capacity[b])).then(b.cmp(&a))
+            })
+            .unwrap_or(0)
+    };
+    loop {
+        let remaining = |id: &u32| -> f64 {
+            class_images[id].iter().filter(|&&idx| assigned[idx].is_none()).map(|&idx| class_counts[idx][id]).sum()
+        };
+        let Some(class_id) = class_images
+            .keys()
+            .filter(|id| remaining(id) > 0.0)
+            .min_by(|a, b| remaining(a).total_cmp(&remaining(b)))
+            .copied()
+        else {
+            break;
+        };
+        for &idx in &class_images[&class_id] {
+            if assigned[idx].is_some() {
+                continue;
+            }
+            let split = best_split(desired.get(&class_id), &capacity);
+            assigned[idx] = Some(split);
+            capacity[split] -= 1.0;
+            for (id, count) in &class_counts[idx] {
+                desired.get_mut(id).unwrap()[split] -= count;
+            }
+        }
+    }
+    // Images without annotations fill up whichever split has room
+    for slot in assigned.iter_mut().filter(|slot| slot.is_none()) {
+        let split = best_split(None, &capacity);
+        *slot = Some(split);
+        capacity[split] -= 1.0;
     }
 
-    println!("\nConversion completed!");
-    println!("Processed JSON files: {}", processed_files);
-    println!("Total images: {}", total_images);
-    println!("Total annotations: {}", total_annotations);
-    
-    Ok(())
+    order_by_split(images, &assigned.into_iter().map(|split| split.unwrap_or(0)).collect::<Vec<_>>(), ratios.len())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+// Put images in split order, keeping their order within a split. Returns the size of each split.
+fn order_by_split(images: &mut Vec<UnifiedImage>, assigned: &[usize], split_count: usize) -> Vec<usize> {
+    let mut split_counts = vec![0; split_count];
+    let mut by_split: Vec<(usize, UnifiedImage)> = images
+        .drain(..)
+        .zip(assigned)
+        .map(|(image, &split)| {
+            split_counts[split] += 1;
+            (split, image)
+        })
+        .collect();
+    by_split.sort_by_key(|(split, _)| *split);
+    images.extend(by_split.into_iter().map(|(_, image)| image));
+    split_counts
+}
+
+// Assign each image by where the CRC32C of its base file name falls in the cumulative split ratios,
+// independent of the other images. Returns the size of each split.
+fn hash_split(images: &mut Vec<UnifiedImage>, ratios: &[f64]) -> Vec<usize> {
+    let assigned: Vec<usize> = images
+        .iter()
+        .map(|image| {
+            let file_name = Path::new(&image.file_name).file_name().map_or(image.file_name.clone(), |name| name.to_string_lossy().into_owned());
+            let position = crc32c::crc32c(file_name.as_bytes()) as f64 / (u32::MAX as f64 + 1.0);
+            let mut end = 0.0;
+            ratios
+                .iter()
+                .position(|ratio| {
+                    end += ratio;
+                    position < end
+                })
+                .unwrap_or(ratios.len() - 1)
+        })
+        .collect();
+    order_by_split(images, &assigned, ratios.len())
+}
 
-    if !args.input.exists() {
-        anyhow::bail!("Input directory does not exist: {}", args.input.display());
+// Shuffle the images and put them in split order with the chosen strategy. Returns the size of each
+// split. `split_by_file` is the conversion-only --split-by-file.
+fn assign_splits(
+    images: &mut Vec<UnifiedImage>,
+    options: &SplitArgs,
+    splits: &[&str],
+    split_by_file: Option<&SplitByFile>,
+) -> Result<Vec<usize>> {
+    shuffle_for_split(images, &mut StdRng::seed_from_u64(options.seed));
+    
+    // Images are ordered by split; `split_counts` holds the length of each block
+    let (mut split_counts, ratios) = match options.kfold.map(|folds| folds as usize) {
+        Some(folds) => {
+            let counts = (0..folds).map(|fold| (images.len() * (fold + 1)) / folds - (images.len() * fold) / folds).collect();
+            (counts, vec![1.0 / folds as f64; folds])
+        }
+        None => {
+            let train_count = (images.len() as f64 * options.train_split) as usize;
+            let test_count = (images.len() as f64 * options.test_split) as usize;
+            let mut counts = vec![train_count, images.len() - train_count - test_count];
+            let mut ratios = vec![options.train_split, 1.0 - options.train_split - options.test_split];
+            if splits.len() == 3 {
+                counts.push(test_count);
+                ratios.push(options.test_split);
+            }
+            (counts, ratios)
+        }
+    };
+    if options.split_strategy == SplitStrategy::Hash {
+        split_counts = hash_split(images, &ratios);
+    } else if options.split_strategy == SplitStrategy::Date {
+        let undated = date_order(images, &options.date_field);
+        if undated > 0 {
+            println!("Warning: {} images have no {}; they are ordered first and go to train", undated, options.date_field);
+        }
+    } else if options.stratify {
+        split_counts = stratify(images, &ratios);
+        report_split_shares(images, splits, &split_counts);
+    } else if let Some(train_list) = &options.train_list {
+        let mut lists = vec![read_image_list(train_list)?];
+        lists.extend(options.val_list.as_deref().map(read_image_list).transpose()?);
+        lists.extend(options.test_list.as_deref().map(read_image_list).transpose()?);
+        let (counts, dropped) = list_split(images, &lists);
+        split_counts = counts;
+        if dropped > 0 {
+            println!("Warning: skipped {} images that are in none of the split lists", dropped);
+        }
+    } else if let Some(split_by_file) = split_by_file {
+        let (counts, dropped) = file_split(images, split_by_file, splits.len());
+        split_counts = counts;
+        if dropped > 0 {
+            println!("Warning: skipped {} images from annotation files no --split-by-file entry matches", dropped);
+        }
+    } else if !options.split_regex.is_empty() {
+        let (counts, matched) = regex_split(images, &options.split_regex, &ratios);
+        split_counts = counts;
+        println!("Split rules matched {} images", matched);
+    } else if let Some(group) = &options.split_group {
+        let (counts, groups) = group_split(images, &ratios, group);
+        split_counts = counts;
+        println!("Split {} groups of images", groups);
     }
+    
+    // An empty or tiny val split makes for an unusable dataset
+    if images.is_empty() {
+        return Ok(split_counts);
+    }
+    if options.kfold.is_some() {
+        let smallest = split_counts.iter().copied().min().unwrap_or(0);
+        if smallest < options.min_val {
+            anyhow::bail!("--kfold leaves a fold with {} images, fewer than --min-val {}", smallest, options.min_val);
+        }
+        return Ok(split_counts);
+    }
+    let fixed = options.train_list.is_some()
+        || split_by_file.is_some()
+        || !options.split_regex.is_empty()
+        || options.split_group.is_some();
+    if split_counts[1] < options.min_val {
+        let missing = options.min_val - split_counts[1];
+        if fixed || split_counts[0] <= missing {
+            anyhow::bail!("The val split would have {} images, fewer than --min-val {}", split_counts[1], options.min_val);
+        }
+        println!("Warning: the val split would have {} images, fewer than --min-val {}; moved {} from train", split_counts[1], options.min_val, missing);
+        split_counts[0] -= missing;
+        split_counts[1] += missing;
+    }
+    // --train-split 0 asks for no training images; otherwise an empty train split is worth a warning
+    if split_counts[0] == 0 && options.train_split > 0.0 {
+        println!("Warning: the train split is empty; {} images can't fill both train and val", images.len());
+    }
+    Ok(split_counts)
+}
 
-    println!("Converting COCO format to YOLO format...");
-    println!("Input directory: {}", args.input.display());
-    println!("Output directory: {}", args.output.display());
-    println!();
+// Sort images by their `field` metadata for a chronological split, keeping the current order of
+// equal and missing values; images without the field come first. Returns the number of those.
+fn date_order(images: &mut [UnifiedImage], field: &str) -> usize {
+    // (kind, number, text): missing sorts before numbers, numbers before strings
+    let key = |image: &UnifiedImage| match image.metadata.get(field) {
+        Some(serde_json::Value::Number(number)) => (1, number.as_f64().unwrap_or(0.0), String::new()),
+        Some(serde_json::Value::String(text)) => (2, 0.0, text.clone()),
+        _ => (0, 0.0, String::new()),
+    };
+    images.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.cmp(&b.2))
+    });
+    images.iter().filter(|image| key(image).0 == 0).count()
+}
 
-    convert_coco_to_yolo(&args.input, &args.output, args.create_classes, &args.format, args.train_split, args.yolo_structure)?;
-    
-    Ok(())
+// Send images matching a --split-regex rule to its split and divide the rest, in their current
+// order, by the split ratios. Returns the size of each split and the number of matched images.
+fn regex_split(images: &mut Vec<UnifiedImage>, rules: &[SplitRule], ratios: &[f64]) -> (Vec<usize>, usize) {
+    let matched: Vec<Option<usize>> = images
+        .iter()
+        .map(|image| rules.iter().find(|rule| rule.pattern.is_match(&image.file_name)).map(|rule| rule.split))
+        .collect();
+    let unmatched = matched.iter().filter(|split| split.is_none()).count();
+    let train_count = (unmatched as f64 * ratios[0]) as usize;
+    let test_count = ratios.get(2).map_or(0, |ratio| (unmatched as f64 * ratio) as usize);
+
+    let mut position = 0;
+    let assigned: Vec<usize> = matched
+        .iter()
+        .map(|split| {
+            split.unwrap_or_else(|| {
+                position += 1;
+                if position <= train_count {
+                    0
+                } else if position <= unmatched - test_count {
+                    1
+                } else {
+                    2
+                }
+            })
+        })
+        .collect();
+    (order_by_split(images, &assigned, ratios.len()), images.len() - unmatched)
+}
+
+// Assign whole groups of images to splits, in order of first appearance, each to the split
+// furthest below its target size. Returns the size of each split and the number of groups.
+fn group_split(images: &mut Vec<UnifiedImage>, ratios: &[f64], group: &SplitGroup) -> (Vec<usize>, usize) {
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    for (idx, image) in images.iter().enumerate() {
+        match group.key(image) {
+            Some(key) => {
+                let next = members.len();
+                let index = *group_index.entry(key).or_insert(next);
+                if index == next {
+                    members.push(Vec::new());
+                }
+                members[index].push(idx);
+            }
+            None => members.push(vec![idx]),
+        }
+    }
+
+    let mut remaining: Vec<f64> = ratios.iter().map(|ratio| ratio * images.len() as f64).collect();
+    let mut assigned = vec![0; images.len()];
+    for group in &members {
+        let split = (0..ratios.len())
+            .filter(|&split| ratios[split] > 0.0)
+            .max_by(|&a, &b| remaining[a].total_cmp(&remaining[b]).then(b.cmp(&a)))
+            .unwrap_or(0);
+        remaining[split] -= group.len() as f64;
+        for &idx in group {
+            assigned[idx] = split;
+        }
+    }
+    (order_by_split(images, &assigned, ratios.len()), members.len())
+}
+
+// Print the share of each class's instances that went to each split
+fn report_split_shares(images: &[UnifiedImage], splits: &[&str], split_counts: &[usize]) {
+    let mut instances: BTreeMap<u32, (String, Vec<usize>)> = BTreeMap::new();
+    let mut start = 0;
+    for (split, &count) in split_counts.iter().enumerate() {
+        for annotation in images[start..start + count].iter().flat_map(|image| &image.annotations) {
+            let entry = instances
+                .entry(annotation.category_id)
+                .or_insert_with(|| (class_name(annotation), vec![0; split_counts.len()]));
+            entry.1[split] += 1;
+        }
+        start += count;
+    }
+
+    println!("Stratified split, share of instances per class ({}):", splits.join("/"));
+    for (id, (name, counts)) in instances {
+        let total: usize = counts.iter().sum();
+        let shares: Vec<String> = counts.iter().map(|count| format!("{:.1}%", 100.0 * *count as f64 / total as f64)).collect();
+        println!("  {} {}: {}", id, name, shares.join(" / "));
+    }
+}
+
+// Duplicate images from the first `train_count` until each class present there has at least
+// `min_instances` annotations, rarest class first. Copies are inserted at the end of the training
+// images and point at the original image file; returns how many were added.
+fn oversample_train(images: &mut Vec<UnifiedImage>, train_count: usize, min_instances: usize, input: &InputSource) -> usize {
+    let mut instance_counts: HashMap<u32, usize> = HashMap::new();
+    for annotation in images[..train_count].iter().flat_map(|image| &image.annotations) {
+        *instance_counts.entry(annotation.category_id).or_default() += 1;
+    }
+    let mut classes: Vec<(u32, usize)> = instance_counts.iter().map(|(id, count)| (*id, *count)).collect();
+    classes.sort_by_key(|(id, count)| (*count, *id));
+
+    // Copies read the original file, or download it again for remote images
+    let source_paths: Vec<Option<PathBuf>> = images[..train_count]
+        .iter()
+        .map(|image| {
+            image.source_path.clone().or_else(|| {
+                let name = Path::new(&image.file_name).file_name()?.to_str()?;
+                input.find_image(name)
+            })
+        })
+        .collect();
+
+    let mut copies = Vec::new();
+    let mut copy_numbers = vec![0usize; train_count];
+    for (class_id, _) in classes {
+        let candidates: Vec<usize> = (0..train_count)
+            .filter(|&idx| source_paths[idx].is_some() || images[idx].source_url.is_some())
+            .filter(|&idx| images[idx].annotations.iter().any(|annotation| annotation.category_id == class_id))
+            .collect();
+        for &idx in candidates.iter().cycle() {
+            if instance_counts[&class_id] >= min_instances {
+                break;
+            }
+            let original = &images[idx];
+            let path = Path::new(&original.file_name);
+            copy_numbers[idx] += 1;
+            let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("image");
+            let file_name = match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) => format!("{}_dup{}.{}", stem, copy_numbers[idx], ext),
+                None => format!("{}_dup{}", stem, copy_numbers[idx]),
+            };
+            for annotation in &original.annotations {
+                *instance_counts.entry(annotation.category_id).or_default() += 1;
+            }
+            copies.push(UnifiedImage { file_name, source_path: source_paths[idx].clone(), ..original.clone() });
+        }
+    }
+
+    let added = copies.len();
+    images.splice(train_count..train_count, copies);
+    added
+}
+
+// Randomly drop images from the first `train_count` whose classes all stay at or above the median
+// per-class instance count without them. Returns the number of dropped images and, per class,
+// the instance counts before and after.
+fn undersample_train(images: &mut Vec<UnifiedImage>, train_count: usize, seed: u64) -> (usize, BTreeMap<u32, (usize, usize)>) {
+    let mut instance_counts: BTreeMap<u32, usize> = BTreeMap::new();
+    for annotation in images[..train_count].iter().flat_map(|image| &image.annotations) {
+        *instance_counts.entry(annotation.category_id).or_default() += 1;
+    }
+    let before = instance_counts.clone();
+    let mut sorted_counts: Vec<usize> = instance_counts.values().copied().collect();
+    sorted_counts.sort();
+    let median = sorted_counts.get(sorted_counts.len().saturating_sub(1) / 2).copied().unwrap_or(0);
+
+    let mut order: Vec<usize> = (0..train_count).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(seed));
+    let mut dropped = vec![false; images.len()];
+    for idx in order {
+        let mut image_counts: HashMap<u32, usize> = HashMap::new();
+        for annotation in &images[idx].annotations {
+            *image_counts.entry(annotation.category_id).or_default() += 1;
+        }
+        let over_represented = !image_counts.is_empty()
+            && image_counts.iter().all(|(id, count)| instance_counts[id] - count >= median);
+        if over_represented {
+            for (id, count) in image_counts {
+                *instance_counts.get_mut(&id).unwrap() -= count;
+            }
+            dropped[idx] = true;
+        }
+    }
+
+    let mut dropped_flags = dropped.iter();
+    images.retain(|_| !dropped_flags.next().unwrap_or(&false));
+    let report = before.into_iter().map(|(id, count)| (id, (count, instance_counts[&id]))).collect();
+    (dropped.iter().filter(|&&d| d).count(), report)
+}
+
+// Category ids unified by name across annotation files, so files numbering the same class
+// differently don't produce duplicate classes. The first id seen for a name is kept; a new name
+// whose id is already taken gets the next free id, above every id seen so far, named or not.
+#[derive(Default)]
+struct CategoryUnifier {
+    ids: HashMap<String, u32>,
+    names: HashMap<u32, String>,
+    max_id: Option<u32>,
+    conflicts: Vec<String>,
+}
+
+impl CategoryUnifier {
+    fn unify(&mut self, images: &mut [UnifiedImage], source: &Path) {
+        let file_max = images.iter().flat_map(|image| &image.annotations).map(|annotation| annotation.category_id).max();
+        self.max_id = self.max_id.max(file_max);
+        let mut remap: HashMap<u32, u32> = HashMap::new();
+        for annotation in images.iter().flat_map(|image| &image.annotations) {
+            let Some(name) = &annotation.category_name else { continue };
+            if remap.contains_key(&annotation.category_id) {
+                continue;
+            }
+            let id = match self.ids.get(name) {
+                Some(&id) => {
+                    if id != annotation.category_id {
+                        self.conflicts.push(format!(
+                            "category '{}' is id {} in {} but already id {}; merged",
+                            name, annotation.category_id, source.display(), id
+                        ));
+                    }
+                    id
+                }
+                None => {
+                    let mut id = annotation.category_id;
+                    if let Some(existing) = self.names.get(&id) {
+                        let free = self.max_id.map_or(0, |max| max + 1);
+                        self.max_id = Some(free);
+                        self.conflicts.push(format!(
+                            "category id {} is '{}' in {} but already '{}'; '{}' becomes id {}",
+                            id, name, source.display(), existing, name, free
+                        ));
+                        id = free;
+                    }
+                    self.ids.insert(name.clone(), id);
+                    self.names.insert(id, name.clone());
+                    id
+                }
+            };
+            remap.insert(annotation.category_id, id);
+        }
+
+        for annotation in images.iter_mut().flat_map(|image| &mut image.annotations) {
+            if annotation.category_name.is_some() {
+                annotation.category_id = remap[&annotation.category_id];
+            }
+        }
+    }
+}
+
+// A --include-classes / --exclude-classes entry names a category or gives its source id
+fn class_selector_matches(selector: &str, annotation: &UnifiedAnnotation) -> bool {
+    annotation.category_name.as_deref() == Some(selector) || selector.parse::<u32>() == Ok(annotation.category_id)
+}
+
+// Returns the number of dropped annotations
+fn filter_classes(images: &mut [UnifiedImage], include: &[String], exclude: &[String]) -> usize {
+    let mut dropped = 0;
+    for image in images.iter_mut() {
+        let before = image.annotations.len();
+        image.annotations.retain(|annotation| {
+            let included = include.is_empty() || include.iter().any(|selector| class_selector_matches(selector, annotation));
+            included && !exclude.iter().any(|selector| class_selector_matches(selector, annotation))
+        });
+        dropped += before - image.annotations.len();
+    }
+    dropped
+}
+
+// The yolo_index and original_id columns of a class mapping CSV; other columns are ignored
+#[derive(Debug, Deserialize)]
+struct ClassMappingRow {
+    yolo_index: u32,
+    original_id: u32,
+}
+
+fn load_class_id_map(path: &Path) -> Result<HashMap<u32, u32>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read class id map: {}", path.display()))?;
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    
+    let mut class_ids = HashMap::new();
+    for row in reader.deserialize() {
+        let row: ClassMappingRow = row.with_context(|| format!("Invalid class id map: {}", path.display()))?;
+        if class_ids.insert(row.original_id, row.yolo_index).is_some() {
+            anyhow::bail!("Category id {} is mapped twice in {}", row.original_id, path.display());
+        }
+    }
+    Ok(class_ids)
+}
+
+// --class-map file: new class name -> source category names or ids
+fn load_class_map(path: &Path) -> Result<BTreeMap<String, Vec<String>>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read class map: {}", path.display()))?;
+    let entries: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(&content)
+        .with_context(|| format!("Invalid class map: {}", path.display()))?;
+
+    let scalar = |value: &serde_yaml::Value| match value {
+        serde_yaml::Value::String(name) => Some(name.clone()),
+        serde_yaml::Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    };
+    entries
+        .into_iter()
+        .map(|(target, value)| {
+            let sources = match &value {
+                serde_yaml::Value::Sequence(items) => items.iter().map(scalar).collect(),
+                other => scalar(other).map(|source| vec![source]),
+            };
+            let sources = sources
+                .with_context(|| format!("Class map entry '{}' must be a name, an id or a list of them", target))?;
+            Ok((target, sources))
+        })
+        .collect()
+}
+
+// Rename annotations to their --class-map target. Returns, for each merged source category id,
+// the id that represents its group (the smallest id mapped to the same target).
+fn apply_class_map(images: &mut [UnifiedImage], class_map: &BTreeMap<String, Vec<String>>) -> Result<HashMap<u32, u32>> {
+    let mut targets: HashMap<u32, &str> = HashMap::new();
+    let mut unused: BTreeSet<&str> = class_map.values().flatten().map(String::as_str).collect();
+    for annotation in images.iter().flat_map(|image| &image.annotations) {
+        if targets.contains_key(&annotation.category_id) {
+            continue;
+        }
+        let mut matches = class_map.iter().filter(|(_, sources)| {
+            sources.iter().any(|source| class_selector_matches(source, annotation))
+        });
+        if let Some((target, sources)) = matches.next() {
+            if let Some((other, _)) = matches.next() {
+                anyhow::bail!("Category {} is mapped to both '{}' and '{}'", annotation.category_id, target, other);
+            }
+            sources.iter().filter(|source| class_selector_matches(source, annotation)).for_each(|source| {
+                unused.remove(source.as_str());
+            });
+            targets.insert(annotation.category_id, target);
+        }
+    }
+    if !unused.is_empty() {
+        println!("Warning: class map sources not found in the dataset: {}", unused.into_iter().collect::<Vec<_>>().join(", "));
+    }
+
+    let mut representatives: HashMap<&str, u32> = HashMap::new();
+    for (&id, &target) in &targets {
+        let representative = representatives.entry(target).or_insert(id);
+        *representative = (*representative).min(id);
+    }
+    for annotation in images.iter_mut().flat_map(|image| &mut image.annotations) {
+        if let Some(target) = targets.get(&annotation.category_id) {
+            annotation.category_name = Some(target.to_string());
+        }
+    }
+    Ok(targets.iter().map(|(id, target)| (*id, representatives[target])).collect())
+}
+
+// Record the path from the root of every node of a --taxonomy. A node's children are a mapping,
+// a list of names or nested mappings, a single name, or nothing for a leaf.
+fn collect_taxonomy(node: &serde_yaml::Value, path: &mut Vec<String>, paths: &mut HashMap<String, Vec<String>>) -> Result<()> {
+    let mut visit = |name: String, children: Option<&serde_yaml::Value>, path: &mut Vec<String>| -> Result<()> {
+        path.push(name.clone());
+        if paths.insert(name.clone(), path.clone()).is_some() {
+            anyhow::bail!("Class '{}' appears more than once in the taxonomy", name);
+        }
+        if let Some(children) = children {
+            collect_taxonomy(children, path, paths)?;
+        }
+        path.pop();
+        Ok(())
+    };
+    match node {
+        serde_yaml::Value::Null => {}
+        serde_yaml::Value::String(name) => visit(name.clone(), None, path)?,
+        serde_yaml::Value::Mapping(children) => {
+            for (name, grandchildren) in children {
+                let name = name.as_str().context("Taxonomy class names must be strings")?;
+                visit(name.to_string(), Some(grandchildren), path)?;
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                collect_taxonomy(item, path, paths)?;
+            }
+        }
+        _ => anyhow::bail!("Taxonomy nodes must be mappings, lists or names"),
+    }
+    Ok(())
+}
+
+// Class map renaming the categories of a --taxonomy deeper than `level` to their ancestor there.
+// Only names used by some annotation are listed.
+fn taxonomy_class_map(path: &Path, level: usize, images: &[UnifiedImage]) -> Result<BTreeMap<String, Vec<String>>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read taxonomy: {}", path.display()))?;
+    let root: serde_yaml::Value = serde_yaml::from_str(&content)
+        .with_context(|| format!("Invalid taxonomy: {}", path.display()))?;
+    let mut paths = HashMap::new();
+    collect_taxonomy(&root, &mut Vec::new(), &mut paths).with_context(|| format!("Invalid taxonomy: {}", path.display()))?;
+
+    let used: BTreeSet<&str> = images
+        .iter()
+        .flat_map(|image| &image.annotations)
+        .filter_map(|annotation| annotation.category_name.as_deref())
+        .collect();
+    let mut class_map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut unknown = Vec::new();
+    for name in used {
+        match paths.get(name) {
+            Some(ancestors) => {
+                let target = ancestors.get(level).filter(|_| ancestors.len() > level + 1).map_or(name, String::as_str);
+                class_map.entry(target.to_string()).or_default().push(name.to_string());
+            }
+            None => unknown.push(name),
+        }
+    }
+    if !unknown.is_empty() {
+        println!("Warning: categories missing from the taxonomy keep their class: {}", unknown.join(", "));
+    }
+    Ok(class_map)
+}
+
+// Give annotations without a rotated box the minimum-area rectangle of their segmentation, so the
+// --oob-policy pass sees the corners --task obb writes
+fn fit_obbs(images: &mut [UnifiedImage]) {
+    for annotation in images.iter_mut().flat_map(|image| image.annotations.iter_mut()) {
+        if annotation.obb.is_none() {
+            annotation.obb = annotation.segmentation.as_deref().and_then(min_area_rect);
+        }
+    }
+}
+
+// How many pixels an annotation reaches past the borders of a `width` x `height` image, over its
+// box, polygon, oriented box and labeled keypoints
+fn overshoot(annotation: &UnifiedAnnotation, width: f64, height: f64) -> f64 {
+    let past = |x: f64, y: f64| (-x).max(x - width).max(-y).max(y - height).max(0.0);
+    let mut points: Vec<(f64, f64)> = annotation.bbox.chunks_exact(2).map(|point| (point[0], point[1])).collect();
+    for polygon in [annotation.segmentation.as_deref(), annotation.obb.as_ref().map(|obb| &obb[..])].into_iter().flatten() {
+        points.extend(polygon.chunks_exact(2).map(|point| (point[0], point[1])));
+    }
+    if let Some(keypoints) = &annotation.keypoints {
+        points.extend(keypoints.chunks_exact(3).filter(|keypoint| keypoint[2] > 0.0).map(|keypoint| (keypoint[0], keypoint[1])));
+    }
+    points.into_iter().map(|(x, y)| past(x, y)).fold(0.0, f64::max)
+}
+
+// Apply --oob-policy to annotations reaching more than `tolerance` pixels past their image with
+// any part of their geometry; those within the tolerance are clipped without counting. Images of
+// unknown size are left alone. Returns the number of affected annotations and how many of them
+// were dropped.
+fn apply_oob_policy(images: &mut [UnifiedImage], policy: OobPolicy, tolerance: f64) -> Result<(usize, usize)> {
+    let (mut affected, mut dropped) = (0, 0);
+    for image in images.iter_mut() {
+        if image.width == 0 || image.height == 0 {
+            continue;
+        }
+        let (width, height) = (image.width as f64, image.height as f64);
+
+        let before = image.annotations.len();
+        let mut kept = Vec::with_capacity(before);
+        for mut annotation in image.annotations.drain(..) {
+            let overshoot = overshoot(&annotation, width, height);
+            if overshoot == 0.0 {
+                kept.push(annotation);
+                continue;
+            }
+            if overshoot > tolerance {
+                affected += 1;
+                match policy {
+                    OobPolicy::Error => anyhow::bail!(
+                        "Annotation with box {:?} of category {} reaches past the {}x{} image {}",
+                        annotation.bbox, annotation.category_id, image.width, image.height, image.file_name
+                    ),
+                    OobPolicy::Drop => continue,
+                    OobPolicy::Clip => {}
+                }
+            }
+
+            let clip = |points: &mut [f64]| {
+                for point in points.chunks_exact_mut(2) {
+                    point[0] = point[0].clamp(0.0, width);
+                    point[1] = point[1].clamp(0.0, height);
+                }
+            };
+            clip(&mut annotation.bbox);
+            if let Some(segmentation) = &mut annotation.segmentation {
+                clip(segmentation);
+            }
+            if let Some(obb) = &mut annotation.obb {
+                clip(obb);
+            }
+            // Keypoints outside the image become unlabeled
+            if let Some(keypoints) = &mut annotation.keypoints {
+                for keypoint in keypoints.chunks_exact_mut(3) {
+                    if !(0.0..=width).contains(&keypoint[0]) || !(0.0..=height).contains(&keypoint[1]) {
+                        keypoint.fill(0.0);
+                    }
+                }
+            }
+            if annotation.bbox[2] > annotation.bbox[0] && annotation.bbox[3] > annotation.bbox[1] {
+                kept.push(annotation);
+            }
+        }
+        dropped += before - kept.len();
+        image.annotations = kept;
+    }
+    Ok((affected, dropped))
+}
+
+// Drop boxes whose area is outside [min_area, max_area]. Returns the number dropped.
+fn filter_box_areas(images: &mut [UnifiedImage], min_area: Option<AreaThreshold>, max_area: Option<AreaThreshold>) -> usize {
+    let mut dropped = 0;
+    for image in images.iter_mut() {
+        let min = min_area.map_or(f64::NEG_INFINITY, |threshold| threshold.pixels(image));
+        let max = max_area.map_or(f64::INFINITY, |threshold| threshold.pixels(image));
+        let before = image.annotations.len();
+        image.annotations.retain(|annotation| {
+            let area = (annotation.bbox[2] - annotation.bbox[0]) * (annotation.bbox[3] - annotation.bbox[1]);
+            (min..=max).contains(&area)
+        });
+        dropped += before - image.annotations.len();
+    }
+    dropped
+}
+
+// Drop boxes narrower or shorter than `min_size`. Returns the number dropped.
+fn filter_small_boxes(images: &mut [UnifiedImage], min_size: BoxSize) -> usize {
+    let mut dropped = 0;
+    for image in images.iter_mut() {
+        let before = image.annotations.len();
+        image.annotations.retain(|annotation| {
+            annotation.bbox[2] - annotation.bbox[0] >= min_size.width && annotation.bbox[3] - annotation.bbox[1] >= min_size.height
+        });
+        dropped += before - image.annotations.len();
+    }
+    dropped
+}
+
+fn box_iou(a: &[f64], b: &[f64]) -> f64 {
+    let intersection = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0) * (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+    let union = (a[2] - a[0]) * (a[3] - a[1]) + (b[2] - b[0]) * (b[3] - b[1]) - intersection;
+    if union > 0.0 { intersection / union } else { 0.0 }
+}
+
+// Remove boxes overlapping a larger same-class box of their image with IoU >= `threshold`.
+// Returns the number removed.
+fn dedup_boxes(images: &mut [UnifiedImage], threshold: f64) -> usize {
+    let area = |bbox: &[f64]| (bbox[2] - bbox[0]) * (bbox[3] - bbox[1]);
+    let mut removed = 0;
+    for image in images.iter_mut() {
+        let annotations = &image.annotations;
+        // Largest first; the sort is stable so equal boxes keep their order
+        let mut order: Vec<usize> = (0..annotations.len()).collect();
+        order.sort_by(|&a, &b| area(&annotations[b].bbox).total_cmp(&area(&annotations[a].bbox)));
+
+        let mut kept: Vec<usize> = Vec::new();
+        for idx in order {
+            let duplicate = kept.iter().any(|&other| {
+                annotations[other].category_id == annotations[idx].category_id
+                    && box_iou(&annotations[other].bbox, &annotations[idx].bbox) >= threshold
+            });
+            if !duplicate {
+                kept.push(idx);
+            }
+        }
+
+        removed += annotations.len() - kept.len();
+        let mut keep = vec![false; annotations.len()];
+        for idx in kept {
+            keep[idx] = true;
+        }
+        let mut keep = keep.into_iter();
+        image.annotations.retain(|_| keep.next().unwrap_or(false));
+    }
+    removed
+}
+
+// Apply --iscrowd. Separate crowd classes take category ids above the largest one in use.
+// Returns the number of crowd annotations dropped or moved.
+fn apply_crowd_policy(images: &mut [UnifiedImage], policy: Crowd) -> usize {
+    let mut affected = 0;
+    match policy {
+        Crowd::Keep => {}
+        Crowd::Skip => {
+            for image in images.iter_mut() {
+                let before = image.annotations.len();
+                image.annotations.retain(|annotation| !annotation.iscrowd);
+                affected += before - image.annotations.len();
+            }
+        }
+        Crowd::SeparateClass => {
+            let max_id = images.iter().flat_map(|image| &image.annotations).map(|annotation| annotation.category_id).max();
+            let mut next_id = max_id.map_or(0, |id| id + 1);
+            let mut crowd_ids: BTreeMap<u32, u32> = BTreeMap::new();
+            for annotation in images.iter_mut().flat_map(|image| &mut image.annotations).filter(|annotation| annotation.iscrowd) {
+                let crowd_id = *crowd_ids.entry(annotation.category_id).or_insert_with(|| {
+                    next_id += 1;
+                    next_id - 1
+                });
+                annotation.category_name = Some(format!("{}_crowd", class_name(annotation)));
+                annotation.supercategory = None;
+                annotation.category_id = crowd_id;
+                affected += 1;
+            }
+        }
+    }
+    affected
+}
+
+// Class map collapsing categories into their supercategories. Categories without one are left out
+// and keep their own class; returns the number of their annotations.
+fn supercategory_class_map(images: &[UnifiedImage]) -> (BTreeMap<String, Vec<String>>, usize) {
+    let mut class_map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    let mut ungrouped = 0;
+    for annotation in images.iter().flat_map(|image| &image.annotations) {
+        match &annotation.supercategory {
+            Some(supercategory) => {
+                if seen.insert(annotation.category_id) {
+                    class_map.entry(supercategory.clone()).or_default().push(annotation.category_id.to_string());
+                }
+            }
+            None => ungrouped += 1,
+        }
+    }
+    (class_map, ungrouped)
+}
+
+// Contiguous class indices from 0 for the category ids in use, in ascending id order, so sparse
+// COCO ids (1-90 with gaps) don't produce out-of-range YOLO classes. Ids in `merged` share the
+// index of their representative id.
+fn contiguous_class_ids(images: &[UnifiedImage], merged: &HashMap<u32, u32>) -> HashMap<u32, u32> {
+    let ids: BTreeSet<u32> = images
+        .iter()
+        .flat_map(|image| &image.annotations)
+        .map(|annotation| annotation.category_id)
+        .collect();
+    let representative = |id: u32| merged.get(&id).copied().unwrap_or(id);
+    let indices: HashMap<u32, u32> = ids
+        .iter()
+        .map(|&id| representative(id))
+        .collect::<BTreeSet<u32>>()
+        .into_iter()
+        .enumerate()
+        .map(|(index, id)| (id, index as u32))
+        .collect();
+    ids.into_iter().map(|id| (id, indices[&representative(id)])).collect()
+}
+
+// Class indices from a --names list: each category id takes the line number of its name
+fn named_class_ids(images: &[UnifiedImage], names: &[Option<String>], strict: bool) -> Result<HashMap<u32, u32>> {
+    let index: HashMap<&str, u32> = names
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| Some((name.as_deref()?, i as u32)))
+        .collect();
+    let mut class_ids = HashMap::new();
+    let mut unlisted = BTreeSet::new();
+    for annotation in images.iter().flat_map(|image| &image.annotations) {
+        match annotation.category_name.as_deref().and_then(|name| index.get(name)) {
+            Some(&class_index) => {
+                class_ids.insert(annotation.category_id, class_index);
+            }
+            None => {
+                unlisted.insert(class_name(annotation));
+            }
+        }
+    }
+    if !unlisted.is_empty() {
+        let unlisted: Vec<String> = unlisted.into_iter().collect();
+        if strict {
+            anyhow::bail!("Classes missing from --names: {}", unlisted.join(", "));
+        }
+        println!("Warning: classes missing from --names are dropped: {}", unlisted.join(", "));
+    }
+    Ok(class_ids)
+}
+
+// Class indices equal to the category ids in use, or to their representative id in `merged`
+fn original_class_ids(images: &[UnifiedImage], merged: &HashMap<u32, u32>) -> HashMap<u32, u32> {
+    images
+        .iter()
+        .flat_map(|image| &image.annotations)
+        .map(|annotation| (annotation.category_id, merged.get(&annotation.category_id).copied().unwrap_or(annotation.category_id)))
+        .collect()
+}
+
+// Replace category ids by their class indices, dropping annotations without one.
+// Returns the number of dropped annotations.
+fn remap_category_ids(images: &mut [UnifiedImage], class_ids: &HashMap<u32, u32>) -> usize {
+    let mut dropped = 0;
+    for image in images.iter_mut() {
+        let before = image.annotations.len();
+        image.annotations.retain(|annotation| class_ids.contains_key(&annotation.category_id));
+        dropped += before - image.annotations.len();
+        for annotation in &mut image.annotations {
+            annotation.category_id = class_ids[&annotation.category_id];
+        }
+    }
+    dropped
+}
+
+// Locate an image under the input directory and read its dimensions from the file header
+fn read_image_size(input: &InputSource, file_name: &str) -> Result<(u32, u32)> {
+    let image_filename = Path::new(file_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Invalid image filename")?;
+    let path = input.find_image(image_filename)
+        .with_context(|| format!("Image file not found: {}", image_filename))?;
+    
+    input.image_size(&path)
+}
+
+// Collect file stems in a directory, split into images and label files.
+fn collect_stems(dir: &Path) -> Result<(Vec<String>, Vec<String>)> {
+    let mut image_stems = Vec::new();
+    let mut label_stems = Vec::new();
+    
+    if !dir.is_dir() {
+        return Ok((image_stems, label_stems));
+    }
+    
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))? {
+        let path = entry?.path();
+        let (Some(stem), Some(ext)) = (path.file_stem().and_then(|s| s.to_str()), path.extension().and_then(|s| s.to_str())) else {
+            continue;
+        };
+        let ext = ext.to_lowercase();
+        if ext == "txt" {
+            label_stems.push(stem.to_string());
+        } else if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+            image_stems.push(stem.to_string());
+        }
+    }
+    
+    Ok((image_stems, label_stems))
+}
+
+// Scan the split directories of a converted dataset and report labels without images and images without labels.
+// Negative images are expected to have an empty label file, so they pair up like any other image.
+fn check_pairing(output_dir: &Path, layout: Layout, strict: bool) -> Result<()> {
+    println!("\nChecking image/label pairing in {}...", output_dir.display());
+    
+    let mut total_mismatches = 0;
+    for split in ["train", "val", "test"] {
+        let split_dir = output_dir.join(split);
+        // The test split is optional
+        if split == "test" && !split_dir.exists() {
+            continue;
+        }
+        let (images_dir, labels_dir) = match layout {
+            Layout::Ultralytics => (split_dir.join("images"), split_dir.join("labels")),
+            Layout::FlatPerSplit => (split_dir.clone(), split_dir.clone()),
+        };
+        
+        let (image_stems, _) = collect_stems(&images_dir)?;
+        let (_, label_stems) = collect_stems(&labels_dir)?;
+        let image_set: HashSet<&String> = image_stems.iter().collect();
+        let label_set: HashSet<&String> = label_stems.iter().collect();
+        
+        let mut orphan_labels: Vec<_> = label_set.difference(&image_set).collect();
+        let mut orphan_images: Vec<_> = image_set.difference(&label_set).collect();
+        orphan_labels.sort();
+        orphan_images.sort();
+        
+        for stem in &orphan_labels {
+            println!("  {}: label without image: {}.txt", split, stem);
+        }
+        for stem in &orphan_images {
+            println!("  {}: image without label: {}", split, stem);
+        }
+        println!(
+            "  {}: {} images, {} labels, {} orphan labels, {} orphan images",
+            split, image_stems.len(), label_stems.len(), orphan_labels.len(), orphan_images.len()
+        );
+        total_mismatches += orphan_labels.len() + orphan_images.len();
+    }
+    
+    if total_mismatches == 0 {
+        println!("Pairing check passed");
+    } else if strict {
+        anyhow::bail!("Pairing check failed: {} mismatched files", total_mismatches);
+    } else {
+        println!("Warning: pairing check found {} mismatched files", total_mismatches);
+    }
+    
+    Ok(())
+}
+
+// Write minimal project files so the output directory can be versioned without the images.
+fn write_scaffold(output_dir: &Path, layout: Option<Layout>) -> Result<()> {
+    let mut gitignore = String::from("# Image data is large; keep it out of version control\n");
+    for split in ["train", "val", "test"] {
+        match layout {
+            Some(Layout::Ultralytics) => gitignore.push_str(&format!("/{}/images/\n", split)),
+            Some(Layout::FlatPerSplit) => {
+                for ext in &IMAGE_EXTENSIONS {
+                    gitignore.push_str(&format!("/{}/*.{}\n", split, ext));
+                }
+            }
+            None => {}
+        }
+    }
+    gitignore.push_str("\n# Ultralytics label caches\n*.cache\n");
+    
+    let gitignore_path = output_dir.join(".gitignore");
+    fs::write(&gitignore_path, gitignore)
+        .with_context(|| format!("Failed to write .gitignore: {}", gitignore_path.display()))?;
+    
+    let configs_dir = output_dir.join("configs");
+    fs::create_dir_all(&configs_dir)
+        .with_context(|| format!("Failed to create configs directory: {}", configs_dir.display()))?;
+    fs::write(configs_dir.join(".gitkeep"), "")?;
+    
+    println!("Generated project scaffold: {}", gitignore_path.display());
+    Ok(())
+}
+
+// The source category id of each written class index (offset included), for translating
+// predictions back. Classes merged into one index map to their lowest source id. None when every
+// index already is its source id.
+fn yolo_to_coco_ids(class_ids: &HashMap<u32, u32>, class_offset: u32) -> Option<BTreeMap<u32, u32>> {
+    if class_ids.iter().all(|(original, index)| index + class_offset == *original) {
+        return None;
+    }
+    let mut inverse: BTreeMap<u32, u32> = BTreeMap::new();
+    for (&original, &index) in class_ids {
+        let entry = inverse.entry(index + class_offset).or_insert(original);
+        *entry = (*entry).min(original);
+    }
+    Some(inverse)
+}
+
+// Write the yolo_to_coco_ids mapping as JSON, when there's one to write
+fn write_yolo_to_coco_ids(path: &Path, class_ids: &HashMap<u32, u32>, class_offset: u32) -> Result<()> {
+    let Some(inverse) = yolo_to_coco_ids(class_ids, class_offset) else {
+        return Ok(());
+    };
+    fs::write(path, serde_json::to_string_pretty(&inverse)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Generated class index mapping: {}", path.display());
+
+    Ok(())
+}
+
+// Track ids for one image, line-aligned with its label file; -1 marks annotations without one
+fn write_track_ids(tracks_dir: &Path, base_name: &str, image: &UnifiedImage) -> Result<()> {
+    fs::create_dir_all(tracks_dir)?;
+    let content: String = image.annotations
+        .iter()
+        .map(|annotation| format!("{}\n", annotation.track_id.unwrap_or(-1)))
+        .collect();
+    let track_path = tracks_dir.join(format!("{}.txt", base_name));
+    fs::write(&track_path, content)
+        .with_context(|| format!("Failed to write track id file: {}", track_path.display()))
+}
+
+// Source metadata for one image keyed by label line number (from 0): each annotation's fields
+// without a YOLO equivalent plus its track id. Lines with nothing to keep are left out.
+fn write_labels_meta(meta_dir: &Path, base_name: &str, image: &UnifiedImage) -> Result<()> {
+    fs::create_dir_all(meta_dir)?;
+    let mut lines = serde_json::Map::new();
+    for (line_number, annotation) in image.annotations.iter().enumerate() {
+        let mut fields = annotation.metadata.clone();
+        if let Some(track_id) = annotation.track_id {
+            fields.entry("track_id").or_insert(track_id.into());
+        }
+        if !fields.is_empty() {
+            lines.insert(line_number.to_string(), fields.into());
+        }
+    }
+    let meta_path = meta_dir.join(format!("{}.json", base_name));
+    fs::write(&meta_path, serde_json::to_string_pretty(&lines)?)
+        .with_context(|| format!("Failed to write label metadata: {}", meta_path.display()))
+}
+
+// Write counters in Prometheus exposition format for node_exporter's textfile collector.
+// The file is written to a temporary path first and renamed so the collector never reads a partial file.
+fn write_metrics_file(path: &Path, metrics: &[(&str, &str, usize)]) -> Result<()> {
+    let mut content = String::new();
+    for (name, help, value) in metrics {
+        content.push_str(&format!("# HELP {} {}\n", name, help));
+        content.push_str(&format!("# TYPE {} counter\n", name));
+        content.push_str(&format!("{} {}\n", name, value));
+    }
+    
+    let tmp_path = path.with_extension("prom.tmp");
+    fs::write(&tmp_path, content)
+        .with_context(|| format!("Failed to write metrics file: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to move metrics file into place: {}", path.display()))?;
+    
+    Ok(())
+}
+
+fn convert_coco_to_yolo(args: &Args) -> Result<()> {
+    let input_dir = args.input.as_deref().context("--input is required for conversion")?;
+    let input = InputSource::open(input_dir)?.with_image_dirs(&args.image_dir);
+    let output_dir = args.output.as_deref().context("--output is required for conversion")?;
+    let format = args.format.as_str();
+    args.split.check()?;
+    
+    let keep_categories_regex = args.keep_categories_regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .context("Invalid --keep-categories-regex pattern")?;
+    let names = match &args.names {
+        Some(path) => {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read class list: {}", path.display()))?;
+            formats::yolo::parse_class_names(&content)
+                .with_context(|| format!("Invalid class list: {}", path.display()))?
+        }
+        None => Vec::new(),
+    };
+    
+    let mut all_images = Vec::new();
+    let mut class_names = HashMap::new();
+    let mut class_counts: HashMap<u32, usize> = HashMap::new();
+    let mut labels = formats::LabelRegistry::default();
+    let mut processed_files = 0;
+    let mut total_annotations = 0;
+    let mut missing_images = 0;
+    let mut filtered_annotations = 0;
+
+    println!("Using format: {}", format);
+    
+    // Open Images labels are MIDs; their display names live in a separate class description CSV
+    let mut class_descriptions = HashMap::new();
+    if format == "open-images" {
+        let descriptions_path = args.class_descriptions.clone()
+            .or_else(|| input.find_file(|name| name.contains("class-descriptions")));
+        match descriptions_path {
+            Some(path) => {
+                // An explicit --class-descriptions is a file on disk; a found one may be an archive entry
+                let content = if args.class_descriptions.is_some() {
+                    fs::read_to_string(&path).map_err(anyhow::Error::from)
+                } else {
+                    input.read_annotation(&path)
+                };
+                let content = content.with_context(|| format!("Failed to read class descriptions: {}", path.display()))?;
+                class_descriptions = formats::open_images::load_class_descriptions(&content, &path)?;
+                println!("Loaded {} class descriptions from {}", class_descriptions.len(), path.display());
+            }
+            None => println!("Warning: no class description CSV found; using LabelName MIDs as class names"),
+        }
+    }
+    
+    if format == "supervisely" {
+        let meta_path = input.root_file("meta.json");
+        if input.contains(&meta_path) {
+            let content = input.read_annotation(&meta_path)
+                .with_context(|| format!("Failed to read Supervisely meta: {}", meta_path.display()))?;
+            let class_count = formats::supervisely::load_meta(&content, &meta_path, &mut labels)?;
+            println!("Loaded {} classes from {}", class_count, meta_path.display());
+        } else {
+            println!("Warning: no meta.json in {}; classes are indexed as they are seen", input_dir.display());
+        }
+    }
+    
+    let images_index = if format == "coco-results" {
+        let index_path = args.images_index.as_deref().context("--format coco-results requires --images-index")?;
+        Some(formats::coco_results::load_index(index_path)?)
+    } else {
+        None
+    };
+    
+    let kitti_class_map = args.kitti_class_map
+        .as_deref()
+        .map(formats::kitti::load_class_map)
+        .transpose()?;
+    
+    let yolo_names = if format == "yolo" {
+        let names = load_yolo_class_names(&input)?;
+        println!("Loaded {} class names", names.len());
+        names
+    } else {
+        Vec::new()
+    };
+    
+    let mut registry = formats::FormatRegistry::default();
+    registry.register(formats::standard::StandardParser {
+        mask_tolerance: matches!(args.task, Task::Segment | Task::Obb).then_some(args.polygon_tolerance),
+    });
+    registry.register(formats::damm::DammParser);
+    registry.register(formats::labelme::LabelMeParser);
+    registry.register(formats::cvat::CvatParser);
+    registry.register(formats::label_studio::LabelStudioParser);
+    registry.register(formats::via::ViaParser { label_attribute: args.via_label_attribute.clone() });
+    registry.register(formats::open_images::OpenImagesParser { descriptions: class_descriptions });
+    registry.register(formats::kitti::KittiParser { class_map: kitti_class_map });
+    registry.register(formats::supervisely::SuperviselyParser);
+    registry.register(formats::createml::CreateMlParser);
+    registry.register(formats::sagemaker::SageMakerParser);
+    registry.register(formats::bdd100k::Bdd100kParser);
+    registry.register(formats::lvis::LvisParser { not_exhaustive: args.lvis_not_exhaustive });
+    registry.register(formats::coco_results::CocoResultsParser { index: images_index, score_threshold: args.score_threshold });
+    registry.register(formats::dota::DotaParser);
+    registry.register(formats::mot::MotParser);
+    registry.register(formats::datumaro::DatumaroParser);
+    registry.register(formats::yolo::YoloParser { names: yolo_names });
+    registry.register(formats::coco_panoptic::CocoPanopticParser);
+    
+    // --format auto scans for the same files as the standard parser and picks a parser per file
+    let auto_format = format == "auto";
+    let Some(scan_parser) = registry.get(if auto_format { "standard" } else { format }) else {
+        let names: Vec<String> = registry.names().iter().map(|name| format!("'{}'", name)).collect();
+        anyhow::bail!("Invalid format '{}'. Use {} or 'auto'", format, names.join(", "));
+    };
+    
+    // Find all JSON files first
+    let json_files = if let Some(manifest) = &args.json_list {
+        if input.is_archive() {
+            anyhow::bail!("--json-list can't be combined with a .zip --input");
+        }
+        println!("Reading metadata files from {}...", manifest.display());
+        read_json_list(manifest)?
+    } else {
+        println!("Scanning for metadata files...");
+        // The images index is a COCO file too; it must not be parsed as a results file
+        let images_index_path = args.images_index.as_ref().and_then(|path| fs::canonicalize(path).ok());
+        let is_images_index = |path: &Path| images_index_path.is_some() && fs::canonicalize(path).ok() == images_index_path;
+        
+        input.annotation_files(scan_parser, &is_images_index)
+    };
+    
+    if json_files.is_empty() {
+        anyhow::bail!("No annotation files for format '{}' found in input directory", format);
+    }
+    
+    println!("Found {} annotation files", json_files.len());
+    
+    // Create progress bar for JSON parsing
+    let pb_parse = ProgressBar::new(json_files.len() as u64);
+    pb_parse.set_style(
+        ProgressStyle::with_template(
+            "Parsing JSON    [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} {msg}"
+        )?
+        .progress_chars("#>-")
+    );
+    
+    // Parse all JSON files with progress bar
+    let parse_start = Instant::now();
+    let mut detected_formats: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut categories = CategoryUnifier::default();
+    for json_file in &json_files {
+        let filename = json_file.file_name().unwrap_or_default().to_string_lossy();
+        pb_parse.set_message(format!("Processing {}", filename));
+        
+        let content = input.read_annotation(json_file)
+            .with_context(|| format!("Failed to read file: {}", json_file.display()))?;
+        
+        let parser = if auto_format {
+            let detected = formats::detect_json_format(&content, formats::is_ndjson(json_file, &content))
+                .with_context(|| format!("Could not detect the format of {}; pass --format explicitly", json_file.display()))?;
+            *detected_formats.entry(detected).or_default() += 1;
+            registry.get(detected).context("Detected format is not registered")?
+        } else {
+            scan_parser
+        };
+        
+        let ctx = formats::ParseContext {
+            path: json_file,
+            // Name of the annotation file once inflated, for formats that derive image names from it
+            file_name: formats::decompressed_name(&filename),
+            input: &input,
+            read_image_size: args.read_image_size,
+        };
+        let mut unified_images = parser.parse(&content, &ctx, &mut labels)
+            .with_context(|| format!("Failed to parse as {}: {}", parser.description(), json_file.display()))?;
+        categories.unify(&mut unified_images, json_file);
+        for image in &mut unified_images {
+            image.annotation_file = Some(ctx.file_name.to_string());
+        }
+
+        all_images.extend(unified_images);
+        processed_files += 1;
+        pb_parse.inc(1);
+    }
+    
+    pb_parse.finish_with_message("JSON parsing complete");
+    for conflict in &categories.conflicts {
+        println!("Warning: {}", conflict);
+    }
+    
+    if !detected_formats.is_empty() {
+        let summary: Vec<String> = detected_formats.iter().map(|(format, count)| format!("{} {}", format, count)).collect();
+        println!("Detected formats: {}", summary.join(", "));
+    }
+    
+    if args.parse_only {
+        let annotation_count: usize = all_images.iter().map(|image| image.annotations.len()).sum();
+        println!("\nParse-only mode, no files written");
+        println!("Processed JSON files: {}", processed_files);
+        println!("Total images: {}", all_images.len());
+        println!("Total annotations: {}", annotation_count);
+        println!("Parse time: {:.3}s", parse_start.elapsed().as_secs_f64());
+        return Ok(());
+    }
+    
+    if let Some(only_images) = &args.only_images {
+        let selected = read_image_list(only_images)?;
+        let before = all_images.len();
+        all_images.retain(|image| {
+            image.image_id.as_ref().is_some_and(|id| selected.contains(id))
+                || Path::new(&image.file_name).file_name().is_some_and(|name| selected.contains(name.to_string_lossy().as_ref()))
+        });
+        println!("Image list {}: kept {} images, skipped {}", only_images.display(), all_images.len(), before - all_images.len());
+    }
+    let had_annotations: Vec<bool> = all_images.iter().map(|image| !image.annotations.is_empty()).collect();
+    if let Some(pattern) = &keep_categories_regex {
+        let (kept, dropped) = filter_categories_by_regex(&mut all_images, pattern)?;
+        filtered_annotations += dropped;
+        println!("Category filter '{}': kept {} annotations, dropped {}", pattern, kept, dropped);
+    }
+    if !args.include_classes.is_empty() || !args.exclude_classes.is_empty() {
+        let dropped = filter_classes(&mut all_images, &args.include_classes, &args.exclude_classes);
+        filtered_annotations += dropped;
+        println!("Class filter: dropped {} annotations", dropped);
+    }
+    if args.task == Task::Obb {
+        fit_obbs(&mut all_images);
+    }
+    let (oob_affected, oob_dropped) = apply_oob_policy(&mut all_images, args.oob_policy, args.bounds_tolerance)?;
+    filtered_annotations += oob_dropped;
+    let mut area_dropped = 0;
+    if args.min_area.is_some() || args.max_area.is_some() {
+        area_dropped = filter_box_areas(&mut all_images, args.min_area, args.max_area);
+        filtered_annotations += area_dropped;
+    }
+    let mut size_dropped = 0;
+    if let Some(min_size) = args.min_box_size {
+        size_dropped = filter_small_boxes(&mut all_images, min_size);
+        filtered_annotations += size_dropped;
+    }
+    let mut duplicates = 0;
+    if let Some(threshold) = args.dedup_iou {
+        duplicates = dedup_boxes(&mut all_images, threshold);
+        filtered_annotations += duplicates;
+    }
+    if args.drop_filtered_images {
+        let before = all_images.len();
+        let mut had_annotations = had_annotations.into_iter();
+        all_images.retain(|image| {
+            let had = had_annotations.next().unwrap_or(false);
+            !(had && image.annotations.is_empty())
+        });
+        println!("Dropped {} images left without annotations", before - all_images.len());
+    }
+    
+    let crowd = apply_crowd_policy(&mut all_images, args.iscrowd);
+    match args.iscrowd {
+        Crowd::Skip => {
+            filtered_annotations += crowd;
+            println!("Dropped {} crowd annotations", crowd);
+        }
+        Crowd::SeparateClass => println!("Moved {} crowd annotations to crowd classes", crowd),
+        Crowd::Keep => {}
+    }
+    let merged_ids = if args.group_by == GroupBy::Supercategory {
+        let (class_map, ungrouped) = supercategory_class_map(&all_images);
+        if ungrouped > 0 {
+            println!("Warning: {} annotations have no supercategory and keep their category", ungrouped);
+        }
+        apply_class_map(&mut all_images, &class_map)?
+    } else if let Some(path) = &args.taxonomy {
+        let class_map = taxonomy_class_map(path, args.level, &all_images)?;
+        apply_class_map(&mut all_images, &class_map)?
+    } else {
+        match &args.class_map {
+            Some(path) => apply_class_map(&mut all_images, &load_class_map(path)?)?,
+            None => HashMap::new(),
+        }
+    };
+    let class_ids = match &args.class_id_map {
+        Some(path) => load_class_id_map(path)?,
+        None if args.names.is_some() => named_class_ids(&all_images, &names, args.strict)?,
+        // The indices of a KITTI class map are the user's and are kept as they are
+        None if args.keep_category_ids || args.kitti_class_map.is_some() => original_class_ids(&all_images, &merged_ids),
+        None => contiguous_class_ids(&all_images, &merged_ids),
+    };
+    let unmapped = remap_category_ids(&mut all_images, &class_ids);
+    if unmapped > 0 {
+        filtered_annotations += unmapped;
+        println!("Warning: dropped {} annotations without a class index", unmapped);
+    }
+    // Name the classes now, before sampling, balancing or missing image files drop images, so
+    // classes.txt and data.yaml keep every index the labels can refer to
+    for annotation in all_images.iter().flat_map(|image| &image.annotations) {
+        class_names.insert(annotation.category_id, class_name(annotation));
+    }
+    fill_class_names(&mut class_names, &names, args.keep_category_ids || args.kitti_class_map.is_some());
+    let written_names = offset_class_names(&class_names, args.class_offset);
+
+    if let Some(policy) = args.background_images {
+        let (added, dropped) = apply_background_policy(&mut all_images, policy, &input, args.split.seed)?;
+        println!("Background images: added {} unreferenced, dropped {}", added, dropped);
+    }
+    if let Some(max_per_class) = args.max_per_class {
+        let skipped = cap_images_per_class(&mut all_images, max_per_class, args.split.seed);
+        println!("Class cap {}: skipped {} images", max_per_class, skipped);
+    }
+    if let Some(sample) = args.sample {
+        let skipped = sample_images(&mut all_images, sample, args.split.seed);
+        println!("Sample: kept {} images, skipped {}", all_images.len(), skipped);
+    }
+
+    let total_images = all_images.len();
+    println!("Found {} images total", total_images);
+    
+    if args.darknet && !args.yolo_structure {
+        anyhow::bail!("--darknet needs --yolo-structure; the legacy layout copies no images to list");
+    }
+    if args.lists && !args.yolo_structure {
+        anyhow::bail!("--lists needs --yolo-structure; the legacy layout copies no images to list");
+    }
+    if args.no_copy && input.is_archive() {
+        anyhow::bail!("--no-copy needs a directory --input; images inside an archive have to be extracted");
+    }
+    if args.no_copy && args.export.contains(&ExportFormat::Huggingface) {
+        anyhow::bail!("--export huggingface can't be combined with --no-copy; imagefolder metadata has to sit next to the images");
+    }
+    if args.image_mode == ImageMode::Move && args.oversample_min.is_some() {
+        anyhow::bail!("--image-mode move can't be combined with --oversample-min; the extra copies need the source images");
+    }
+    if args.oversample_min.is_some() && !args.yolo_structure {
+        anyhow::bail!("--oversample-min needs --yolo-structure; the legacy layout has no training split");
+    }
+    if args.balance.is_some() && !args.yolo_structure {
+        anyhow::bail!("--balance needs --yolo-structure; the legacy layout has no training split");
+    }
+    for (export, name) in [
+        (ExportFormat::Tfrecord, "tfrecord"),
+        (ExportFormat::Webdataset, "webdataset"),
+        (ExportFormat::Huggingface, "huggingface"),
+        (ExportFormat::Fiftyone, "fiftyone"),
+    ] {
+        if args.export.contains(&export) && !args.yolo_structure {
+            anyhow::bail!("--export {} needs --yolo-structure; the legacy layout copies no images", name);
+        }
+    }
+    let label_style = LabelStyle {
+        task: args.task,
+        precision: args.precision,
+        class_offset: args.class_offset,
+        obb_coords: args.obb_coords,
+    };
+    let split_names = args.split.split_names(args.split_by_file.as_ref().is_some_and(SplitByFile::has_test));
+    let split_refs: Vec<&str> = split_names.iter().map(String::as_str).collect();
+    let splits: &[&str] = &split_refs;
+
+    // Splits are assigned before anything is written, so --dry-run and --from-data-yaml report
+    // what the conversion would actually produce
+    let mut split_counts = Vec::new();
+    if args.yolo_structure {
+        split_counts = assign_splits(&mut all_images, &args.split, splits, args.split_by_file.as_ref())?;
+        if let Some(min_instances) = args.oversample_min {
+            let added = oversample_train(&mut all_images, split_counts[0], min_instances, &input);
+            println!("Oversampling to {} instances per class: added {} training image copies", min_instances, added);
+            split_counts[0] += added;
+        }
+        if args.balance == Some(Balance::Undersample) {
+            let (dropped, report) = undersample_train(&mut all_images, split_counts[0], args.balance_seed.unwrap_or(args.split.seed));
+            println!("Undersampling: dropped {} training images", dropped);
+            for (id, (before, after)) in report {
+                let name = all_images.iter().flat_map(|image| &image.annotations).find(|annotation| annotation.category_id == id).map(class_name);
+                println!("  {} {}: {} -> {} instances", id, name.unwrap_or_default(), before, after);
+            }
+            split_counts[0] -= dropped;
+        }
+        let summary: Vec<String> = splits.iter().zip(&split_counts).map(|(split, count)| format!("{} {}", count, split)).collect();
+        println!("Split: {} images", summary.join(", "));
+    }
+
+    if let Some(data_yaml) = &args.from_data_yaml {
+        let existing = read_data_yaml_names(data_yaml)?;
+        println!("\nComparing classes with {}:", data_yaml.display());
+        let differences = report_class_drift(&existing, &written_names.values().cloned().collect::<Vec<_>>());
+        if differences == 0 {
+            println!("  no class drift");
+        } else if args.strict {
+            anyhow::bail!("Class list differs from {} in {} places", data_yaml.display(), differences);
+        }
+    }
+
+    if args.dry_run {
+        let annotation_count: usize = all_images.iter().map(|image| image.annotations.len()).sum();
+        println!("\nDry run, no files written");
+        if args.yolo_structure {
+            let summary: Vec<String> = splits.iter().zip(&split_counts).map(|(split, count)| format!("{} {}", count, split)).collect();
+            println!("Would convert {} images ({})", all_images.len(), summary.join(", "));
+        } else {
+            println!("Would convert {} images", all_images.len());
+        }
+        println!("Would write {} annotations", annotation_count);
+        return Ok(());
+    }
+
+    fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+    let mut split_images: Vec<(&str, Vec<PathBuf>)> = splits.iter().map(|split| (*split, Vec::new())).collect();
+    let mut stats = stats::ClassStats::new(if args.yolo_structure { splits } else { &["all"] });
+    let mut csv = args.export
+        .contains(&ExportFormat::Csv)
+        .then(|| writers::csv::CsvWriter::create(&output_dir.join("annotations.csv")))
+        .transpose()?;
+    let mut sqlite = args.export
+        .contains(&ExportFormat::Sqlite)
+        .then(|| writers::sqlite::SqliteWriter::create(&output_dir.join("dataset.db")))
+        .transpose()?;
+    let mut parquet = args.export
+        .contains(&ExportFormat::Parquet)
+        .then(|| writers::parquet::ParquetWriter::new(&output_dir.join("annotations.parquet")));
+    let mut tfrecord = args.export
+        .contains(&ExportFormat::Tfrecord)
+        .then(|| writers::tfrecord::TfRecordWriter::create(&output_dir.join("tfrecord"), args.shard_size))
+        .transpose()?;
+    let mut webdataset = args.export
+        .contains(&ExportFormat::Webdataset)
+        .then(|| writers::webdataset::WebDatasetWriter::new(&output_dir.join("webdataset"), args.shard_size));
+    let mut huggingface = args.export
+        .contains(&ExportFormat::Huggingface)
+        .then(|| writers::huggingface::HuggingFaceWriter::new(output_dir));
+    let mut cvat = args.export
+        .contains(&ExportFormat::Cvat)
+        .then(|| writers::cvat::CvatWriter::new(&output_dir.join("cvat").join("annotations.xml")));
+    let mut coco = args.export
+        .contains(&ExportFormat::Coco)
+        .then(|| writers::coco::CocoWriter::new(&output_dir.join("coco")));
+    let mut fiftyone = args.export
+        .contains(&ExportFormat::Fiftyone)
+        .then(|| writers::fiftyone::FiftyOneWriter::new(&output_dir.join("fiftyone")));
+    
+    let keypoint_count = if args.task == Task::Pose {
+        let count = pad_keypoints(&mut all_images);
+        if count == 0 {
+            anyhow::bail!("--task pose needs annotations with keypoints; none were found");
+        }
+        println!("Keypoints per object: {}", count);
+        Some(count)
+    } else {
+        None
+    };
+    
+    if args.yolo_structure {
+        // Create professional YOLO directory structure: (images, labels) directories per split
+        let split_dirs: Vec<(PathBuf, PathBuf)> = splits
+            .iter()
+            .map(|split| match args.layout {
+                Layout::Ultralytics => (output_dir.join(split).join("images"), output_dir.join(split).join("labels")),
+                Layout::FlatPerSplit => (output_dir.join(split), output_dir.join(split)),
+            })
+            .collect();
+        for (images_dir, labels_dir) in &split_dirs {
+            // Downloads still land in the output with --no-copy
+            if !args.no_copy || args.download_images {
+                fs::create_dir_all(images_dir)?;
+            }
+            fs::create_dir_all(labels_dir)?;
+        }
+        
+        let images = all_images;
+        let split_index = |idx: usize| {
+            let mut end = 0;
+            split_counts.iter().position(|count| {
+                end += count;
+                idx < end
+            }).unwrap_or(split_counts.len() - 1)
+        };
+        
+        // Fetch remote images up front so downloads can run concurrently
+        let mut downloaded = HashSet::new();
+        if args.download_images {
+            let mut jobs = Vec::new();
+            for (idx, image) in images.iter().enumerate() {
+                if let Some(url) = &image.source_url {
+                    let images_dir = &split_dirs[split_index(idx)].0;
+                    let image_filename = Path::new(&image.file_name)
+                        .file_name()
+                        .context("Invalid image filename")?;
+                    jobs.push(download::DownloadJob { index: idx, url: url.clone(), dest: images_dir.join(image_filename) });
+                }
+            }
+            
+            println!("Downloading {} images ({} concurrent)", jobs.len(), args.download_concurrency);
+            downloaded = download::download_images(&jobs, args.download_concurrency, args.download_retries)?;
+        }
+        
+        let mut copied_instead = 0;
+        
+        // Create progress bar for image processing
+        let pb_images = ProgressBar::new(images.len() as u64);
+        pb_images.set_style(
+            ProgressStyle::with_template(
+                "Processing     [{elapsed_precise}] [{bar:40.green/blue}] {pos:>7}/{len:7} {msg}"
+            )?
+            .progress_chars("#>-")
+        );
+        
+        for (idx, image) in images.iter().enumerate() {
+            let split = split_index(idx);
+            let (images_dir, labels_dir) = &split_dirs[split];
+            let split_name = splits[split];
+            
+            // Extract filename from path
+            let image_filename = Path::new(&image.file_name)
+                .file_name()
+                .context("Invalid image filename")?
+                .to_str()
+                .context("Non-UTF8 filename")?;
+            
+            pb_images.set_message(format!("{} - {} ({} ann)", split_name, image_filename, image.annotations.len()));
+            
+            // Find the actual image file; remote images were already downloaded into place
+            let image_path = if args.download_images && image.source_url.is_some() {
+                downloaded.contains(&idx).then(|| images_dir.join(image_filename))
+            } else if let Some(source_image_path) = image.source_path
+                .clone()
+                .filter(|path| input.contains(path))
+                .or_else(|| input.find_image(image_filename))
+            {
+                if args.no_copy {
+                    Some(source_image_path)
+                } else {
+                    if !input.place_image(&source_image_path, &images_dir.join(image_filename), args.image_mode)? {
+                        copied_instead += 1;
+                    }
+                    Some(images_dir.join(image_filename))
+                }
+            } else {
+                None
+            };
+            
+            if let Some(image_path) = image_path {
+                split_images[split].1.push(std::path::absolute(&image_path)?);
+                
+                // Create annotation file
+                let base_name = Path::new(image_filename)
+                    .file_stem()
+                    .unwrap()
+                    .to_str()
+                    .unwrap();
+                let annotation_path = labels_dir.join(format!("{}.txt", base_name));
+                
+                let mut yolo_annotations = Vec::new();
+                for annotation in &image.annotations {
+                    yolo_annotations.push(label_line(annotation, image, label_style));
+                    *class_counts.entry(annotation.category_id).or_default() += 1;
+                    total_annotations += 1;
+                }
+                
+                let content = if yolo_annotations.is_empty() { 
+                    String::new() 
+                } else { 
+                    yolo_annotations.join("\n") + "\n"
+                };
+                
+                fs::write(&annotation_path, &content)
+                    .with_context(|| format!("Failed to write annotation file: {}", annotation_path.display()))?;
+                
+                if args.track_ids {
+                    write_track_ids(&output_dir.join(split_name).join("tracks"), base_name, image)?;
+                }
+                if args.labels_meta {
+                    write_labels_meta(&output_dir.join(split_name).join("labels_meta"), base_name, image)?;
+                }
+                if args.export.contains(&ExportFormat::Voc) {
+                    let folder = images_dir.file_name().and_then(|s| s.to_str()).unwrap_or(split_name);
+                    writers::voc::write(&output_dir.join(split_name).join("Annotations"), base_name, image, folder)?;
+                }
+                stats.add_image(image, split_name);
+                if let Some(csv) = &mut csv {
+                    csv.add_image(image, Some(split_name))?;
+                }
+                if let Some(sqlite) = &mut sqlite {
+                    sqlite.add_image(image, Some(split_name))?;
+                }
+                if let Some(parquet) = &mut parquet {
+                    parquet.add_image(image, Some(split_name));
+                }
+                if let Some(tfrecord) = &mut tfrecord {
+                    tfrecord.add_image(image, split_name, &image_path)?;
+                }
+                if let Some(webdataset) = &mut webdataset {
+                    webdataset.add_image(image, split_name, &image_path, &content)?;
+                }
+                if let Some(huggingface) = &mut huggingface {
+                    let file_name = match args.layout {
+                        Layout::Ultralytics => format!("images/{}", image_filename),
+                        Layout::FlatPerSplit => image_filename.to_string(),
+                    };
+                    huggingface.add_image(image, split_name, &file_name)?;
+                }
+                if let Some(cvat) = &mut cvat {
+                    cvat.add_image(image, Some(split_name));
+                }
+                if let Some(coco) = &mut coco {
+                    coco.add_image(image, Some(split_name));
+                }
+                if let Some(fiftyone) = &mut fiftyone {
+                    fiftyone.add_image(image, split_name, &image_path)?;
+                }
+            } else {
+                missing_images += 1;
+            }
+            
+            pb_images.inc(1);
+        }
+        
+        pb_images.finish_with_message("Image processing complete");
+        
+        if missing_images > 0 {
+            println!("Warning: {} image files not found", missing_images);
+        }
+        report_copied_instead(copied_instead, args.image_mode);
+    } else {
+        // Legacy flat structure
+        for image in &all_images {
+            let image_name = Path::new(&image.file_name)
+                .file_stem()
+                .unwrap_or_default()
+                .to_str()
+                .unwrap_or("unknown");
+            
+            let output_file = output_dir.join(format!("{}.txt", image_name));
+            let mut yolo_annotations = Vec::new();
+
+            for annotation in &image.annotations {
+                yolo_annotations.push(label_line(annotation, image, label_style));
+                *class_counts.entry(annotation.category_id).or_default() += 1;
+                total_annotations += 1;
+            }
+
+            let content = if yolo_annotations.is_empty() { 
+                String::new() 
+            } else { 
+                yolo_annotations.join("\n") + "\n"
+            };
+            
+            fs::write(&output_file, content)
+                .with_context(|| format!("Failed to write output file: {}", output_file.display()))?;
+            
+            if args.track_ids {
+                write_track_ids(&output_dir.join("tracks"), image_name, image)?;
+            }
+            if args.labels_meta {
+                write_labels_meta(&output_dir.join("labels_meta"), image_name, image)?;
+            }
+            if args.export.contains(&ExportFormat::Voc) {
+                writers::voc::write(&output_dir.join("Annotations"), image_name, image, "images")?;
+            }
+            stats.add_image(image, "all");
+            if let Some(csv) = &mut csv {
+                csv.add_image(image, None)?;
+            }
+            if let Some(sqlite) = &mut sqlite {
+                sqlite.add_image(image, None)?;
+            }
+            if let Some(parquet) = &mut parquet {
+                parquet.add_image(image, None);
+            }
+            if let Some(cvat) = &mut cvat {
+                cvat.add_image(image, None);
+            }
+            if let Some(coco) = &mut coco {
+                coco.add_image(image, None);
+            }
+            
+            println!("  -> Generated: {} ({} annotations)", output_file.display(), image.annotations.len());
+        }
+    }
+
+    if let Some(csv) = csv {
+        csv.finish()?;
+        println!("Generated annotations CSV: {}", output_dir.join("annotations.csv").display());
+    }
+
+    if let Some(sqlite) = sqlite {
+        sqlite.finish(&class_names)?;
+        println!("Generated SQLite database: {}", output_dir.join("dataset.db").display());
+    }
+    
+    if let Some(parquet) = parquet {
+        parquet.finish()?;
+        println!("Generated Parquet file: {}", output_dir.join("annotations.parquet").display());
+    }
+    
+    if let Some(tfrecord) = tfrecord {
+        tfrecord.finish(&class_names)?;
+        println!("Generated TFRecord shards: {}", output_dir.join("tfrecord").display());
+    }
+    
+    if let Some(webdataset) = webdataset {
+        webdataset.finish()?;
+        println!("Generated WebDataset shards: {}", output_dir.join("webdataset").display());
+    }
+    
+    if let Some(huggingface) = huggingface {
+        huggingface.finish()?;
+        println!("Generated Hugging Face metadata.jsonl files");
+    }
+    
+    if let Some(cvat) = cvat {
+        cvat.finish(&class_names)?;
+        println!("Generated CVAT XML: {}", output_dir.join("cvat").join("annotations.xml").display());
+    }
+    
+    if let Some(coco) = coco {
+        coco.finish(&class_names)?;
+    }
+    
+    if let Some(fiftyone) = fiftyone {
+        fiftyone.finish(&class_names)?;
+        println!("Generated FiftyOne datasets: {}", output_dir.join("fiftyone").display());
+    }
+
+    if let Some(mapping_path) = &args.class_mapping_csv {
+        writers::csv::write_class_mapping(mapping_path, &class_ids, &class_names, &class_counts)?;
+    }
+    let id_map_path = args.category_id_map_out.clone().unwrap_or_else(|| output_dir.join("yolo_to_coco_ids.json"));
+    write_yolo_to_coco_ids(&id_map_path, &class_ids, args.class_offset)?;
+
+    // Create classes.txt file
+    if args.create_classes && !class_names.is_empty() {
+        let classes_file = output_dir.join("classes.txt");
+        let class_content: String = written_names.values().map(|name| format!("{}\n", name)).collect();
+        
+        fs::write(&classes_file, class_content)
+            .with_context(|| format!("Failed to write classes file: {}", classes_file.display()))?;
+        
+        println!("\nGenerated classes file: {}", classes_file.display());
+    }
+    
+    let class_weights = if args.class_weights {
+        let split = if args.yolo_structure { "train" } else { "all" };
+        let mut weights = inverse_frequency_weights(&class_names, &stats.instance_counts(split));
+        // The placeholder classes below --class-offset have no instances
+        weights.splice(0..0, vec![0.0; args.class_offset as usize]);
+        let weights_file = output_dir.join("class_weights.txt");
+        let content: String = weights.iter().map(|weight| format!("{}\n", weight)).collect();
+        fs::write(&weights_file, content)
+            .with_context(|| format!("Failed to write class weights: {}", weights_file.display()))?;
+        println!("Generated class weights: {}", weights_file.display());
+        Some(weights)
+    } else {
+        None
+    };
+    
+    // The legacy flat structure has no image splits to point at
+    if args.split.kfold.is_some() {
+        writers::yolo::write_fold_files(output_dir, &split_images, &written_names, keypoint_count, args.archive.is_some())?;
+    } else if args.yolo_structure {
+        if args.lists {
+            writers::yolo::write_image_lists(output_dir, &split_images)?;
+        }
+        let split_paths: Vec<(&str, String)> = splits
+            .iter()
+            .map(|split| {
+                let path = match args.layout {
+                    _ if args.lists => format!("{}.txt", split),
+                    Layout::Ultralytics => format!("{}/images", split),
+                    Layout::FlatPerSplit => split.to_string(),
+                };
+                (*split, path)
+            })
+            .collect();
+        writers::yolo::write_data_yaml(output_dir, &split_paths, written_names.clone(), keypoint_count, class_weights.as_deref(), args.archive.is_some())?;
+    }
+    
+    if args.darknet {
+        writers::darknet::write(output_dir, &split_images, &written_names.values().cloned().collect::<Vec<_>>())?;
+    }
+
+    stats.print(&class_names);
+    stats.write(&output_dir.join("stats.json"), &class_names)?;
+
+    println!("\nConversion completed!");
+    println!("Processed JSON files: {}", processed_files);
+    println!("Total images: {}", total_images);
+    println!("Total annotations: {}", total_annotations);
+    if oob_affected > 0 {
+        println!("Annotations past the image borders: {} ({} dropped)", oob_affected, oob_dropped);
+    }
+    if args.min_area.is_some() || args.max_area.is_some() {
+        println!("Dropped by area filters: {}", area_dropped);
+    }
+    if args.min_box_size.is_some() {
+        println!("Dropped by minimum box size: {}", size_dropped);
+    }
+    if args.dedup_iou.is_some() {
+        println!("Duplicate boxes removed: {}", duplicates);
+    }
+    
+    if let Some(metrics_file) = &args.metrics_file {
+        write_metrics_file(metrics_file, &[
+            ("coco2yolo_json_files_total", "Annotation JSON files parsed.", processed_files),
+            ("coco2yolo_images_total", "Images found in the annotation files.", total_images),
+            ("coco2yolo_annotations_total", "Annotations written as YOLO labels.", total_annotations),
+            ("coco2yolo_missing_images_total", "Images whose files could not be found.", missing_images),
+            (
+                "coco2yolo_filtered_annotations_total",
+                "Annotations dropped by category and class filters, out-of-bounds, area, size, deduplication, crowd or unmapped-class rules.",
+                filtered_annotations,
+            ),
+        ])?;
+        println!("Metrics written to: {}", metrics_file.display());
+    }
+    
+    if args.scaffold {
+        write_scaffold(output_dir, args.yolo_structure.then_some(args.layout))?;
+    }
+    
+    if args.check_pairing {
+        if args.yolo_structure {
+            check_pairing(output_dir, args.layout, args.strict)?;
+        } else {
+            println!("Skipping pairing check: no images are copied without --yolo-structure");
+        }
+    }
+    
+    if let Some(format) = args.archive {
+        archive::write(output_dir, format)?;
+        if args.archive_only {
+            archive::remove_output(output_dir)?;
+        }
+    }
+    
+    Ok(())
+}
+
+// JSON schema of the annotation files accepted for --print-schema
+fn input_schema(format: SchemaFormat) -> schemars::Schema {
+    match format {
+        SchemaFormat::Damm => schemars::schema_for!(formats::damm::DammDataset),
+        SchemaFormat::Coco => schemars::schema_for!(CocoDataset),
+    }
+}
+
+// Parse an existing YOLO dataset and write it out as a single standard COCO JSON
+fn convert_yolo_to_coco(args: &Yolo2CocoArgs) -> Result<()> {
+    let input = InputSource::open(&args.input)?;
+    let names = load_yolo_class_names(&input)?;
+    let parser = formats::yolo::YoloParser { names: names.clone() };
+    let mut labels = formats::LabelRegistry::default();
+    
+    let label_files = input.annotation_files(&parser, &|_| false);
+    if label_files.is_empty() {
+        anyhow::bail!("No YOLO label files found in {}", args.input.display());
+    }
+    println!("Found {} label files", label_files.len());
+    
+    let mut images = Vec::new();
+    for label_file in &label_files {
+        let content = input.read_annotation(label_file)
+            .with_context(|| format!("Failed to read file: {}", label_file.display()))?;
+        let filename = label_file.file_name().unwrap_or_default().to_string_lossy();
+        let ctx = formats::ParseContext {
+            path: label_file,
+            file_name: formats::decompressed_name(&filename),
+            input: &input,
+            read_image_size: true,
+        };
+        images.extend(formats::FormatParser::parse(&parser, &content, &ctx, &mut labels)
+            .with_context(|| format!("Failed to parse as YOLO labels: {}", label_file.display()))?);
+    }
+    
+    let class_names: BTreeMap<u32, String> = names.into_iter().enumerate().filter_map(|(id, name)| Some((id as u32, name?))).collect();
+    let dataset = writers::coco::to_dataset(&images, &class_names);
+    writers::coco::write(&args.output, &dataset)?;
+    
+    println!("\nConversion completed!");
+    println!("Total images: {}", dataset.images.len());
+    println!("Total annotations: {}", dataset.annotations.len());
+    Ok(())
+}
+
+// Warn about images a hardlink or move had to copy because the output is on another filesystem
+fn report_copied_instead(count: usize, mode: ImageMode) {
+    if count > 0 {
+        let mode = if mode == ImageMode::Hardlink { "hard linked" } else { "moved" };
+        println!("Warning: {} images were copied instead of {} because the output is on another filesystem", count, mode);
+    }
+}
+
+// Copy a flat YOLO dataset into split directories, with labels copied as they are. Images without a
+// label file get an empty one.
+fn split_yolo_dataset(args: &SplitCommandArgs) -> Result<()> {
+    args.split.check()?;
+    let input = InputSource::open(&args.input)?;
+    let names = load_yolo_class_names(&input)?;
+    
+    let mut images = Vec::new();
+    let mut label_files = HashMap::new();
+    for image_path in input.image_files() {
+        let file_name = image_path.file_name().context("Invalid image filename")?.to_string_lossy().into_owned();
+        let label_path = image_path.with_extension("txt");
+        let mut annotations = Vec::new();
+        if input.contains(&label_path) {
+            let content = input.read_annotation(&label_path)?;
+            annotations = content
+                .lines()
+                .filter_map(|line| line.split_whitespace().next()?.parse::<u32>().ok())
+                .map(|category_id| UnifiedAnnotation {
+                    category_id,
+                    category_name: Some(names.get(category_id as usize).cloned().flatten().unwrap_or_else(|| category_id.to_string())),
+                    ..Default::default()
+                })
+                .collect();
+            label_files.insert(file_name.clone(), content);
+        }
+        images.push(UnifiedImage { file_name, source_path: Some(image_path), annotations, ..Default::default() });
+    }
+    if images.is_empty() {
+        anyhow::bail!("No images found in {}", args.input.display());
+    }
+    println!("Found {} images, {} with label files", images.len(), label_files.len());
+    
+    let split_names = args.split.split_names(false);
+    let splits: Vec<&str> = split_names.iter().map(String::as_str).collect();
+    let split_counts = assign_splits(&mut images, &args.split, &splits, None)?;
+    
+    let mut split_images: Vec<(&str, Vec<PathBuf>)> = Vec::new();
+    let mut copied_instead = 0;
+    let mut remaining = images.iter();
+    for (split, &count) in splits.iter().zip(&split_counts) {
+        let images_dir = args.output.join(split).join("images");
+        let labels_dir = args.output.join(split).join("labels");
+        fs::create_dir_all(&images_dir)?;
+        fs::create_dir_all(&labels_dir)?;
+        let mut paths = Vec::new();
+        for image in remaining.by_ref().take(count) {
+            let source_path = image.source_path.as_deref().context("Image without a source path")?;
+            let image_path = images_dir.join(&image.file_name);
+            if !input.place_image(source_path, &image_path, args.image_mode)? {
+                copied_instead += 1;
+            }
+            let stem = Path::new(&image.file_name).file_stem().unwrap_or_default().to_string_lossy();
+            let label_path = labels_dir.join(format!("{}.txt", stem));
+            fs::write(&label_path, label_files.get(&image.file_name).map_or("", String::as_str))
+                .with_context(|| format!("Failed to write annotation file: {}", label_path.display()))?;
+            paths.push(std::path::absolute(&image_path)?);
+        }
+        split_images.push((split, paths));
+    }
+    report_copied_instead(copied_instead, args.image_mode);
+    
+    let summary: Vec<String> = splits.iter().zip(&split_counts).map(|(split, count)| format!("{} {}", count, split)).collect();
+    println!("Split: {} images", summary.join(", "));
+    
+    // Without a class list, classes are named by their index
+    let names = formats::yolo::slot_names(&names);
+    let mut class_names: BTreeMap<u32, String> = names.iter().enumerate().map(|(id, name)| (id as u32, name.clone())).collect();
+    for annotation in images.iter().flat_map(|image| &image.annotations) {
+        class_names.entry(annotation.category_id).or_insert_with(|| class_name(annotation));
+    }
+    if !names.is_empty() {
+        fs::write(args.output.join("classes.txt"), names.iter().map(|name| format!("{}\n", name)).collect::<String>())?;
+    }
+    if args.split.kfold.is_some() {
+        writers::yolo::write_fold_files(&args.output, &split_images, &class_names, None, false)?;
+    } else {
+        let split_paths: Vec<(&str, String)> = splits.iter().map(|split| (*split, format!("{}/images", split))).collect();
+        writers::yolo::write_data_yaml(&args.output, &split_paths, class_names, None, None, false)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    
+    match &args.command {
+        Some(Command::Yolo2coco(yolo_args)) => return convert_yolo_to_coco(yolo_args),
+        Some(Command::Split(split_args)) => return split_yolo_dataset(split_args),
+        None => {}
+    }
+
+    if let Some(schema_format) = args.print_schema {
+        println!("{}", serde_json::to_string_pretty(&input_schema(schema_format))?);
+        return Ok(());
+    }
+
+    let output = args.output.as_deref().context("--output is required")?;
+    if args.check_only {
+        return check_pairing(output, args.layout, args.strict);
+    }
+
+    let input = args.input.as_deref().context("--input is required for conversion")?;
+    if !input.exists() {
+        anyhow::bail!("Input directory does not exist: {}", input.display());
+    }
+    if let Some(image_dir) = args.image_dir.iter().find(|dir| !dir.is_dir()) {
+        anyhow::bail!("Image directory does not exist: {}", image_dir.display());
+    }
+
+    println!("Converting COCO format to YOLO format...");
+    println!("Input directory: {}", input.display());
+    println!("Output directory: {}", output.display());
+    println!();
+
+    convert_coco_to_yolo(&args)?;
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use walkdir::WalkDir;
+
+    fn annotation(category_id: u32, name: &str, bbox: [f64; 4]) -> UnifiedAnnotation {
+        UnifiedAnnotation { bbox: bbox.to_vec(), category_id, category_name: Some(name.to_string()), ..Default::default() }
+    }
+
+    fn image(file_name: &str, annotations: Vec<UnifiedAnnotation>) -> UnifiedImage {
+        UnifiedImage { file_name: file_name.to_string(), width: 64, height: 48, annotations, ..Default::default() }
+    }
+
+    // Standard COCO file with one person box per image, and placeholder files for its images
+    fn write_coco_fixture(dir: &Path, json_name: &str, file_names: &[&str]) {
+        let images: Vec<_> = file_names.iter().enumerate()
+            .map(|(i, name)| serde_json::json!({"id": i + 1, "file_name": name, "width": 64, "height": 48}))
+            .collect();
+        let annotations: Vec<_> = (1..=file_names.len())
+            .map(|id| serde_json::json!({"id": id, "image_id": id, "category_id": 1, "bbox": [4, 4, 20, 10], "area": 200, "iscrowd": 0}))
+            .collect();
+        let dataset = serde_json::json!({
+            "images": images,
+            "annotations": annotations,
+            "categories": [{"id": 1, "name": "person"}],
+        });
+        fs::write(dir.join(json_name), dataset.to_string()).unwrap();
+        for name in file_names {
+            fs::write(dir.join(name), b"image").unwrap();
+        }
+    }
+
+    fn convert(input: &Path, output: &Path, extra: &[&str]) -> Result<()> {
+        convert_format("standard", input, output, extra)
+    }
+
+    fn convert_format(format: &str, input: &Path, output: &Path, extra: &[&str]) -> Result<()> {
+        let mut argv = vec!["coco-to-yolo", "--format", format];
+        argv.extend(["--input", input.to_str().unwrap(), "--output", output.to_str().unwrap()]);
+        argv.extend(extra);
+        convert_coco_to_yolo(&Args::try_parse_from(argv)?)
+    }
+
+    // A ZIP archive holding `entries` (entry name, content)
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in entries {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut writer, content).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    // A blank grayscale PNG of the given size
+    fn png_image(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&vec![0; (width * height) as usize]).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    // Paths of the .txt files under `dir`, sorted
+    fn text_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".txt"))
+            .map(|entry| entry.path().strip_prefix(dir).unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn renumbered_categories_skip_every_id_seen() {
+        let mut unifier = CategoryUnifier::default();
+        let unnamed = UnifiedAnnotation { bbox: vec![0.0; 4], category_id: 2, ..Default::default() };
+        let mut first = vec![image("a.jpg", vec![annotation(1, "person", [0.0, 0.0, 4.0, 4.0]), unnamed])];
+        unifier.unify(&mut first, Path::new("a.json"));
+
+        // "car" can't keep id 1, nor take 2 (unnamed) or 3 (truck, later in the same file)
+        let mut second = vec![image("b.jpg", vec![
+            annotation(1, "car", [0.0, 0.0, 4.0, 4.0]),
+            annotation(3, "truck", [0.0, 0.0, 4.0, 4.0]),
+        ])];
+        unifier.unify(&mut second, Path::new("b.json"));
+        let ids: Vec<u32> = second[0].annotations.iter().map(|annotation| annotation.category_id).collect();
+        assert_eq!(ids, [4, 3]);
+        assert_eq!(unifier.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn keep_categories_regex_keeps_matching_names() {
+        let mut images = vec![image("a.jpg", vec![
+            annotation(1, "person", [0.0, 0.0, 4.0, 4.0]),
+            annotation(3, "vehicle.car", [0.0, 0.0, 4.0, 4.0]),
+            annotation(7, "vehicle.truck", [0.0, 0.0, 4.0, 4.0]),
+        ])];
+        let (kept, dropped) = filter_categories_by_regex(&mut images, &Regex::new(r"^vehicle\.").unwrap()).unwrap();
+        assert_eq!((kept, dropped), (2, 1));
+        let names: Vec<_> = images[0].annotations.iter().map(|annotation| annotation.category_name.as_deref().unwrap()).collect();
+        assert_eq!(names, ["vehicle.car", "vehicle.truck"]);
+    }
+
+    #[test]
+    fn keep_categories_regex_needs_names() {
+        let mut images = vec![image("a.jpg", vec![UnifiedAnnotation { bbox: vec![0.0; 4], category_id: 1, ..Default::default() }])];
+        assert!(filter_categories_by_regex(&mut images, &Regex::new("person").unwrap()).is_err());
+    }
+
+    #[test]
+    fn metrics_file_is_prometheus_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("coco2yolo.prom");
+        write_metrics_file(&path, &[
+            ("coco2yolo_images_total", "Images found in the annotation files.", 3),
+            ("coco2yolo_missing_images_total", "Images whose files could not be found.", 0),
+        ]).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "# HELP coco2yolo_images_total Images found in the annotation files.\n\
+             # TYPE coco2yolo_images_total counter\n\
+             coco2yolo_images_total 3\n\
+             # HELP coco2yolo_missing_images_total Images whose files could not be found.\n\
+             # TYPE coco2yolo_missing_images_total counter\n\
+             coco2yolo_missing_images_total 0\n"
+        );
+        assert!(!path.with_extension("prom.tmp").exists());
+    }
+
+    #[test]
+    fn json_list_converts_only_the_listed_files() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "listed.json", &["a.jpg", "b.jpg"]);
+        write_coco_fixture(input.path(), "stray.json", &["stray.jpg"]);
+        let manifest = input.path().join("files.txt");
+        fs::write(&manifest, "# annotation files\nlisted.json\n\n").unwrap();
+
+        convert(input.path(), output.path(), &["--json-list", manifest.to_str().unwrap(), "--train-split", "1.0"]).unwrap();
+        assert_eq!(text_files(output.path()), ["classes.txt", "train/labels/a.txt", "train/labels/b.txt"]);
+    }
+
+    #[test]
+    fn min_val_moves_images_over_from_train() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg", "c.jpg"]);
+
+        convert(input.path(), output.path(), &["--train-split", "1.0", "--min-val", "1"]).unwrap();
+        let labels = text_files(output.path());
+        assert_eq!(labels.iter().filter(|name| name.starts_with("val/labels/")).count(), 1);
+        assert_eq!(labels.iter().filter(|name| name.starts_with("train/labels/")).count(), 2);
+        assert!(convert(input.path(), output.path(), &["--train-split", "1.0", "--min-val", "3"]).is_err());
+    }
+
+    #[test]
+    fn json_list_rejects_missing_files() {
+        let input = tempfile::tempdir().unwrap();
+        let manifest = input.path().join("files.txt");
+        fs::write(&manifest, "missing.json\n").unwrap();
+        assert!(read_json_list(&manifest).is_err());
+    }
+
+    #[test]
+    fn flat_per_split_layout_puts_labels_next_to_images() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+
+        convert(input.path(), output.path(), &["--layout", "flat-per-split", "--train-split", "0.5"]).unwrap();
+        let mut splits = Vec::new();
+        for stem in ["a", "b"] {
+            let split = ["train", "val"]
+                .into_iter()
+                .find(|split| output.path().join(split).join(format!("{}.jpg", stem)).is_file())
+                .unwrap();
+            assert!(output.path().join(split).join(format!("{}.txt", stem)).is_file());
+            splits.push(split);
+        }
+        splits.sort();
+        assert_eq!(splits, ["train", "val"]);
+        assert!(!output.path().join("train").join("images").exists());
+        check_pairing(output.path(), Layout::FlatPerSplit, true).unwrap();
+    }
+
+    #[test]
+    fn no_copy_writes_labels_only_into_the_output() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg"]);
+
+        convert(input.path(), output.path(), &["--lists", "--no-copy", "--train-split", "1.0"]).unwrap();
+        assert_eq!(text_files(input.path()), Vec::<String>::new());
+        assert_eq!(text_files(output.path()), ["classes.txt", "train.txt", "train/labels/a.txt", "val.txt"]);
+        let list = fs::read_to_string(output.path().join("train.txt")).unwrap();
+        assert_eq!(list.trim(), std::path::absolute(input.path().join("a.jpg")).unwrap().to_str().unwrap());
+
+        let error = convert(input.path(), output.path(), &["--lists", "--no-copy", "--export", "huggingface"]).unwrap_err();
+        assert!(error.to_string().contains("huggingface"));
+    }
+
+    #[test]
+    fn check_pairing_finds_an_orphan_label() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+        convert(input.path(), output.path(), &[]).unwrap();
+        check_pairing(output.path(), Layout::Ultralytics, true).unwrap();
+
+        fs::write(output.path().join("train").join("labels").join("orphan.txt"), "").unwrap();
+        check_pairing(output.path(), Layout::Ultralytics, false).unwrap();
+        assert!(check_pairing(output.path(), Layout::Ultralytics, true).is_err());
+    }
+
+    #[test]
+    fn parse_only_writes_nothing() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+        let output_dir = output.path().join("dataset");
+
+        convert(input.path(), &output_dir, &["--parse-only"]).unwrap();
+        assert!(!output_dir.exists());
+    }
+
+    #[test]
+    fn names_leave_empty_slots_unmatched() {
+        let images = vec![image("a.jpg", vec![
+            annotation(1, "person", [0.0, 0.0, 4.0, 4.0]),
+            annotation(5, "class_1", [0.0, 0.0, 4.0, 4.0]),
+        ])];
+        let names = formats::yolo::parse_class_names("person\n-\nclass_1\n").unwrap();
+        let class_ids = named_class_ids(&images, &names, true).unwrap();
+        assert_eq!(class_ids, HashMap::from([(1, 0), (5, 2)]));
+    }
+
+    #[test]
+    fn names_file_fixes_class_order_by_name() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg"]);
+        let names_path = input.path().join("names.txt");
+        fs::write(&names_path, "-\nperson\n\n").unwrap();
+
+        convert(input.path(), output.path(), &["--names", names_path.to_str().unwrap(), "--train-split", "1.0"]).unwrap();
+        assert_eq!(fs::read_to_string(output.path().join("classes.txt")).unwrap(), "class_0\nperson\n");
+        let label = fs::read_to_string(output.path().join("train").join("labels").join("a.txt")).unwrap();
+        assert!(label.starts_with("1 "));
+    }
+
+    fn obb_style(obb_coords: ObbCoords) -> LabelStyle {
+        LabelStyle { task: Task::Obb, precision: 6, class_offset: 0, obb_coords }
+    }
+
+    #[test]
+    fn obb_corners_in_pixels_are_rounded_integers() {
+        let ann = annotation(2, "car", [10.4, 5.6, 30.5, 20.49]);
+        assert_eq!(obb_line(&ann, 64, 48, obb_style(ObbCoords::Pixel)), "2 10 6 31 6 31 20 10 20");
+    }
+
+    #[test]
+    fn obb_corners_are_normalized_by_default() {
+        let ann = annotation(0, "car", [16.0, 12.0, 32.0, 24.0]);
+        assert_eq!(
+            obb_line(&ann, 64, 48, obb_style(ObbCoords::Normalized)),
+            "0 0.250000 0.250000 0.500000 0.250000 0.500000 0.500000 0.250000 0.500000"
+        );
+        let args = Args::try_parse_from(["coco-to-yolo", "--task", "obb", "--input", "in", "--output", "out"]).unwrap();
+        assert_eq!(args.obb_coords, ObbCoords::Normalized);
+    }
+
+    #[test]
+    fn obb_labels_in_pixels() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg"]);
+
+        convert(input.path(), output.path(), &["--task", "obb", "--obb-coords", "pixel", "--train-split", "1.0"]).unwrap();
+        let label = fs::read_to_string(output.path().join("train").join("labels").join("a.txt")).unwrap();
+        assert_eq!(label, "0 4 4 24 4 24 14 4 14\n");
+    }
+
+    // Property names of a schema definition, or of the root with `None`
+    fn schema_properties(schema: &serde_json::Value, definition: Option<&str>) -> Vec<String> {
+        let object = match definition {
+            Some(name) => &schema["$defs"][name],
+            None => schema,
+        };
+        let mut names: Vec<String> = object["properties"].as_object().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn coco_schema_lists_the_accepted_fields() {
+        let schema = serde_json::to_value(input_schema(SchemaFormat::Coco)).unwrap();
+        assert_eq!(schema_properties(&schema, None), ["annotations", "categories", "images"]);
+        let image_fields = schema_properties(&schema, Some("CocoImageInfo"));
+        for field in ["id", "file_name", "width", "height"] {
+            assert!(image_fields.iter().any(|name| name == field), "missing image field {}", field);
+        }
+        let annotation_fields = schema_properties(&schema, Some("CocoAnnotation"));
+        for field in ["image_id", "category_id", "bbox"] {
+            assert!(annotation_fields.iter().any(|name| name == field), "missing annotation field {}", field);
+        }
+    }
+
+    #[test]
+    fn damm_schema_has_annotations() {
+        let schema = serde_json::to_value(input_schema(SchemaFormat::Damm)).unwrap();
+        assert_eq!(schema_properties(&schema, None), ["annotations"]);
+    }
+
+    #[test]
+    fn overshoot_within_the_bounds_tolerance_is_not_flagged() {
+        let mut images = vec![image("a.jpg", vec![
+            annotation(0, "person", [-0.2, 4.0, 64.2, 48.2]),
+            annotation(0, "person", [4.0, 4.0, 20.0, 10.0]),
+        ])];
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Error, 1.0).unwrap(), (0, 0));
+        // Still clipped into the image
+        assert_eq!(images[0].annotations[0].bbox, [0.0, 4.0, 64.0, 48.0]);
+    }
+
+    #[test]
+    fn overshoot_past_the_bounds_tolerance_follows_the_policy() {
+        let box_past = || vec![image("a.jpg", vec![annotation(0, "person", [50.0, 4.0, 66.0, 10.0])])];
+        assert!(apply_oob_policy(&mut box_past(), OobPolicy::Error, 1.0).is_err());
+
+        let mut images = box_past();
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Drop, 1.0).unwrap(), (1, 1));
+        assert!(images[0].annotations.is_empty());
+
+        let mut images = box_past();
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Clip, 1.0).unwrap(), (1, 0));
+        assert_eq!(images[0].annotations[0].bbox, [50.0, 4.0, 64.0, 10.0]);
+
+        // Without a tolerance even the 0.2px overshoot counts
+        let mut images = vec![image("a.jpg", vec![annotation(0, "person", [-0.2, 4.0, 20.0, 10.0])])];
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Clip, 0.0).unwrap(), (1, 0));
+    }
+
+    #[test]
+    fn scaffold_ignores_the_image_directories() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+
+        convert(input.path(), output.path(), &["--scaffold"]).unwrap();
+        let gitignore = fs::read_to_string(output.path().join(".gitignore")).unwrap();
+        for entry in ["/train/images/", "/val/images/"] {
+            assert!(gitignore.lines().any(|line| line == entry), "missing {}", entry);
+        }
+        assert!(output.path().join("configs").is_dir());
+    }
+
+    #[test]
+    fn yolo_to_coco_ids_round_trips_the_remap() {
+        let images = vec![image("a.jpg", vec![
+            annotation(90, "toothbrush", [0.0, 0.0, 4.0, 4.0]),
+            annotation(1, "person", [0.0, 0.0, 4.0, 4.0]),
+            annotation(3, "car", [0.0, 0.0, 4.0, 4.0]),
+        ])];
+        let class_ids = contiguous_class_ids(&images, &HashMap::new());
+        let inverse = yolo_to_coco_ids(&class_ids, 0).unwrap();
+        assert_eq!(inverse, BTreeMap::from([(0, 1), (1, 3), (2, 90)]));
+        for (original, index) in &class_ids {
+            assert_eq!(inverse[index], *original);
+        }
+
+        let shifted = yolo_to_coco_ids(&class_ids, 1).unwrap();
+        assert_eq!(shifted, BTreeMap::from([(1, 1), (2, 3), (3, 90)]));
+    }
+
+    #[test]
+    fn yolo_to_coco_ids_skips_the_identity_and_merges_to_the_lowest_id() {
+        assert_eq!(yolo_to_coco_ids(&HashMap::from([(0, 0), (1, 1)]), 0), None);
+        assert_eq!(yolo_to_coco_ids(&HashMap::from([(1, 0), (2, 1)]), 1), None);
+        assert_eq!(yolo_to_coco_ids(&HashMap::from([(7, 0), (3, 0), (5, 1)]), 0), Some(BTreeMap::from([(0, 3), (1, 5)])));
+    }
+
+    #[test]
+    fn category_id_map_out_chooses_the_path() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+        let map_path = output.path().join("ids.json");
+
+        convert(input.path(), output.path(), &["--category-id-map-out", map_path.to_str().unwrap()]).unwrap();
+        let written: BTreeMap<String, u32> = serde_json::from_str(&fs::read_to_string(&map_path).unwrap()).unwrap();
+        assert_eq!(written, BTreeMap::from([("0".to_string(), 1)]));
+        assert!(!output.path().join("yolo_to_coco_ids.json").exists());
+    }
+
+    // File names in split order after a shuffle seeded with `seed`
+    fn shuffled_names(mut images: Vec<UnifiedImage>, seed: u64) -> Vec<String> {
+        use rand::SeedableRng;
+        shuffle_for_split(&mut images, &mut rand::rngs::StdRng::seed_from_u64(seed));
+        images.into_iter().map(|image| image.file_name).collect()
+    }
+
+    #[test]
+    fn splits_are_identical_across_runs_and_input_orders() {
+        let images = || (0..50).map(|i| image(&format!("img{}.jpg", i), Vec::new())).collect::<Vec<_>>();
+        let mut reversed = images();
+        reversed.reverse();
+
+        let first = shuffled_names(images(), 7);
+        assert_eq!(shuffled_names(images(), 7), first);
+        assert_eq!(shuffled_names(reversed, 7), first);
+        assert_ne!(shuffled_names(images(), 8), first);
+    }
+
+    #[test]
+    fn class_mapping_csv_is_opt_in() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+        convert(input.path(), output.path(), &[]).unwrap();
+        assert!(!output.path().join("class_mapping.csv").exists());
+    }
+
+    #[test]
+    fn padded_lvis_category_names_are_trimmed() {
+        let dataset = serde_json::json!({
+            "images": [{"id": 1, "width": 64, "height": 48, "coco_url": "http://images.cocodataset.org/val2017/a.jpg"}],
+            "annotations": [{"id": 1, "image_id": 1, "category_id": 3, "bbox": [4, 4, 20, 10]}],
+            "categories": [{"id": 3, "name": "cat "}],
+        });
+        let mut labels = formats::LabelRegistry::default();
+        let policy = formats::lvis::NotExhaustivePolicy::Keep;
+        let images = formats::lvis::parse(&dataset.to_string(), policy, &mut labels).unwrap();
+        assert_eq!(images[0].annotations[0].category_name.as_deref(), Some("cat"));
+    }
+
+    #[test]
+    fn class_drift_counts_added_removed_and_reordered_names() {
+        let existing: Vec<String> = ["person", "car", "dog"].map(String::from).to_vec();
+        assert_eq!(report_class_drift(&existing, &existing), 0);
+
+        let reordered: Vec<String> = ["car", "person", "dog"].map(String::from).to_vec();
+        assert_eq!(report_class_drift(&existing, &reordered), 2);
+
+        let changed: Vec<String> = ["person", "car", "bus"].map(String::from).to_vec();
+        assert_eq!(report_class_drift(&existing, &changed), 2);
+    }
+
+    #[test]
+    fn dry_run_with_strict_drift_check_writes_nothing() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "a.json", &["a.jpg", "b.jpg"]);
+        let data_yaml = input.path().join("data.yaml");
+        fs::write(&data_yaml, "names:\n  0: person\n").unwrap();
+
+        let data_yaml = data_yaml.to_str().unwrap();
+        convert(input.path(), output.path(), &["--dry-run", "--strict", "--from-data-yaml", data_yaml]).unwrap();
+        assert!(text_files(output.path()).is_empty());
+
+        fs::write(input.path().join("data.yaml"), "names: [car]\n").unwrap();
+        assert!(convert(input.path(), output.path(), &["--dry-run", "--strict", "--from-data-yaml", data_yaml]).is_err());
+    }
+
+    #[test]
+    fn class_drift_compares_filtered_classes() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg", "c.jpg", "d.jpg"]);
+        // Add a car box to every image
+        let json_path = input.path().join("instances.json");
+        let mut dataset: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        for id in 1..=4 {
+            dataset["annotations"].as_array_mut().unwrap().push(serde_json::json!(
+                {"id": 10 + id, "image_id": id, "category_id": 2, "bbox": [30, 4, 20, 10], "area": 200, "iscrowd": 0}
+            ));
+        }
+        dataset["categories"].as_array_mut().unwrap().push(serde_json::json!({"id": 2, "name": "car"}));
+        fs::write(&json_path, dataset.to_string()).unwrap();
+        let data_yaml = input.path().join("data.yaml");
+        fs::write(&data_yaml, "names:\n  - car\n").unwrap();
+
+        let drift = ["--from-data-yaml", data_yaml.to_str().unwrap(), "--strict", "--dry-run"];
+        let error = convert(input.path(), output.path(), &drift).unwrap_err();
+        assert!(error.to_string().starts_with("Class list differs"), "{}", error);
+        let mut filtered = drift.to_vec();
+        filtered.extend(["--include-classes", "car"]);
+        convert(input.path(), output.path(), &filtered).unwrap();
+        assert!(text_files(output.path()).is_empty());
+    }
+
+    #[test]
+    fn classes_keep_their_index_when_images_are_dropped() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg", "b.jpg"]);
+        let json_path = input.path().join("instances.json");
+        let mut dataset: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        dataset["annotations"][1]["category_id"] = serde_json::json!(2);
+        dataset["categories"].as_array_mut().unwrap().push(serde_json::json!({"id": 2, "name": "car"}));
+        fs::write(&json_path, dataset.to_string()).unwrap();
+        // The only person image is missing, so only car boxes get written
+        fs::remove_file(input.path().join("a.jpg")).unwrap();
+
+        convert(input.path(), output.path(), &["--train-split", "1.0"]).unwrap();
+        assert_eq!(fs::read_to_string(output.path().join("classes.txt")).unwrap(), "person\ncar\n");
+        let label = fs::read_to_string(output.path().join("train").join("labels").join("b.txt")).unwrap();
+        assert!(label.starts_with("1 "));
+        let data_yaml = fs::read_to_string(output.path().join("data.yaml")).unwrap();
+        assert!(data_yaml.contains("nc: 2"), "{}", data_yaml);
+    }
+
+    #[test]
+    fn class_offset_names_the_slots_below_it() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        write_coco_fixture(input.path(), "instances.json", &["a.jpg"]);
+
+        convert(input.path(), output.path(), &["--class-offset", "1", "--train-split", "1.0", "--darknet"]).unwrap();
+        let label = fs::read_to_string(output.path().join("train").join("labels").join("a.txt")).unwrap();
+        assert!(label.starts_with("1 "));
+        assert_eq!(fs::read_to_string(output.path().join("classes.txt")).unwrap(), "class_0\nperson\n");
+        assert_eq!(fs::read_to_string(output.path().join("obj.names")).unwrap(), "class_0\nperson\n");
+        let data_yaml: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(output.path().join("data.yaml")).unwrap()).unwrap();
+        assert_eq!(data_yaml["nc"], serde_yaml::Value::from(2));
+        assert_eq!(data_yaml["names"][0], serde_yaml::Value::from("class_0"));
+        assert_eq!(data_yaml["names"][1], serde_yaml::Value::from("person"));
+    }
+
+    #[test]
+    fn supervisely_meta_is_read_from_a_zip_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("project.zip");
+        let meta = serde_json::json!({"classes": [{"title": "car"}, {"title": "person"}]}).to_string();
+        let box_object = |class: &str| serde_json::json!(
+            {"classTitle": class, "geometryType": "rectangle", "points": {"exterior": [[4, 4], [24, 14]]}}
+        );
+        let annotation = serde_json::json!(
+            {"size": {"height": 48, "width": 64}, "objects": [box_object("person"), box_object("car")]}
+        ).to_string();
+        write_zip(&zip_path, &[
+            ("meta.json", meta.as_bytes()),
+            ("ds/ann/a.jpg.json", annotation.as_bytes()),
+            ("ds/img/a.jpg", b"image"),
+        ]);
+
+        convert_format("supervisely", &zip_path, output.path(), &["--train-split", "1.0"]).unwrap();
+        assert_eq!(fs::read_to_string(output.path().join("classes.txt")).unwrap(), "car\nperson\n");
+    }
+
+    #[test]
+    fn open_images_class_descriptions_are_found_in_a_zip_input() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("open_images.zip");
+        let boxes = "ImageID,Source,LabelName,Confidence,XMin,XMax,YMin,YMax\na,xclick,/m/01g317,1,0.1,0.5,0.2,0.6\n";
+        write_zip(&zip_path, &[
+            ("oidv6-class-descriptions.csv", b"LabelName,DisplayName\n/m/01g317,Person\n"),
+            ("validation-annotations-bbox.csv", boxes.as_bytes()),
+            ("images/a.jpg", &png_image(64, 48)),
+        ]);
+
+        convert_format("open-images", &zip_path, output.path(), &["--read-image-size", "--train-split", "1.0"]).unwrap();
+        assert_eq!(fs::read_to_string(output.path().join("classes.txt")).unwrap(), "Person\n");
+    }
+
+    #[test]
+    fn via_reads_image_headers_with_read_image_size() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let regions = serde_json::json!({"a.jpg": {"filename": "a.jpg", "regions": [
+            {"shape_attributes": {"name": "rect", "x": 16, "y": 12, "width": 32, "height": 24}, "region_attributes": {"label": "car"}}
+        ]}});
+        fs::write(input.path().join("via_region_data.json"), regions.to_string()).unwrap();
+        fs::write(input.path().join("a.jpg"), png_image(64, 48)).unwrap();
+
+        assert!(convert_format("via", input.path(), output.path(), &["--train-split", "1.0"]).is_err());
+        convert_format("via", input.path(), output.path(), &["--read-image-size", "--train-split", "1.0"]).unwrap();
+        let label = fs::read_to_string(output.path().join("train").join("labels").join("a.txt")).unwrap();
+        assert_eq!(label, "0 0.500000 0.500000 0.500000 0.500000\n");
+    }
+
+    #[test]
+    fn min_area_rect_follows_a_rotated_outline() {
+        // A diamond with an extra point inside: the tightest rectangle is the diamond, not its
+        // 4x4 axis-aligned box
+        let corners = min_area_rect(&[2.0, 0.0, 4.0, 2.0, 2.0, 4.0, 2.0, 2.0, 0.0, 2.0]).unwrap();
+        let mut points: Vec<(i64, i64)> = corners.chunks(2).map(|point| (point[0].round() as i64, point[1].round() as i64)).collect();
+        points.sort();
+        assert_eq!(points, [(0, 2), (2, 0), (2, 4), (4, 2)]);
+        assert!(corners.iter().all(|value| (value - value.round()).abs() < 1e-9));
+
+        assert_eq!(min_area_rect(&[0.0, 0.0, 1.0, 1.0, 2.0, 2.0]), None);
+    }
+
+    #[test]
+    fn fitted_obbs_are_checked_against_the_image() {
+        // The outline lies inside the image but its tightest rectangle has a corner at (36, -2)
+        let outline = || UnifiedAnnotation {
+            segmentation: Some(vec![0.0, 10.0, 30.0, 0.0, 40.0, 10.0, 10.0, 20.0]),
+            ..annotation(0, "car", [0.0, 0.0, 40.0, 20.0])
+        };
+        let mut images = vec![image("a.jpg", vec![outline()])];
+        fit_obbs(&mut images);
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Drop, 1.0).unwrap(), (1, 1));
+
+        let mut images = vec![image("a.jpg", vec![outline()])];
+        fit_obbs(&mut images);
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Clip, 1.0).unwrap(), (1, 0));
+        let corners = images[0].annotations[0].obb.unwrap();
+        assert!(corners.chunks(2).all(|point| (0.0..=64.0).contains(&point[0]) && (0.0..=48.0).contains(&point[1])));
+    }
+
+    #[test]
+    fn clipping_moves_polygons_and_keypoints_inside_the_image() {
+        let overshooting = UnifiedAnnotation {
+            segmentation: Some(vec![-8.0, 4.0, 20.0, 4.0, 20.0, 52.0]),
+            obb: Some([-8.0, 4.0, 20.0, 4.0, 20.0, 52.0, -8.0, 52.0]),
+            keypoints: Some(vec![-2.0, 10.0, 2.0, 10.0, 10.0, 2.0]),
+            ..annotation(0, "person", [-8.0, 4.0, 20.0, 52.0])
+        };
+        let outside = annotation(0, "person", [70.0, 4.0, 90.0, 10.0]);
+        let mut images = vec![image("a.jpg", vec![overshooting, outside])];
+
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Clip, 1.0).unwrap(), (2, 1));
+        let clipped = &images[0].annotations[0];
+        assert_eq!(clipped.bbox, [0.0, 4.0, 20.0, 48.0]);
+        assert_eq!(clipped.segmentation.as_deref(), Some(&[0.0, 4.0, 20.0, 4.0, 20.0, 48.0][..]));
+        assert_eq!(clipped.obb, Some([0.0, 4.0, 20.0, 4.0, 20.0, 48.0, 0.0, 48.0]));
+        assert_eq!(clipped.keypoints.as_deref(), Some(&[0.0, 0.0, 0.0, 10.0, 10.0, 2.0][..]));
+    }
+
+    #[test]
+    fn polygons_and_keypoints_past_the_image_follow_the_policy() {
+        let inside = [4.0, 4.0, 20.0, 10.0];
+        let cases = [
+            UnifiedAnnotation { segmentation: Some(vec![4.0, 4.0, 70.0, 4.0, 20.0, 10.0]), ..annotation(0, "person", inside) },
+            UnifiedAnnotation { obb: Some([4.0, 4.0, 20.0, 4.0, 20.0, 60.0, 4.0, 60.0]), ..annotation(0, "person", inside) },
+            UnifiedAnnotation { keypoints: Some(vec![10.0, 6.0, 2.0, -9.0, 6.0, 2.0]), ..annotation(0, "person", inside) },
+        ];
+        for case in cases {
+            let mut images = vec![image("a.jpg", vec![case])];
+            assert_eq!(apply_oob_policy(&mut images, OobPolicy::Drop, 1.0).unwrap(), (1, 1));
+        }
+
+        // Unlabeled keypoints don't count
+        let unlabeled = UnifiedAnnotation { keypoints: Some(vec![-9.0, 6.0, 0.0]), ..annotation(0, "person", inside) };
+        let mut images = vec![image("a.jpg", vec![unlabeled])];
+        assert_eq!(apply_oob_policy(&mut images, OobPolicy::Error, 1.0).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn dedup_keeps_the_larger_of_overlapping_same_class_boxes() {
+        let mut images = vec![image("a.jpg", vec![
+            annotation(0, "person", [10.0, 10.0, 29.0, 30.0]),
+            annotation(0, "person", [10.0, 10.0, 30.0, 30.0]),
+            // Same place, other class
+            annotation(1, "car", [10.0, 10.0, 30.0, 30.0]),
+            // Same class, IoU 1/3
+            annotation(0, "person", [20.0, 10.0, 40.0, 30.0]),
+        ])];
+        assert_eq!(box_iou(&[10.0, 10.0, 30.0, 30.0], &[20.0, 10.0, 40.0, 30.0]), 1.0 / 3.0);
+
+        assert_eq!(dedup_boxes(&mut images, 0.5), 1);
+        let kept: Vec<_> = images[0].annotations.iter().map(|annotation| (annotation.category_id, annotation.bbox[2])).collect();
+        assert_eq!(kept, [(0, 30.0), (1, 30.0), (0, 40.0)]);
+    }
+
+    #[test]
+    fn stratify_spreads_each_class_across_the_splits() {
+        let mut images = vec![
+            image("car1.jpg", vec![annotation(1, "car", [0.0, 0.0, 10.0, 10.0])]),
+            image("car2.jpg", vec![annotation(1, "car", [0.0, 0.0, 10.0, 10.0])]),
+            image("person1.jpg", vec![annotation(0, "person", [0.0, 0.0, 10.0, 10.0])]),
+            image("person2.jpg", vec![annotation(0, "person", [0.0, 0.0, 10.0, 10.0])]),
+            image("empty.jpg", vec![]),
+            image("empty2.jpg", vec![]),
+        ];
+        assert_eq!(stratify(&mut images, &[0.5, 0.5]), [3, 3]);
+        for split in images.chunks(3) {
+            let mut classes: Vec<u32> = split.iter().flat_map(|image| image.annotations.iter().map(|a| a.category_id)).collect();
+            classes.sort();
+            assert_eq!(classes, [0, 1]);
+        }
+    }
+
+    #[test]
+    fn hash_split_depends_only_on_the_file_name() {
+        let names: Vec<String> = (0..50).map(|i| format!("img{i}.jpg")).collect();
+        let split_of = |images: &[UnifiedImage], counts: &[usize]| -> HashMap<String, usize> {
+            let mut result = HashMap::new();
+            let mut start = 0;
+            for (split, &count) in counts.iter().enumerate() {
+                for image in &images[start..start + count] {
+                    let name = Path::new(&image.file_name).file_name().unwrap().to_string_lossy().into_owned();
+                    result.insert(name, split);
+                }
+                start += count;
+            }
+            result
+        };
+
+        let mut images: Vec<UnifiedImage> = names.iter().map(|name| image(name, vec![])).collect();
+        let counts = hash_split(&mut images, &[0.8, 0.2]);
+        assert_eq!(counts.iter().sum::<usize>(), 50);
+        assert!(counts.iter().all(|&count| count > 0));
+
+        // Reordered and moved into a subdirectory, every image keeps its split
+        let mut moved: Vec<UnifiedImage> = names.iter().rev().map(|name| image(&format!("sub/{name}"), vec![])).collect();
+        let moved_counts = hash_split(&mut moved, &[0.8, 0.2]);
+        assert_eq!(moved_counts, counts);
+        assert_eq!(split_of(&moved, &moved_counts), split_of(&images, &counts));
+    }
 }
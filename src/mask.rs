@@ -0,0 +1,260 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+// COCO run-length encoded mask. `counts` alternate between background and foreground runs in
+// column-major order, either as a plain list or in the compressed string form used by pycocotools.
+#[derive(Debug, Deserialize)]
+pub struct Rle {
+    size: [usize; 2], // [height, width]
+    counts: RleCounts,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RleCounts {
+    Uncompressed(Vec<u64>),
+    Compressed(String),
+}
+
+// Binary mask in row-major order
+pub struct Mask {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Mask {
+    fn get(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+            && self.pixels[y as usize * self.width + x as usize]
+    }
+}
+
+// Decode the compressed counts string (pycocotools rleFrString): 5 bits per character with a
+// continuation bit, sign-extended, and counts after the second stored as deltas.
+fn decode_counts(encoded: &str) -> Result<Vec<u64>> {
+    let bytes = encoded.as_bytes();
+    let mut counts: Vec<i64> = Vec::new();
+    let mut p = 0;
+    while p < bytes.len() {
+        let mut value: i64 = 0;
+        let mut k = 0;
+        loop {
+            let c = bytes[p].checked_sub(48).context("Invalid character in RLE counts")? as i64;
+            value |= (c & 0x1f) << (5 * k);
+            p += 1;
+            k += 1;
+            if c & 0x20 == 0 {
+                if c & 0x10 != 0 {
+                    value |= -1 << (5 * k);
+                }
+                break;
+            }
+            if p >= bytes.len() {
+                anyhow::bail!("Truncated RLE counts");
+            }
+        }
+        if counts.len() > 2 {
+            value += counts[counts.len() - 2];
+        }
+        counts.push(value);
+    }
+    counts.into_iter().map(|count| u64::try_from(count).context("Negative RLE count")).collect()
+}
+
+pub fn decode_rle(rle: &Rle) -> Result<Mask> {
+    let [height, width] = rle.size;
+    let counts = match &rle.counts {
+        RleCounts::Uncompressed(counts) => counts.clone(),
+        RleCounts::Compressed(encoded) => decode_counts(encoded)?,
+    };
+
+    let mut pixels = vec![false; width * height];
+    let mut index = 0;
+    for (run, &count) in counts.iter().enumerate() {
+        let end = index + count as usize;
+        if end > width * height {
+            anyhow::bail!("RLE counts exceed the {}x{} mask", width, height);
+        }
+        if run % 2 == 1 {
+            for column_major in index..end {
+                let (x, y) = (column_major / height, column_major % height);
+                pixels[y * width + x] = true;
+            }
+        }
+        index = end;
+    }
+
+    Ok(Mask { width, height, pixels })
+}
+
+// Largest 4-connected foreground region of a mask
+fn largest_component(mask: &Mask) -> Option<Mask> {
+    let mut labels = vec![0usize; mask.pixels.len()];
+    let mut best: Option<(usize, usize)> = None; // (label, size)
+    let mut next_label = 1;
+
+    for start in 0..mask.pixels.len() {
+        if !mask.pixels[start] || labels[start] != 0 {
+            continue;
+        }
+        let mut size = 0;
+        let mut queue = VecDeque::from([start]);
+        labels[start] = next_label;
+        while let Some(index) = queue.pop_front() {
+            size += 1;
+            let (x, y) = ((index % mask.width) as i64, (index / mask.width) as i64);
+            for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if mask.get(nx, ny) {
+                    let neighbour = ny as usize * mask.width + nx as usize;
+                    if labels[neighbour] == 0 {
+                        labels[neighbour] = next_label;
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+        }
+        if best.is_none_or(|(_, best_size)| size > best_size) {
+            best = Some((next_label, size));
+        }
+        next_label += 1;
+    }
+
+    let (label, _) = best?;
+    let pixels = labels.iter().map(|&l| l == label).collect();
+    Some(Mask { width: mask.width, height: mask.height, pixels })
+}
+
+// Outer boundary of a single region, traced along pixel edges with the region on the right-hand
+// side. Vertices are pixel corners where the boundary turns.
+fn trace_boundary(region: &Mask) -> Option<Vec<(f64, f64)>> {
+    let start_index = region.pixels.iter().position(|&inside| inside)?;
+    let start = ((start_index % region.width) as i64, (start_index / region.width) as i64);
+
+    // Directions clockwise: right, down, left, up
+    const DIRS: [(i64, i64); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    // Pixels to the right and left of the edge leaving vertex (x, y) in each direction
+    let right_pixel = |(x, y): (i64, i64), dir: usize| match dir {
+        0 => (x, y),
+        1 => (x - 1, y),
+        2 => (x - 1, y - 1),
+        _ => (x, y - 1),
+    };
+    let left_pixel = |(x, y): (i64, i64), dir: usize| match dir {
+        0 => (x, y - 1),
+        1 => (x, y),
+        2 => (x - 1, y),
+        _ => (x - 1, y - 1),
+    };
+    let inside = |(x, y): (i64, i64)| region.get(x, y);
+
+    let mut vertices = vec![(start.0 as f64, start.1 as f64)];
+    let mut vertex = (start.0 + 1, start.1);
+    let mut dir = 0;
+    while vertex != start {
+        let next_dir = if !inside(right_pixel(vertex, dir)) {
+            (dir + 1) % 4
+        } else if inside(left_pixel(vertex, dir)) {
+            (dir + 3) % 4
+        } else {
+            dir
+        };
+        if next_dir != dir {
+            vertices.push((vertex.0 as f64, vertex.1 as f64));
+            dir = next_dir;
+        }
+        vertex = (vertex.0 + DIRS[dir].0, vertex.1 + DIRS[dir].1);
+    }
+    Some(vertices)
+}
+
+fn point_line_distance(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return ((point.0 - a.0).powi(2) + (point.1 - a.1).powi(2)).sqrt();
+    }
+    ((point.0 - a.0) * dy - (point.1 - a.1) * dx).abs() / length
+}
+
+// Douglas-Peucker simplification of an open polyline
+fn simplify(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (index, distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, &point)| (i + 1, point_line_distance(point, first, last)))
+        .fold((0, 0.0), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+
+    if distance <= tolerance {
+        return vec![first, last];
+    }
+    let mut left = simplify(&points[..=index], tolerance);
+    left.pop();
+    left.extend(simplify(&points[index..], tolerance));
+    left
+}
+
+// Polygon [x1, y1, x2, y2, ...] outlining the largest region of a mask, simplified so no boundary
+// pixel corner is further than `tolerance` pixels from it. None if nothing polygon-like remains.
+pub fn mask_to_polygon(mask: &Mask, tolerance: f64) -> Option<Vec<f64>> {
+    let boundary = trace_boundary(&largest_component(mask)?)?;
+
+    // Closing the ring makes the first split fall on the point farthest from the start
+    let mut ring = boundary.clone();
+    ring.push(boundary[0]);
+    let mut simplified = simplify(&ring, tolerance);
+    simplified.pop();
+    if simplified.len() < 3 {
+        simplified = boundary;
+    }
+
+    Some(simplified.into_iter().flat_map(|(x, y)| [x, y]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rle(counts: serde_json::Value) -> Rle {
+        serde_json::from_value(serde_json::json!({"size": [3, 4], "counts": counts})).unwrap()
+    }
+
+    // Rows of the mask as strings of '#' (foreground) and '.'
+    fn rows(mask: &Mask) -> Vec<String> {
+        mask.pixels.chunks(mask.width).map(|row| row.iter().map(|&on| if on { '#' } else { '.' }).collect()).collect()
+    }
+
+    #[test]
+    fn counts_run_down_the_columns() {
+        let mask = decode_rle(&rle(serde_json::json!([3, 6, 3]))).unwrap();
+        assert_eq!(rows(&mask), [".##.", ".##.", ".##."]);
+    }
+
+    #[test]
+    fn compressed_counts_match_the_plain_list() {
+        // pycocotools stores the third count as a delta to the first: 3 - 3 = 0
+        let mask = decode_rle(&rle(serde_json::json!("360"))).unwrap();
+        assert_eq!(rows(&mask), [".##.", ".##.", ".##."]);
+    }
+
+    #[test]
+    fn counts_past_the_mask_are_an_error() {
+        assert!(decode_rle(&rle(serde_json::json!([3, 10]))).is_err());
+    }
+
+    #[test]
+    fn polygon_outlines_the_largest_region() {
+        // A stray pixel in the corner and a 2x3 block in the last two columns
+        let mask = decode_rle(&rle(serde_json::json!([0, 1, 5, 6]))).unwrap();
+        assert_eq!(rows(&mask), ["#.##", "..##", "..##"]);
+        let polygon = mask_to_polygon(&mask, 0.5).unwrap();
+        let mut corners: Vec<(i64, i64)> = polygon.chunks(2).map(|point| (point[0] as i64, point[1] as i64)).collect();
+        corners.sort();
+        assert_eq!(corners, [(2, 0), (2, 3), (4, 0), (4, 3)]);
+    }
+}
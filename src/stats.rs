@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::UnifiedImage;
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+struct SplitCounts {
+    instances: usize,
+    images: usize,
+}
+
+#[derive(Serialize)]
+struct ClassEntry<'a> {
+    id: u32,
+    name: &'a str,
+    splits: BTreeMap<&'a str, SplitCounts>,
+}
+
+#[derive(Serialize)]
+struct StatsFile<'a> {
+    classes: Vec<ClassEntry<'a>>,
+}
+
+// Instance and image counts of each class per split, for the table printed after conversion and
+// stats.json
+pub struct ClassStats {
+    splits: Vec<(String, HashMap<u32, SplitCounts>)>, // In output order
+}
+
+impl ClassStats {
+    // Splits are listed up front so one left without images still shows up
+    pub fn new(splits: &[&str]) -> Self {
+        ClassStats { splits: splits.iter().map(|split| (split.to_string(), HashMap::new())).collect() }
+    }
+
+    pub fn add_image(&mut self, image: &UnifiedImage, split: &str) {
+        let position = match self.splits.iter().position(|(name, _)| name == split) {
+            Some(position) => position,
+            None => {
+                self.splits.push((split.to_string(), HashMap::new()));
+                self.splits.len() - 1
+            }
+        };
+        let counts = &mut self.splits[position].1;
+        let mut seen = HashSet::new();
+        for annotation in &image.annotations {
+            let class = counts.entry(annotation.category_id).or_default();
+            class.instances += 1;
+            if seen.insert(annotation.category_id) {
+                class.images += 1;
+            }
+        }
+    }
+
+    pub fn instance_counts(&self, split: &str) -> HashMap<u32, usize> {
+        self.splits
+            .iter()
+            .find(|(name, _)| name == split)
+            .map(|(_, counts)| counts.iter().map(|(id, class)| (*id, class.instances)).collect())
+            .unwrap_or_default()
+    }
+
+    fn entries<'a>(&'a self, class_names: &'a HashMap<u32, String>) -> Vec<ClassEntry<'a>> {
+        let mut ids: Vec<u32> = self.splits.iter().flat_map(|(_, counts)| counts.keys().copied()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter()
+            .map(|id| ClassEntry {
+                id,
+                name: class_names.get(&id).map(String::as_str).unwrap_or(""),
+                splits: self
+                    .splits
+                    .iter()
+                    .map(|(split, counts)| (split.as_str(), counts.get(&id).copied().unwrap_or_default()))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    // Print one row per class with instances/images for every split, then warn about classes that
+    // only appear in some of them
+    pub fn print(&self, class_names: &HashMap<u32, String>) {
+        let entries = self.entries(class_names);
+        if entries.is_empty() {
+            return;
+        }
+        let name_width = entries.iter().map(|entry| entry.name.len()).max().unwrap_or(0).max(4);
+
+        println!("\nClass statistics (instances/images):");
+        let mut header = format!("  {:>5}  {:<name_width$}", "id", "name");
+        for (split, _) in &self.splits {
+            header.push_str(&format!("  {:>13}", split));
+        }
+        println!("{}", header);
+        for entry in &entries {
+            let mut row = format!("  {:>5}  {:<name_width$}", entry.id, entry.name);
+            for (split, _) in &self.splits {
+                let counts = entry.splits[split.as_str()];
+                row.push_str(&format!("  {:>13}", format!("{}/{}", counts.instances, counts.images)));
+            }
+            println!("{}", row);
+        }
+
+        if self.splits.len() > 1 {
+            for entry in &entries {
+                let missing: Vec<&str> = self
+                    .splits
+                    .iter()
+                    .map(|(split, _)| split.as_str())
+                    .filter(|split| entry.splits[split].images == 0)
+                    .collect();
+                if !missing.is_empty() {
+                    println!("Warning: class {} ({}) has no images in {}", entry.id, entry.name, missing.join(", "));
+                }
+            }
+        }
+    }
+
+    pub fn write(&self, path: &Path, class_names: &HashMap<u32, String>) -> Result<()> {
+        let stats = StatsFile { classes: self.entries(class_names) };
+        fs::write(path, serde_json::to_string_pretty(&stats)?)
+            .with_context(|| format!("Failed to write class statistics: {}", path.display()))?;
+        println!("Generated class statistics: {}", path.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{CocoAnnotation, CocoAnnotationFields, CocoCategory, CocoDataset, CocoId, CocoImageInfo, UnifiedImage};
+
+// Standard COCO dataset built one image at a time. Images and annotations are numbered from 1 in
+// the order they are added and category ids are the class ids.
+struct CocoBuilder {
+    dataset: CocoDataset,
+    seen_categories: BTreeMap<u32, String>,
+}
+
+impl CocoBuilder {
+    fn new() -> Self {
+        CocoBuilder {
+            dataset: CocoDataset { images: Vec::new(), annotations: Vec::new(), categories: None },
+            seen_categories: BTreeMap::new(),
+        }
+    }
+
+    fn add_image(&mut self, image: &UnifiedImage) {
+        let image_id = CocoId::Int(self.dataset.images.len() as u64 + 1);
+        for annotation in &image.annotations {
+            let (x1, y1, x2, y2) = (annotation.bbox[0], annotation.bbox[1], annotation.bbox[2], annotation.bbox[3]);
+            let (width, height) = (x2 - x1, y2 - y1);
+            self.seen_categories.entry(annotation.category_id).or_insert_with(|| crate::class_name(annotation));
+
+            self.dataset.annotations.push(CocoAnnotation {
+                id: CocoId::Int(self.dataset.annotations.len() as u64 + 1),
+                image_id: image_id.clone(),
+                fields: CocoAnnotationFields {
+                    category_id: annotation.category_id,
+                    bbox: vec![x1, y1, width, height],
+                    area: width * height,
+                    iscrowd: annotation.iscrowd as u32,
+                    segmentation: annotation.segmentation.as_ref().map(|polygon| serde_json::json!([polygon])),
+                    keypoints: annotation.keypoints.clone(),
+                    extra: annotation.metadata.clone(),
+                },
+            });
+        }
+
+        self.dataset.images.push(CocoImageInfo {
+            id: image_id,
+            file_name: image.file_name.clone(),
+            height: image.height,
+            width: image.width,
+            coco_url: image.source_url.clone(),
+            video_id: None,
+            extra: image.metadata.clone(),
+        });
+    }
+
+    // Categories are `class_names`, plus classes seen in annotations but missing from it
+    fn finish(mut self, class_names: &BTreeMap<u32, String>) -> CocoDataset {
+        let mut categories = self.seen_categories;
+        categories.extend(class_names.iter().map(|(id, name)| (*id, name.clone())));
+        self.dataset.categories = Some(categories.into_iter().map(|(id, name)| CocoCategory { id, name, supercategory: None }).collect());
+        self.dataset
+    }
+}
+
+// Build a standard COCO dataset with images in file name order
+pub fn to_dataset(images: &[UnifiedImage], class_names: &BTreeMap<u32, String>) -> CocoDataset {
+    let mut sorted: Vec<&UnifiedImage> = images.iter().collect();
+    sorted.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+    let mut builder = CocoBuilder::new();
+    for image in sorted {
+        builder.add_image(image);
+    }
+    builder.finish(class_names)
+}
+
+pub fn write(path: &Path, dataset: &CocoDataset) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string(dataset)?;
+    fs::write(path, content).with_context(|| format!("Failed to write COCO JSON: {}", path.display()))?;
+    println!("Generated COCO JSON: {}", path.display());
+    Ok(())
+}
+
+// Re-export of the converted images as <dir>/<split>.json, or <dir>/annotations.json for the
+// legacy unsplit structure
+pub struct CocoWriter {
+    dir: PathBuf,
+    splits: BTreeMap<String, CocoBuilder>,
+}
+
+impl CocoWriter {
+    pub fn new(dir: &Path) -> Self {
+        CocoWriter { dir: dir.to_path_buf(), splits: BTreeMap::new() }
+    }
+
+    pub fn add_image(&mut self, image: &UnifiedImage, split: Option<&str>) {
+        self.splits
+            .entry(split.unwrap_or("annotations").to_string())
+            .or_insert_with(CocoBuilder::new)
+            .add_image(image);
+    }
+
+    pub fn finish(self, class_names: &HashMap<u32, String>) -> Result<()> {
+        let class_names: BTreeMap<u32, String> = class_names.iter().map(|(id, name)| (*id, name.clone())).collect();
+        for (split, builder) in self.splits {
+            write(&self.dir.join(format!("{}.json", split)), &builder.finish(&class_names))?;
+        }
+        Ok(())
+    }
+}
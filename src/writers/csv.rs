@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use crate::UnifiedImage;
+
+// annotations.csv with one image,width,height,class_name,xmin,ymin,xmax,ymax,split row per box (in
+// pixels), for auditing in pandas or a spreadsheet. Rows are written as images are.
+pub struct CsvWriter {
+    writer: csv::Writer<File>,
+}
+
+impl CsvWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut writer = csv::Writer::from_path(path)
+            .with_context(|| format!("Failed to create annotations CSV: {}", path.display()))?;
+        writer.write_record(["image", "width", "height", "class_name", "xmin", "ymin", "xmax", "ymax", "split"])?;
+        Ok(CsvWriter { writer })
+    }
+
+    // `split` is None for the legacy unsplit structure, which leaves the split column empty
+    pub fn add_image(&mut self, image: &UnifiedImage, split: Option<&str>) -> Result<()> {
+        for annotation in &image.annotations {
+            let mut record = vec![
+                image.file_name.clone(),
+                image.width.to_string(),
+                image.height.to_string(),
+                crate::class_name(annotation),
+            ];
+            record.extend(annotation.bbox.iter().map(f64::to_string));
+            record.push(split.unwrap_or_default().to_string());
+            self.writer.write_record(&record)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// One row per source category id, in class index order. `class_ids` maps category ids to class indices.
+pub fn write_class_mapping(
+    path: &Path,
+    class_ids: &HashMap<u32, u32>,
+    class_names: &HashMap<u32, String>,
+    class_counts: &HashMap<u32, usize>,
+) -> Result<()> {
+    let mut rows: Vec<(u32, u32)> = class_ids.iter().map(|(original, index)| (*index, *original)).collect();
+    rows.sort();
+
+    let mut writer = csv::Writer::from_path(path)
+        .with_context(|| format!("Failed to write class mapping CSV: {}", path.display()))?;
+    writer.write_record(["yolo_index", "original_id", "name", "annotation_count"])?;
+    for (index, original) in rows {
+        let name = class_names.get(&index).map(String::as_str).unwrap_or("");
+        let count = class_counts.get(&index).copied().unwrap_or(0);
+        writer.write_record([index.to_string(), original.to_string(), name.to_string(), count.to_string()])?;
+    }
+    writer.flush()?;
+    println!("Generated class mapping CSV: {}", path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UnifiedAnnotation;
+
+    #[test]
+    fn fields_with_separators_are_quoted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("annotations.csv");
+        let image = UnifiedImage {
+            file_name: "a, b.jpg".to_string(),
+            width: 64,
+            height: 48,
+            annotations: vec![UnifiedAnnotation {
+                bbox: vec![4.0, 4.5, 24.0, 14.0],
+                category_name: Some("say \"hi\"".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut writer = CsvWriter::create(&path).unwrap();
+        writer.add_image(&image, Some("train")).unwrap();
+        writer.add_image(&image, None).unwrap();
+        writer.finish().unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<&str> = content.lines().collect();
+        assert_eq!(rows, [
+            "image,width,height,class_name,xmin,ymin,xmax,ymax,split",
+            "\"a, b.jpg\",64,48,\"say \"\"hi\"\"\",4,4.5,24,14,train",
+            "\"a, b.jpg\",64,48,\"say \"\"hi\"\"\",4,4.5,24,14,",
+        ]);
+    }
+
+    #[test]
+    fn class_mapping_csv_has_a_row_per_category() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("class_mapping.csv");
+        let class_ids = HashMap::from([(1, 0), (3, 1), (8, 1)]);
+        let class_names = HashMap::from([(0, "person".to_string()), (1, "car, truck".to_string())]);
+        let class_counts = HashMap::from([(0, 4), (1, 2)]);
+        write_class_mapping(&path, &class_ids, &class_names, &class_counts).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<&str> = content.lines().collect();
+        assert_eq!(rows, [
+            "yolo_index,original_id,name,annotation_count",
+            "0,1,person,4",
+            "1,3,\"car, truck\",2",
+            "1,8,\"car, truck\",2",
+        ]);
+    }
+}
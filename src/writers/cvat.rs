@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::escape_xml;
+use crate::UnifiedImage;
+
+// CVAT for images 1.1 XML, importable into a CVAT task for re-annotation. Objects with a polygon
+// are written as <polygon>, others as <box>; each image records its split as the subset.
+pub struct CvatWriter {
+    path: PathBuf,
+    images: String,
+    image_count: usize,
+}
+
+impl CvatWriter {
+    pub fn new(path: &Path) -> Self {
+        CvatWriter { path: path.to_path_buf(), images: String::new(), image_count: 0 }
+    }
+
+    // `split` is None for the legacy unsplit structure
+    pub fn add_image(&mut self, image: &UnifiedImage, split: Option<&str>) {
+        let file_name = Path::new(&image.file_name).file_name().and_then(|s| s.to_str()).unwrap_or(&image.file_name);
+        let subset = split.map(|split| format!(" subset=\"{}\"", escape_xml(split))).unwrap_or_default();
+        self.images.push_str(&format!(
+            "  <image id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\"{}>\n",
+            self.image_count, escape_xml(file_name), image.width, image.height, subset
+        ));
+        for annotation in &image.annotations {
+            let label = escape_xml(&crate::class_name(annotation));
+            match &annotation.segmentation {
+                Some(polygon) => {
+                    let points: Vec<String> = polygon.chunks_exact(2).map(|pair| format!("{:.2},{:.2}", pair[0], pair[1])).collect();
+                    self.images.push_str(&format!(
+                        "    <polygon label=\"{}\" occluded=\"0\" source=\"manual\" points=\"{}\" z_order=\"0\"/>\n",
+                        label, points.join(";")
+                    ));
+                }
+                None => self.images.push_str(&format!(
+                    "    <box label=\"{}\" occluded=\"0\" source=\"manual\" xtl=\"{:.2}\" ytl=\"{:.2}\" xbr=\"{:.2}\" ybr=\"{:.2}\" z_order=\"0\"/>\n",
+                    label, annotation.bbox[0], annotation.bbox[1], annotation.bbox[2], annotation.bbox[3]
+                )),
+            }
+        }
+        self.images.push_str("  </image>\n");
+        self.image_count += 1;
+    }
+
+    pub fn finish(self, class_names: &HashMap<u32, String>) -> Result<()> {
+        let mut sorted: Vec<_> = class_names.iter().collect();
+        sorted.sort_by_key(|(id, _)| **id);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<annotations>\n  <version>1.1</version>\n");
+        xml.push_str(&format!("  <meta>\n    <task>\n      <size>{}</size>\n      <mode>annotation</mode>\n      <labels>\n", self.image_count));
+        for (_, name) in sorted {
+            xml.push_str(&format!(
+                "        <label>\n          <name>{}</name>\n          <type>any</type>\n          <attributes/>\n        </label>\n",
+                escape_xml(name)
+            ));
+        }
+        xml.push_str("      </labels>\n    </task>\n  </meta>\n");
+        xml.push_str(&self.images);
+        xml.push_str("</annotations>\n");
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, xml).with_context(|| format!("Failed to write CVAT XML: {}", self.path.display()))
+    }
+}
@@ -0,0 +1,35 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::yolo::write_image_lists;
+
+// Write the files original Darknet training reads: absolute image lists per split, obj.names
+// with one class per line and obj.data tying them together
+pub fn write(output_dir: &Path, split_images: &[(&str, Vec<PathBuf>)], class_names: &[String]) -> Result<()> {
+    let list_paths = write_image_lists(output_dir, split_images)?;
+    let output_dir = fs::canonicalize(output_dir)?;
+
+    let names_path = output_dir.join("obj.names");
+    fs::write(&names_path, class_names.iter().map(|name| format!("{}\n", name)).collect::<String>())
+        .with_context(|| format!("Failed to write names file: {}", names_path.display()))?;
+
+    // Darknet saves weights into the backup directory but doesn't create it
+    let backup_dir = output_dir.join("backup");
+    fs::create_dir_all(&backup_dir)?;
+
+    let data_path = output_dir.join("obj.data");
+    let data = format!(
+        "classes = {}\ntrain = {}\nvalid = {}\nnames = {}\nbackup = {}\n",
+        class_names.len(),
+        list_paths["train"].display(),
+        list_paths["val"].display(),
+        names_path.display(),
+        backup_dir.display()
+    );
+    fs::write(&data_path, data)
+        .with_context(|| format!("Failed to write data file: {}", data_path.display()))?;
+
+    println!("Generated Darknet files: {}", data_path.display());
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::UnifiedImage;
+
+// FiftyOneImageDetectionDataset per split: <dir>/<split>/data/ holds the images and labels.json maps
+// each image stem to its detections, with boxes as relative [x, y, width, height]
+pub struct FiftyOneWriter {
+    dir: PathBuf,
+    labels: BTreeMap<String, serde_json::Map<String, serde_json::Value>>, // Split → stem → detections
+}
+
+impl FiftyOneWriter {
+    pub fn new(dir: &Path) -> Self {
+        FiftyOneWriter { dir: dir.to_path_buf(), labels: BTreeMap::new() }
+    }
+
+    // `image_path` is the copied image, copied again into data/
+    pub fn add_image(&mut self, image: &UnifiedImage, split: &str, image_path: &Path) -> Result<()> {
+        let data_dir = self.dir.join(split).join("data");
+        fs::create_dir_all(&data_dir)?;
+        let file_name = image_path.file_name().context("Invalid image filename")?;
+        fs::copy(image_path, data_dir.join(file_name))
+            .with_context(|| format!("Failed to copy image: {}", image_path.display()))?;
+
+        let (w, h) = (image.width as f64, image.height as f64);
+        let detections: Vec<serde_json::Value> = image.annotations
+            .iter()
+            .map(|annotation| {
+                let (x1, y1, x2, y2) = (annotation.bbox[0], annotation.bbox[1], annotation.bbox[2], annotation.bbox[3]);
+                serde_json::json!({
+                    "label": crate::class_name(annotation),
+                    "bounding_box": [x1 / w, y1 / h, (x2 - x1) / w, (y2 - y1) / h],
+                })
+            })
+            .collect();
+
+        let stem = Path::new(file_name).file_stem().and_then(|s| s.to_str()).context("Non-UTF8 image filename")?;
+        self.labels.entry(split.to_string()).or_default().insert(stem.to_string(), detections.into());
+        Ok(())
+    }
+
+    pub fn finish(self, class_names: &HashMap<u32, String>) -> Result<()> {
+        let classes = crate::sorted_class_names(class_names);
+        for (split, labels) in self.labels {
+            let labels_path = self.dir.join(split).join("labels.json");
+            let content = serde_json::json!({ "classes": classes, "labels": labels });
+            fs::write(&labels_path, serde_json::to_string(&content)?)
+                .with_context(|| format!("Failed to write FiftyOne labels: {}", labels_path.display()))?;
+        }
+        Ok(())
+    }
+}
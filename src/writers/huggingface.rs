@@ -0,0 +1,48 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::UnifiedImage;
+
+// Hugging Face imagefolder metadata: <split>/metadata.jsonl with one record per image holding its
+// path relative to the split directory and its objects as COCO-style [x, y, width, height] boxes
+pub struct HuggingFaceWriter {
+    dir: PathBuf,
+    records: BTreeMap<String, String>, // Split → JSON lines
+}
+
+impl HuggingFaceWriter {
+    pub fn new(dir: &Path) -> Self {
+        HuggingFaceWriter { dir: dir.to_path_buf(), records: BTreeMap::new() }
+    }
+
+    pub fn add_image(&mut self, image: &UnifiedImage, split: &str, file_name: &str) -> Result<()> {
+        let (mut bboxes, mut categories) = (Vec::new(), Vec::new());
+        for annotation in &image.annotations {
+            let (x1, y1, x2, y2) = (annotation.bbox[0], annotation.bbox[1], annotation.bbox[2], annotation.bbox[3]);
+            bboxes.push([x1, y1, x2 - x1, y2 - y1]);
+            categories.push(annotation.category_id);
+        }
+        let record = serde_json::json!({
+            "file_name": file_name,
+            "width": image.width,
+            "height": image.height,
+            "objects": { "bbox": bboxes, "category": categories },
+        });
+
+        let lines = self.records.entry(split.to_string()).or_default();
+        lines.push_str(&serde_json::to_string(&record)?);
+        lines.push('\n');
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        for (split, lines) in self.records {
+            let metadata_path = self.dir.join(split).join("metadata.jsonl");
+            fs::write(&metadata_path, lines)
+                .with_context(|| format!("Failed to write metadata file: {}", metadata_path.display()))?;
+        }
+        Ok(())
+    }
+}
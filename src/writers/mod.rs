@@ -0,0 +1,19 @@
+// Files written next to the YOLO labels: other annotation formats, the class tables and the
+// dataset files that point trainers at the labels
+pub mod coco;
+pub mod csv;
+pub mod cvat;
+pub mod darknet;
+pub mod fiftyone;
+pub mod huggingface;
+pub mod parquet;
+pub mod sqlite;
+pub mod tfrecord;
+pub mod voc;
+pub mod webdataset;
+pub mod yolo;
+
+// Escape text for XML element content and attribute values
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
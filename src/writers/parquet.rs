@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use arrow_array::builder::{Float64Builder, StringBuilder, UInt32Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::UnifiedImage;
+
+// annotations.parquet with one row per box and the image's metadata repeated on each row, for
+// DuckDB/Polars. Columns are buffered and written as one snappy-compressed batch by `finish`.
+pub struct ParquetWriter {
+    path: PathBuf,
+    file_name: StringBuilder,
+    width: UInt32Builder,
+    height: UInt32Builder,
+    split: StringBuilder,
+    category_id: UInt32Builder,
+    class_name: StringBuilder,
+    xmin: Float64Builder,
+    ymin: Float64Builder,
+    xmax: Float64Builder,
+    ymax: Float64Builder,
+}
+
+impl ParquetWriter {
+    pub fn new(path: &Path) -> Self {
+        ParquetWriter {
+            path: path.to_path_buf(),
+            file_name: StringBuilder::new(),
+            width: UInt32Builder::new(),
+            height: UInt32Builder::new(),
+            split: StringBuilder::new(),
+            category_id: UInt32Builder::new(),
+            class_name: StringBuilder::new(),
+            xmin: Float64Builder::new(),
+            ymin: Float64Builder::new(),
+            xmax: Float64Builder::new(),
+            ymax: Float64Builder::new(),
+        }
+    }
+
+    // `split` is None for the legacy unsplit structure
+    pub fn add_image(&mut self, image: &UnifiedImage, split: Option<&str>) {
+        for annotation in &image.annotations {
+            self.file_name.append_value(&image.file_name);
+            self.width.append_value(image.width);
+            self.height.append_value(image.height);
+            self.split.append_option(split);
+            self.category_id.append_value(annotation.category_id);
+            self.class_name.append_value(crate::class_name(annotation));
+            self.xmin.append_value(annotation.bbox[0]);
+            self.ymin.append_value(annotation.bbox[1]);
+            self.xmax.append_value(annotation.bbox[2]);
+            self.ymax.append_value(annotation.bbox[3]);
+        }
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("image", DataType::Utf8, false),
+            Field::new("width", DataType::UInt32, false),
+            Field::new("height", DataType::UInt32, false),
+            Field::new("split", DataType::Utf8, true),
+            Field::new("category_id", DataType::UInt32, false),
+            Field::new("class_name", DataType::Utf8, false),
+            Field::new("xmin", DataType::Float64, false),
+            Field::new("ymin", DataType::Float64, false),
+            Field::new("xmax", DataType::Float64, false),
+            Field::new("ymax", DataType::Float64, false),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.file_name.finish()),
+            Arc::new(self.width.finish()),
+            Arc::new(self.height.finish()),
+            Arc::new(self.split.finish()),
+            Arc::new(self.category_id.finish()),
+            Arc::new(self.class_name.finish()),
+            Arc::new(self.xmin.finish()),
+            Arc::new(self.ymin.finish()),
+            Arc::new(self.xmax.finish()),
+            Arc::new(self.ymax.finish()),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns)?;
+
+        let file = File::create(&self.path)
+            .with_context(|| format!("Failed to create Parquet file: {}", self.path.display()))?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let mut writer = ArrowWriter::try_new(file, schema, Some(properties))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::UnifiedImage;
+
+const SCHEMA: &str = "
+CREATE TABLE categories (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+CREATE TABLE images (
+    id INTEGER PRIMARY KEY,
+    file_name TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    split TEXT
+);
+-- Categories are inserted last, once every class is known, so that reference is checked at commit
+CREATE TABLE annotations (
+    id INTEGER PRIMARY KEY,
+    image_id INTEGER NOT NULL REFERENCES images(id),
+    category_id INTEGER NOT NULL REFERENCES categories(id) DEFERRABLE INITIALLY DEFERRED,
+    xmin REAL NOT NULL,
+    ymin REAL NOT NULL,
+    xmax REAL NOT NULL,
+    ymax REAL NOT NULL,
+    width REAL NOT NULL,
+    height REAL NOT NULL,
+    area REAL NOT NULL
+);
+CREATE INDEX annotations_image_id ON annotations(image_id);
+CREATE INDEX annotations_category_id ON annotations(category_id);
+";
+
+// dataset.db mirroring the converted images, boxes (in pixels) and classes. Rows are inserted as
+// images are written, inside one transaction committed by `finish`.
+pub struct SqliteWriter {
+    connection: Connection,
+    image_count: i64,
+}
+
+impl SqliteWriter {
+    // Replaces any database left at `path` by an earlier run
+    pub fn create(path: &Path) -> Result<Self> {
+        if path.exists() {
+            fs::remove_file(path).with_context(|| format!("Failed to replace database: {}", path.display()))?;
+        }
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to create database: {}", path.display()))?;
+        connection.execute_batch(SCHEMA)?;
+        connection.execute_batch("BEGIN")?;
+        Ok(SqliteWriter { connection, image_count: 0 })
+    }
+
+    // `split` is None for the legacy unsplit structure
+    pub fn add_image(&mut self, image: &UnifiedImage, split: Option<&str>) -> Result<()> {
+        self.image_count += 1;
+        self.connection.execute(
+            "INSERT INTO images (id, file_name, width, height, split) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![self.image_count, image.file_name, image.width, image.height, split],
+        )?;
+
+        let mut insert = self.connection.prepare_cached(
+            "INSERT INTO annotations (image_id, category_id, xmin, ymin, xmax, ymax, width, height, area)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for annotation in &image.annotations {
+            let (x1, y1, x2, y2) = (annotation.bbox[0], annotation.bbox[1], annotation.bbox[2], annotation.bbox[3]);
+            let (width, height) = (x2 - x1, y2 - y1);
+            insert.execute(params![self.image_count, annotation.category_id, x1, y1, x2, y2, width, height, width * height])?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self, class_names: &HashMap<u32, String>) -> Result<()> {
+        {
+            let mut insert = self.connection.prepare("INSERT INTO categories (id, name) VALUES (?1, ?2)")?;
+            for (id, name) in class_names {
+                insert.execute(params![id, name])?;
+            }
+        }
+        self.connection.execute_batch("COMMIT")?;
+        Ok(())
+    }
+}
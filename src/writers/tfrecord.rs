@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::UnifiedImage;
+
+// Minimal protobuf encoding of tf.train.Example: Example.features (1) → Features.feature (1, map
+// entries of key 1 / value 2) → Feature.{bytes_list 1, float_list 2, int64_list 3} → value (1)
+fn varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn length_delimited(field: u64, bytes: &[u8], out: &mut Vec<u8>) {
+    varint(field << 3 | 2, out);
+    varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+enum Feature {
+    Bytes(Vec<Vec<u8>>),
+    Float(Vec<f32>),
+    Int64(Vec<i64>),
+}
+
+impl Feature {
+    fn encode(&self) -> Vec<u8> {
+        let mut list = Vec::new();
+        let field = match self {
+            Feature::Bytes(values) => {
+                for value in values {
+                    length_delimited(1, value, &mut list);
+                }
+                1
+            }
+            Feature::Float(values) => {
+                let packed: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+                length_delimited(1, &packed, &mut list);
+                2
+            }
+            Feature::Int64(values) => {
+                let mut packed = Vec::new();
+                for &value in values {
+                    varint(value as u64, &mut packed);
+                }
+                length_delimited(1, &packed, &mut list);
+                3
+            }
+        };
+        let mut feature = Vec::new();
+        length_delimited(field, &list, &mut feature);
+        feature
+    }
+}
+
+fn encode_example(features: &BTreeMap<&str, Feature>) -> Vec<u8> {
+    let mut map = Vec::new();
+    for (key, feature) in features {
+        let mut entry = Vec::new();
+        length_delimited(1, key.as_bytes(), &mut entry);
+        length_delimited(2, &feature.encode(), &mut entry);
+        length_delimited(1, &entry, &mut map);
+    }
+    let mut example = Vec::new();
+    length_delimited(1, &map, &mut example);
+    example
+}
+
+// TFRecord checksums are CRC32C, rotated and offset
+fn masked_crc(bytes: &[u8]) -> u32 {
+    let crc = crc32c::crc32c(bytes);
+    crc.rotate_right(15).wrapping_add(0xa282ead8)
+}
+
+fn write_record(writer: &mut impl Write, data: &[u8]) -> Result<()> {
+    let length = (data.len() as u64).to_le_bytes();
+    writer.write_all(&length)?;
+    writer.write_all(&masked_crc(&length).to_le_bytes())?;
+    writer.write_all(data)?;
+    writer.write_all(&masked_crc(data).to_le_bytes())?;
+    Ok(())
+}
+
+struct Shard {
+    writer: BufWriter<File>,
+    index: usize,
+    records: usize,
+}
+
+// TF Object Detection API records in <dir>/<split>-NNNNN.tfrecord shards of `shard_size` images,
+// plus the label_map.pbtxt they refer to. Labels are class id + 1, since the API reserves 0 for
+// the background.
+pub struct TfRecordWriter {
+    dir: PathBuf,
+    shard_size: usize,
+    shards: HashMap<String, Shard>,
+}
+
+impl TfRecordWriter {
+    pub fn create(dir: &Path, shard_size: usize) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(TfRecordWriter { dir: dir.to_path_buf(), shard_size: shard_size.max(1), shards: HashMap::new() })
+    }
+
+    fn open_shard(&self, split: &str, index: usize) -> Result<Shard> {
+        let path = self.dir.join(format!("{}-{:05}.tfrecord", split, index));
+        let file = File::create(&path).with_context(|| format!("Failed to create TFRecord shard: {}", path.display()))?;
+        Ok(Shard { writer: BufWriter::new(file), index, records: 0 })
+    }
+
+    // `image_path` is the copied image whose bytes are embedded
+    pub fn add_image(&mut self, image: &UnifiedImage, split: &str, image_path: &Path) -> Result<()> {
+        let encoded = fs::read(image_path).with_context(|| format!("Failed to read image: {}", image_path.display()))?;
+        let file_name = image_path.file_name().and_then(|s| s.to_str()).unwrap_or(&image.file_name);
+        let format = match image_path.extension().and_then(|s| s.to_str()).map(str::to_lowercase).as_deref() {
+            Some("jpg") | Some("jpeg") => "jpeg".to_string(),
+            Some(ext) => ext.to_string(),
+            None => String::new(),
+        };
+        let (w, h) = (image.width as f64, image.height as f64);
+        let coordinate = |index: usize, size: f64| {
+            Feature::Float(image.annotations.iter().map(|annotation| (annotation.bbox[index] / size) as f32).collect())
+        };
+
+        let features = BTreeMap::from([
+            ("image/height", Feature::Int64(vec![image.height as i64])),
+            ("image/width", Feature::Int64(vec![image.width as i64])),
+            ("image/filename", Feature::Bytes(vec![file_name.as_bytes().to_vec()])),
+            ("image/source_id", Feature::Bytes(vec![file_name.as_bytes().to_vec()])),
+            ("image/encoded", Feature::Bytes(vec![encoded])),
+            ("image/format", Feature::Bytes(vec![format.into_bytes()])),
+            ("image/object/bbox/xmin", coordinate(0, w)),
+            ("image/object/bbox/ymin", coordinate(1, h)),
+            ("image/object/bbox/xmax", coordinate(2, w)),
+            ("image/object/bbox/ymax", coordinate(3, h)),
+            ("image/object/class/text", Feature::Bytes(
+                image.annotations.iter().map(|annotation| crate::class_name(annotation).into_bytes()).collect(),
+            )),
+            ("image/object/class/label", Feature::Int64(
+                image.annotations.iter().map(|annotation| annotation.category_id as i64 + 1).collect(),
+            )),
+        ]);
+
+        let full = self.shards.get(split).is_some_and(|shard| shard.records >= self.shard_size);
+        if full || !self.shards.contains_key(split) {
+            let index = self.shards.get(split).map_or(0, |shard| shard.index + 1);
+            let shard = self.open_shard(split, index)?;
+            if let Some(mut previous) = self.shards.insert(split.to_string(), shard) {
+                previous.writer.flush()?;
+            }
+        }
+        let shard = self.shards.get_mut(split).context("TFRecord shard not open")?;
+        write_record(&mut shard.writer, &encode_example(&features))?;
+        shard.records += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self, class_names: &HashMap<u32, String>) -> Result<()> {
+        for shard in self.shards.values_mut() {
+            shard.writer.flush()?;
+        }
+
+        let mut sorted: Vec<_> = class_names.iter().collect();
+        sorted.sort_by_key(|(id, _)| **id);
+        let mut label_map = String::new();
+        for (id, name) in sorted {
+            label_map.push_str(&format!("item {{\n  id: {}\n  name: {:?}\n}}\n", id + 1, name));
+        }
+        let label_map_path = self.dir.join("label_map.pbtxt");
+        fs::write(&label_map_path, label_map)
+            .with_context(|| format!("Failed to write label map: {}", label_map_path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_framed_with_masked_crc32c() {
+        // CRC32C check value of "123456789" is 0xe3069283
+        assert_eq!(masked_crc(b"123456789"), 0xe3069283u32.rotate_right(15).wrapping_add(0xa282ead8));
+        assert_eq!(masked_crc(b""), 0xa282ead8);
+
+        let mut record = Vec::new();
+        write_record(&mut record, b"hello").unwrap();
+        assert_eq!(record.len(), 8 + 4 + 5 + 4);
+        assert_eq!(record[..8], 5u64.to_le_bytes());
+        assert_eq!(record[8..12], masked_crc(&5u64.to_le_bytes()).to_le_bytes());
+        assert_eq!(&record[12..17], b"hello");
+        assert_eq!(record[17..], masked_crc(b"hello").to_le_bytes());
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use super::escape_xml;
+use crate::UnifiedImage;
+
+// Write <base_name>.xml into `dir` in Pascal VOC layout. Box corners are rounded to whole pixels
+// as VOC tools expect; `folder` names the directory holding the image.
+pub fn write(dir: &Path, base_name: &str, image: &UnifiedImage, folder: &str) -> Result<()> {
+    let file_name = Path::new(&image.file_name).file_name().and_then(|s| s.to_str()).unwrap_or(&image.file_name);
+    let mut xml = String::from("<annotation>\n");
+    xml.push_str(&format!("  <folder>{}</folder>\n", escape_xml(folder)));
+    xml.push_str(&format!("  <filename>{}</filename>\n", escape_xml(file_name)));
+    xml.push_str(&format!(
+        "  <size>\n    <width>{}</width>\n    <height>{}</height>\n    <depth>3</depth>\n  </size>\n",
+        image.width, image.height
+    ));
+    xml.push_str("  <segmented>0</segmented>\n");
+    for annotation in &image.annotations {
+        let [xmin, ymin, xmax, ymax] = [0, 1, 2, 3].map(|i| annotation.bbox[i].round() as i64);
+        xml.push_str("  <object>\n");
+        xml.push_str(&format!("    <name>{}</name>\n", escape_xml(&crate::class_name(annotation))));
+        xml.push_str("    <pose>Unspecified</pose>\n    <truncated>0</truncated>\n    <difficult>0</difficult>\n");
+        xml.push_str(&format!(
+            "    <bndbox>\n      <xmin>{}</xmin>\n      <ymin>{}</ymin>\n      <xmax>{}</xmax>\n      <ymax>{}</ymax>\n    </bndbox>\n",
+            xmin, ymin, xmax, ymax
+        ));
+        xml.push_str("  </object>\n");
+    }
+    xml.push_str("</annotation>\n");
+
+    fs::create_dir_all(dir)?;
+    let xml_path = dir.join(format!("{}.xml", base_name));
+    fs::write(&xml_path, xml).with_context(|| format!("Failed to write VOC annotation: {}", xml_path.display()))
+}
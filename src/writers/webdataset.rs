@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use crate::UnifiedImage;
+
+struct Shard {
+    builder: tar::Builder<File>,
+    index: usize,
+    samples: usize,
+}
+
+// WebDataset shards in <dir>/<split>/shard-NNNNNN.tar, each sample an image plus its YOLO label
+// under a shared key. Dots in the key would split the sample, so they are replaced.
+pub struct WebDatasetWriter {
+    dir: PathBuf,
+    shard_size: usize,
+    shards: HashMap<String, Shard>,
+}
+
+fn append(builder: &mut tar::Builder<File>, name: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+impl WebDatasetWriter {
+    pub fn new(dir: &Path, shard_size: usize) -> Self {
+        WebDatasetWriter { dir: dir.to_path_buf(), shard_size: shard_size.max(1), shards: HashMap::new() }
+    }
+
+    fn open_shard(&self, split: &str, index: usize) -> Result<Shard> {
+        let split_dir = self.dir.join(split);
+        fs::create_dir_all(&split_dir)?;
+        let path = split_dir.join(format!("shard-{:06}.tar", index));
+        let file = File::create(&path).with_context(|| format!("Failed to create shard: {}", path.display()))?;
+        Ok(Shard { builder: tar::Builder::new(file), index, samples: 0 })
+    }
+
+    // `image_path` is the copied image; `labels` is the content of its label file
+    pub fn add_image(&mut self, image: &UnifiedImage, split: &str, image_path: &Path, labels: &str) -> Result<()> {
+        let data = fs::read(image_path).with_context(|| format!("Failed to read image: {}", image_path.display()))?;
+        let stem = Path::new(&image.file_name).file_stem().and_then(|s| s.to_str()).context("Invalid image filename")?;
+        let extension = image_path.extension().and_then(|s| s.to_str()).unwrap_or("jpg").to_lowercase();
+        let key = stem.replace('.', "_");
+
+        let full = self.shards.get(split).is_some_and(|shard| shard.samples >= self.shard_size);
+        if full || !self.shards.contains_key(split) {
+            let index = self.shards.get(split).map_or(0, |shard| shard.index + 1);
+            let shard = self.open_shard(split, index)?;
+            if let Some(previous) = self.shards.insert(split.to_string(), shard) {
+                previous.builder.into_inner()?;
+            }
+        }
+        let shard = self.shards.get_mut(split).context("WebDataset shard not open")?;
+        append(&mut shard.builder, &format!("{}.{}", key, extension), &data)?;
+        append(&mut shard.builder, &format!("{}.txt", key), labels.as_bytes())?;
+        shard.samples += 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        for shard in self.shards.into_values() {
+            shard.builder.into_inner()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// data.yaml written next to a converted dataset, ready for `yolo train data=...`
+#[derive(Debug, Serialize)]
+struct DataYamlOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>, // Without it Ultralytics resolves the splits against data.yaml's directory
+    train: String,
+    val: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    test: Option<String>,
+    nc: usize,
+    names: BTreeMap<u32, String>, // Keyed by class id, as written in the label files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kpt_shape: Option<[usize; 2]>, // Only for pose labels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    class_weights: Option<Vec<f64>>, // With --class-weights, in class order
+}
+
+// Write data.yaml pointing Ultralytics at the splits, given as the image directory or image list of
+// each split relative to `output_dir`; pose datasets also get their keypoint shape. `names` are
+// keyed by the class ids written in the label files. `portable` leaves out the absolute path for
+// datasets that will be moved, such as archives.
+pub fn write_data_yaml(
+    output_dir: &Path,
+    split_paths: &[(&str, String)],
+    names: BTreeMap<u32, String>,
+    keypoint_count: Option<usize>,
+    class_weights: Option<&[f64]>,
+    portable: bool,
+) -> Result<()> {
+    let split_path = |split: &str| split_paths.iter().find(|(name, _)| *name == split).map(|(_, path)| path.clone());
+    let data = DataYamlOutput {
+        path: if portable { None } else { Some(fs::canonicalize(output_dir)?) },
+        train: split_path("train").unwrap_or_default(),
+        val: split_path("val").unwrap_or_default(),
+        test: split_path("test"),
+        nc: names.len(),
+        names,
+        kpt_shape: keypoint_count.map(|count| [count, 3]),
+        class_weights: class_weights.map(|weights| weights.to_vec()),
+    };
+
+    let data_yaml_path = output_dir.join("data.yaml");
+    fs::write(&data_yaml_path, serde_yaml::to_string(&data)?)
+        .with_context(|| format!("Failed to write data.yaml: {}", data_yaml_path.display()))?;
+    println!("Generated data.yaml: {}", data_yaml_path.display());
+
+    Ok(())
+}
+
+// Write <split>.txt with the absolute path of every image in the split, one per line. Returns the
+// list file of each split.
+pub fn write_image_lists<'a>(output_dir: &Path, split_images: &[(&'a str, Vec<PathBuf>)]) -> Result<HashMap<&'a str, PathBuf>> {
+    let output_dir = fs::canonicalize(output_dir)?;
+    let mut list_paths = HashMap::new();
+    for (split, images) in split_images {
+        let list_path = output_dir.join(format!("{}.txt", split));
+        let content: String = images.iter().map(|path| format!("{}\n", path.display())).collect();
+        fs::write(&list_path, content)
+            .with_context(|| format!("Failed to write image list: {}", list_path.display()))?;
+        list_paths.insert(*split, list_path);
+    }
+    Ok(list_paths)
+}
+
+// Write train.txt/val.txt and data.yaml into each --kfold fold directory, validating on that fold
+// and training on all the others
+pub fn write_fold_files(
+    output_dir: &Path,
+    split_images: &[(&str, Vec<PathBuf>)],
+    names: &BTreeMap<u32, String>,
+    keypoint_count: Option<usize>,
+    portable: bool,
+) -> Result<()> {
+    for (fold, (fold_name, val_images)) in split_images.iter().enumerate() {
+        let train_images: Vec<PathBuf> = split_images
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != fold)
+            .flat_map(|(_, (_, images))| images.iter().cloned())
+            .collect();
+        let fold_dir = output_dir.join(fold_name);
+        write_image_lists(&fold_dir, &[("train", train_images), ("val", val_images.clone())])?;
+        let split_paths = [("train", "train.txt".to_string()), ("val", "val.txt".to_string())];
+        write_data_yaml(&fold_dir, &split_paths, names.clone(), keypoint_count, None, portable)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portable_data_yaml_leaves_out_the_dataset_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let split_paths = [("train", "train/images".to_string()), ("val", "val/images".to_string())];
+        let names = BTreeMap::from([(0, "person".to_string()), (1, "car".to_string())]);
+        write_data_yaml(dir.path(), &split_paths, names, Some(17), None, true).unwrap();
+
+        let content = fs::read_to_string(dir.path().join("data.yaml")).unwrap();
+        assert_eq!(content, "train: train/images\nval: val/images\nnc: 2\nnames:\n  0: person\n  1: car\nkpt_shape:\n- 17\n- 3\n");
+    }
+}